@@ -1,47 +1,82 @@
+use std::any::type_name;
+use std::collections::HashSet;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_json, to_json_binary, wasm_execute, Addr, Binary, CosmosMsg, Deps, DepsMut, Empty, Env,
-    IbcEndpoint, IbcQuery, MessageInfo, Order, PortIdResponse, Response, StdError, StdResult,
-    Storage, Timestamp, Uint128,
+    attr, from_json, to_json_binary, to_json_string, wasm_execute, Addr, Attribute, Binary,
+    BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, Event, IbcEndpoint, IbcQuery, MessageInfo,
+    Order, PortIdResponse, Response, StdError, StdResult, Storage, SubMsg, Timestamp, Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw20_ics20_msg::converter::ConverterController;
-use cw20_ics20_msg::helper::parse_ibc_wasm_port_id;
-use cw_controllers::AdminError;
+use cw20_ics20_msg::helper::{parse_asset_info_denom, parse_ibc_wasm_port_id};
 use cw_storage_plus::Bound;
 use oraiswap::asset::AssetInfo;
 use oraiswap::router::RouterController;
 
 use crate::error::ContractError;
-use crate::ibc::{build_ibc_send_packet, parse_voucher_denom, process_deduct_fee};
+use crate::ibc::{
+    build_ibc_send_packet, check_gas_limit, convert_remote_denom_to_evm_prefix,
+    parse_ibc_info_without_sanity_checks, parse_voucher_denom, process_deduct_fee,
+    SEND_PACKET_CALLBACK_ID,
+};
 use crate::ibc_hooks::ibc_hooks_receive;
+use crate::migrations;
 use crate::msg::{
-    AllowedResponse, ChannelResponse, ChannelWithKeyResponse, ConfigResponse, ExecuteMsg, InitMsg,
-    ListAllowedResponse, ListChannelsResponse, ListMappingResponse, MigrateMsg, PairQuery,
-    PortResponse, QueryMsg, RegisterDenomMsg, RelayerFeeResponse, SudoMsg,
+    AllowedResponse, ChannelBalanceChange, ChannelBalanceHistoryResponse, ChannelResponse,
+    ChannelStats, ChannelWithKeyResponse, ConfigResponse, ContractInfoResponse, DustResponse,
+    ExecuteMsg, InitMsg, ListAllowedResponse, ListAuditLogResponse, ListChannelsResponse,
+    ListMappingDetailResponse, ListMappingResponse, ListReconcileResponse, ListRolesResponse,
+    MigrateMsg, OwnershipResponse, PairMappingDetail, PairQuery, PortResponse, QueryMsg,
+    ReconcileResponse, RegisterDenomMsg, RelayerFeeResponse, RoleGrant, RolesResponse, SudoMsg,
+    TimelockOperationsResponse, UpdateValue,
+};
+use crate::query_helper::{
+    convert_outstanding_to_local, get_local_obligations_of_asset, get_local_outstanding_of_asset,
+    get_mappings_from_asset_info, query_asset_holdings,
 };
-use crate::query_helper::get_mappings_from_asset_info;
 use crate::state::{
-    get_key_ics20_ibc_denom, ics20_denoms, increase_channel_balance, override_channel_balance,
-    reduce_channel_balance, Config, RefundInfo, ADMIN, ALLOW_LIST, CHANNEL_INFO,
-    CHANNEL_REVERSE_STATE, CONFIG, REFUND_INFO, REFUND_INFO_LIST, RELAYER_FEE, REPLY_ARGS,
-    SINGLE_STEP_REPLY_ARGS, TOKEN_FEE,
+    accumulate_dust, add_pending_packet, append_audit_entry, assert_role, assert_transfer_amount,
+    audit_log, get_key_ics20_ibc_denom, has_role, ics20_denoms, increase_channel_balance,
+    increase_minted_supply, override_channel_balance, reduce_channel_balance, reduce_minted_supply,
+    remove_mapping_pair, rescale_channel_balance, save_mapping_pair, schedule_timelock,
+    AuditAction, ChannelState, Config, DustBalance, RefundInfo, Role, TimelockAction,
+    TimelockOperation, ADMIN, ALLOW_LIST, CHANNEL_COUNT, CHANNEL_HISTORY_START, CHANNEL_INFO,
+    CHANNEL_REVERSE_STATE, CONFIG, DEFAULT_TIMELOCK_DELAY, DUST, HOOK_CONTRACT_ALLOW_LIST,
+    MAPPING_COUNT, MINTED_SUPPLY, PENDING_OWNER, PENDING_PACKETS, REFUND_INFO, REFUND_INFO_LIST,
+    RELAYER_FEE, REPLY_ARGS, ROLES, SEND_PACKET_CALLBACK, SINGLE_STEP_REPLY_ARGS, TIMELOCK_DELAY,
+    TIMELOCK_OPERATIONS, TOKEN_FEE,
+};
+use cw20_ics20_msg::amount::{
+    convert_local_to_remote_exact, convert_remote_to_local, convert_remote_to_local_exact,
+    split_decimals, Amount,
 };
-use cw20_ics20_msg::amount::{convert_local_to_remote, convert_remote_to_local, Amount};
 use cw20_ics20_msg::msg::{AllowedInfo, DeletePairMsg, TransferBackMsg, UpdatePairMsg};
-use cw20_ics20_msg::state::{AllowInfo, MappingMetadata, RelayerFee, ReplyArgs, TokenFee};
+use cw20_ics20_msg::state::{
+    AllowInfo, DustPolicy, MappingMetadata, MappingStatus, RelayerFee, ReplyArgs, TokenFee,
+};
 use cw_utils::{maybe_addr, nonpayable, one_coin};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-ics20";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// capabilities reported by the contract info query, so clients can detect what a deployment supports
+const FEATURES: &[&str] = &[
+    "mint_burn",
+    "ibc_hooks",
+    "json_memo",
+    "lifecycle_callbacks",
+    "roles",
+    "timelock",
+    "cw20_allow_list",
+];
 
 #[entry_point]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InitMsg,
 ) -> Result<Response, ContractError> {
@@ -63,6 +98,7 @@ pub fn instantiate(
 
     REFUND_INFO.save(deps.storage, &None)?;
     REFUND_INFO_LIST.save(deps.storage, &vec![])?;
+    CHANNEL_HISTORY_START.save(deps.storage, &env.block.height)?;
 
     // add all allows
     for allowed in msg.allowlist {
@@ -95,7 +131,12 @@ pub fn execute(
         }
         ExecuteMsg::UpdateMappingPair(msg) => execute_update_mapping_pair(deps, env, info, msg),
         ExecuteMsg::DeleteMappingPair(msg) => execute_delete_mapping_pair(deps, env, info, msg),
-        // ExecuteMsg::Allow(allow) => execute_allow(deps, env, info, allow),
+        ExecuteMsg::BatchUpdateMappingPairs { pairs } => {
+            execute_batch_update_mapping_pairs(deps, env, info, pairs)
+        }
+        ExecuteMsg::BatchDeleteMappingPairs { pairs, force } => {
+            execute_batch_delete_mapping_pairs(deps, env, info, pairs, force)
+        }
         ExecuteMsg::UpdateConfig {
             default_timeout,
             default_gas_limit,
@@ -108,8 +149,10 @@ pub fn execute(
             converter_contract,
             osor_entrypoint_contract,
             token_factory_addr,
+            reason,
         } => update_config(
             deps,
+            env,
             info,
             default_timeout,
             default_gas_limit,
@@ -122,6 +165,7 @@ pub fn execute(
             converter_contract,
             osor_entrypoint_contract,
             token_factory_addr,
+            reason,
         ),
         // self-called msgs for ibc_packet_receive
         ExecuteMsg::IncreaseChannelBalanceIbcReceive {
@@ -133,6 +177,7 @@ pub fn execute(
             deps,
             info.sender,
             env.contract.address,
+            &env.block,
             dest_channel_id,
             ibc_denom,
             amount,
@@ -147,6 +192,7 @@ pub fn execute(
             deps.storage,
             info.sender,
             env.contract.address,
+            &env.block,
             src_channel_id,
             ibc_denom,
             amount,
@@ -157,13 +203,16 @@ pub fn execute(
             ibc_denom,
             outstanding,
             total_sent,
+            reason,
         } => handle_override_channel_balance(
             deps,
+            env,
             info,
             channel_id,
             ibc_denom,
             outstanding,
             total_sent,
+            reason,
         ),
         ExecuteMsg::IbcHooksReceive {
             func,
@@ -171,8 +220,69 @@ pub fn execute(
             args,
         } => ibc_hooks_receive(deps, env, info, func, orai_receiver, args),
         ExecuteMsg::RegisterDenom(msg) => register_denom(deps, env, info, msg),
-        ExecuteMsg::WithdrawAsset { coin, receiver } => {
-            execute_withdraw_asset(deps, info, coin, receiver)
+        ExecuteMsg::WithdrawAsset {
+            coin,
+            receiver,
+            emergency,
+            reason,
+        } => execute_withdraw_asset(deps, env, info, coin, receiver, emergency, reason),
+        ExecuteMsg::SweepDust {
+            ibc_denom,
+            receiver,
+            reason,
+        } => execute_sweep_dust(deps, env, info, ibc_denom, receiver, reason),
+        ExecuteMsg::Allow {
+            contract,
+            gas_limit,
+            reason,
+        } => execute_allow(deps, env, info, contract, gas_limit, reason),
+        ExecuteMsg::Disallow { contract, reason } => {
+            execute_disallow(deps, env, info, contract, reason)
+        }
+        ExecuteMsg::UpdateHookContract {
+            contract,
+            allowed,
+            reason,
+        } => execute_update_hook_contract(deps, env, info, contract, allowed, reason),
+        ExecuteMsg::TransferOwnership { new_owner, reason } => {
+            execute_transfer_ownership(deps, env, info, new_owner, reason)
+        }
+        ExecuteMsg::AcceptOwnership { reason } => execute_accept_ownership(deps, env, info, reason),
+        ExecuteMsg::GrantRole {
+            role,
+            address,
+            reason,
+        } => execute_update_role(deps, env, info, role, address, true, reason),
+        ExecuteMsg::RevokeRole {
+            role,
+            address,
+            reason,
+        } => execute_update_role(deps, env, info, role, address, false, reason),
+        ExecuteMsg::UpdateFees {
+            token_fee,
+            relayer_fee,
+            fee_receiver,
+            relayer_fee_receiver,
+            reason,
+        } => execute_update_fees(
+            deps,
+            env,
+            info,
+            token_fee,
+            relayer_fee,
+            fee_receiver,
+            relayer_fee_receiver,
+            reason,
+        ),
+        ExecuteMsg::PauseMappingPair { ibc_denom, reason } => {
+            execute_pause_mapping_pair(deps, env, info, ibc_denom, reason)
+        }
+        ExecuteMsg::UpdateTimelockDelay { delay, reason } => {
+            execute_update_timelock_delay(deps, env, info, delay, reason)
+        }
+        ExecuteMsg::ExecuteTimelock { id } => execute_timelock(deps, env, id),
+        ExecuteMsg::CancelTimelock { id, reason } => {
+            execute_cancel_timelock(deps, env, info, id, reason)
         }
         ExecuteMsg::ClockEndBlock { hash } => handle_clock_end_block_sudo(deps, hash),
     }
@@ -188,32 +298,385 @@ pub fn is_caller_contract(caller: Addr, contract_addr: Addr) -> StdResult<()> {
 }
 
 // withdraw stuck coin and transfer back to user
-// only owner can execute
+// only refund operators can execute
+#[allow(clippy::too_many_arguments)]
 fn execute_withdraw_asset(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     coin: Amount,
     receiver: Option<Addr>,
+    emergency: bool,
+    reason: String,
 ) -> Result<Response, ContractError> {
-    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
-    let receiver = receiver.unwrap_or(info.sender);
+    assert_role(deps.as_ref(), &info.sender, Role::RefundOperator)?;
+    let receiver = receiver.unwrap_or_else(|| info.sender.clone());
+
+    // emergency withdrawals may take funds owed to users, so only the owner can queue them behind the timelock
+    if emergency {
+        ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+        let operation = schedule_timelock(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            TimelockAction::WithdrawAsset { coin, receiver },
+            reason,
+        )?;
+        return Ok(Response::new()
+            .add_attribute("action", "withdraw_asset")
+            .add_attributes(timelock_attributes(&operation)));
+    }
 
-    let msg = coin.send_amount(receiver.to_string(), None);
+    // funds backing the outstanding balances and the pending refunds stay in the contract
+    let asset_info = coin.into_asset_info(deps.api)?;
+    let holdings = query_asset_holdings(&deps.querier, &env.contract.address, &asset_info, false)?;
+    let surplus =
+        holdings.saturating_sub(get_local_obligations_of_asset(deps.storage, &asset_info)?);
+    if coin.amount() > surplus {
+        return Err(ContractError::WithdrawExceedsSurplus {
+            denom: coin.denom(),
+            amount: coin.amount(),
+            surplus,
+        });
+    }
+    let msg = apply_withdraw_asset(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        coin,
+        receiver,
+        reason,
+    )?;
 
     Ok(Response::new()
         .add_attributes(vec![("action", "withdraw_asset")])
         .add_message(msg))
 }
 
+// only owner can execute
+fn execute_update_hook_contract(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    allowed: bool,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let contract = deps.api.addr_validate(&contract)?;
+    let before = HOOK_CONTRACT_ALLOW_LIST.has(deps.storage, &contract);
+    if allowed {
+        HOOK_CONTRACT_ALLOW_LIST.save(deps.storage, &contract, &Empty {})?;
+    } else {
+        HOOK_CONTRACT_ALLOW_LIST.remove(deps.storage, &contract);
+    }
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::UpdateHookContract,
+        contract.to_string(),
+        reason,
+        Some(before.to_string()),
+        Some(allowed.to_string()),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_hook_contract"),
+        ("contract", contract.as_str()),
+        ("allowed", &allowed.to_string()),
+    ]))
+}
+
+// proposes a new owner, only owner can execute
+fn execute_transfer_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    update_pending_owner(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        Some(new_owner.clone()),
+        reason,
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "transfer_ownership"),
+        ("pending_owner", new_owner.as_str()),
+    ]))
+}
+
+// sets or cancels the pending owner, every change is audited since the pending owner can take over the contract
+fn update_pending_owner(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    pending_owner: Option<Addr>,
+    reason: String,
+) -> Result<(), ContractError> {
+    let before = PENDING_OWNER.may_load(storage)?;
+    match &pending_owner {
+        Some(pending_owner) => PENDING_OWNER.save(storage, pending_owner)?,
+        None => PENDING_OWNER.remove(storage),
+    }
+    append_audit_entry(
+        storage,
+        block,
+        sender,
+        AuditAction::TransferOwnership,
+        "pending_owner".to_string(),
+        reason,
+        before.map(|owner| owner.to_string()),
+        pending_owner.map(|owner| owner.to_string()),
+    )?;
+    Ok(())
+}
+
+fn execute_accept_ownership(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if info.sender != pending_owner {
+        return Err(ContractError::NotPendingOwner {});
+    }
+    let before = ADMIN.get(deps.as_ref())?;
+    ADMIN.set(deps.branch(), Some(pending_owner.clone()))?;
+    PENDING_OWNER.remove(deps.storage);
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::TransferOwnership,
+        "owner".to_string(),
+        reason,
+        before.map(|owner| owner.to_string()),
+        Some(pending_owner.to_string()),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "accept_ownership"),
+        ("owner", pending_owner.as_str()),
+    ]))
+}
+
+// only owner can execute
+fn execute_update_role(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+    granted: bool,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    let before = ROLES.has(deps.storage, (role.as_str(), &address));
+    let action = if granted {
+        ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+        AuditAction::GrantRole
+    } else {
+        ROLES.remove(deps.storage, (role.as_str(), &address));
+        AuditAction::RevokeRole
+    };
+    let action_name = action.to_string();
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        action,
+        format!("{}/{}", role, address),
+        reason,
+        Some(before.to_string()),
+        Some(granted.to_string()),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", action_name.as_str()),
+        ("role", role.as_str()),
+        ("address", address.as_str()),
+    ]))
+}
+
+// only fee managers can execute
+#[allow(clippy::too_many_arguments)]
+fn execute_update_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_fee: Option<Vec<TokenFee>>,
+    relayer_fee: Option<Vec<RelayerFee>>,
+    fee_receiver: Option<String>,
+    relayer_fee_receiver: Option<String>,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::FeeManager)?;
+    let before = to_json_string(&query_config(deps.as_ref())?)?;
+    save_fees(deps.storage, token_fee, relayer_fee)?;
+    CONFIG.update(deps.storage, |mut config| -> StdResult<Config> {
+        if let Some(fee_receiver) = fee_receiver {
+            config.token_fee_receiver = deps.api.addr_validate(&fee_receiver)?;
+        }
+        if let Some(relayer_fee_receiver) = relayer_fee_receiver {
+            config.relayer_fee_receiver = deps.api.addr_validate(&relayer_fee_receiver)?;
+        }
+        Ok(config)
+    })?;
+    let after = to_json_string(&query_config(deps.as_ref())?)?;
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::UpdateConfig,
+        "fees".to_string(),
+        reason,
+        Some(before),
+        Some(after),
+    )?;
+    Ok(Response::new().add_attribute("action", "update_fees"))
+}
+
+fn save_fees(
+    storage: &mut dyn Storage,
+    token_fee: Option<Vec<TokenFee>>,
+    relayer_fee: Option<Vec<RelayerFee>>,
+) -> StdResult<()> {
+    for fee in token_fee.unwrap_or_default() {
+        TOKEN_FEE.save(storage, &fee.token_denom, &fee.ratio)?;
+    }
+    for fee in relayer_fee.unwrap_or_default() {
+        RELAYER_FEE.save(storage, &fee.prefix, &fee.fee)?;
+    }
+    Ok(())
+}
+
+// disables both directions of a mapping, only pausers can execute. Re-enabling goes through the mapping update
+fn execute_pause_mapping_pair(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ibc_denom: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+    let mut pair_mapping = ics20_denoms()
+        .load(deps.storage, &ibc_denom)
+        .map_err(|_| ContractError::MappingPairNotFound {})?;
+    let before = to_json_string(&pair_mapping.status)?;
+    pair_mapping.status = MappingStatus::Disabled;
+    save_mapping_pair(deps.storage, &ibc_denom, &pair_mapping)?;
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::PauseMappingPair,
+        ibc_denom.clone(),
+        reason,
+        Some(before),
+        Some(to_json_string(&pair_mapping.status)?),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "pause_mapping_pair"),
+        ("ibc_denom", &ibc_denom),
+    ]))
+}
+
+// send the accumulated dust of a mapping. Inbound dust is only swept once it adds up to whole local units
+// only fee managers can execute
+fn execute_sweep_dust(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ibc_denom: String,
+    receiver: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::FeeManager)?;
+    let receiver = deps.api.addr_validate(&receiver)?;
+    let pair_mapping = ics20_denoms()
+        .load(deps.storage, &ibc_denom)
+        .map_err(|_| ContractError::NotOnMappingList {})?;
+    let before = DUST.may_load(deps.storage, &ibc_denom)?.unwrap_or_default();
+    let (from_remote, remote_left) = split_decimals(
+        before.remote,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )?;
+    let swept = before.local + from_remote;
+    if swept.is_zero() {
+        return Err(ContractError::NoDustToSweep { ibc_denom });
+    }
+    let after = DustBalance {
+        remote: remote_left,
+        local: Uint128::zero(),
+    };
+    DUST.save(deps.storage, &ibc_denom, &after)?;
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::SweepDust,
+        ibc_denom.clone(),
+        reason,
+        Some(to_json_string(&before)?),
+        Some(to_json_string(&after)?),
+    )?;
+
+    // outbound dust is held by the contract. Inbound dust of mint-burn mappings has never been minted
+    let denom = parse_asset_info_denom(&pair_mapping.asset_info);
+    let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
+    let held = if pair_mapping.is_mint_burn {
+        before.local
+    } else {
+        swept
+    };
+    if !held.is_zero() {
+        cosmos_msgs.push(Amount::from_parts(denom, held).send_amount(receiver.to_string(), None));
+    }
+    if pair_mapping.is_mint_burn && !from_remote.is_zero() {
+        increase_minted_supply(deps.storage, &pair_mapping, from_remote)?;
+        if let Some(mint_msg) = build_mint_mapping_msg(
+            CONFIG.load(deps.storage)?.token_factory_addr.to_string(),
+            true,
+            pair_mapping.asset_info,
+            from_remote,
+            receiver.to_string(),
+        )? {
+            cosmos_msgs.push(mint_msg);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(cosmos_msgs)
+        .add_attributes(vec![
+            ("action", "sweep_dust"),
+            ("ibc_denom", &ibc_denom),
+            ("receiver", receiver.as_str()),
+            ("amount", &swept.to_string()),
+        ]))
+}
+
 pub fn register_denom(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: RegisterDenomMsg,
 ) -> Result<Response, ContractError> {
-    if !ADMIN.is_admin(deps.as_ref(), &info.sender)? && info.sender != env.contract.address {
-        return Err(ContractError::Admin(AdminError::NotAdmin {}));
-    };
+    if info.sender != env.contract.address {
+        assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+    }
 
     let config = CONFIG.load(deps.storage)?;
 
@@ -231,35 +694,274 @@ pub fn register_denom(
         .add_message(create_denom_msg))
 }
 
+// queues the override, which takes effect once the timelock delay has passed
+// only owner can execute
+#[allow(clippy::too_many_arguments)]
 pub fn handle_override_channel_balance(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     channel_id: String,
     ibc_denom: String,
     outstanding: Uint128,
     total_sent: Option<Uint128>,
+    reason: String,
 ) -> Result<Response, ContractError> {
     ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
-    override_channel_balance(
+    let operation = schedule_timelock(
         deps.storage,
+        &env.block,
+        &info.sender,
+        TimelockAction::OverrideChannelBalance {
+            channel_id,
+            ibc_denom,
+            outstanding,
+            total_sent,
+        },
+        reason,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "override_channel_balance")
+        .add_attributes(timelock_attributes(&operation)))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_override_channel_balance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    actor: &Addr,
+    channel_id: String,
+    ibc_denom: String,
+    outstanding: Uint128,
+    total_sent: Option<Uint128>,
+    reason: String,
+) -> Result<Vec<Attribute>, ContractError> {
+    let before = CHANNEL_REVERSE_STATE.may_load(storage, (&channel_id, &ibc_denom))?;
+    override_channel_balance(
+        storage,
         &channel_id,
         &ibc_denom,
         outstanding,
         total_sent,
+        block,
+    )?;
+    let after = CHANNEL_REVERSE_STATE.load(storage, (&channel_id, &ibc_denom))?;
+    append_audit_entry(
+        storage,
+        block,
+        actor,
+        AuditAction::OverrideChannelBalance,
+        format!("{}/{}", channel_id, ibc_denom),
+        reason,
+        before.map(|state| to_json_string(&state)).transpose()?,
+        Some(to_json_string(&after)?),
+    )?;
+    Ok(vec![
+        attr("channel_id", channel_id),
+        attr("ibc_denom", ibc_denom),
+        attr("new_outstanding", outstanding.to_string()),
+        attr("total_sent", total_sent.unwrap_or_default().to_string()),
+    ])
+}
+
+fn apply_withdraw_asset(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    actor: &Addr,
+    coin: Amount,
+    receiver: Addr,
+    reason: String,
+) -> Result<CosmosMsg, ContractError> {
+    append_audit_entry(
+        storage,
+        block,
+        actor,
+        AuditAction::WithdrawAsset,
+        receiver.to_string(),
+        reason,
+        None,
+        Some(to_json_string(&coin)?),
+    )?;
+    Ok(coin.send_amount(receiver.to_string(), None))
+}
+
+fn timelock_attributes(operation: &TimelockOperation) -> Vec<Attribute> {
+    vec![
+        attr("timelock_id", operation.id.to_string()),
+        attr(
+            "executable_at",
+            operation.executable_at.seconds().to_string(),
+        ),
+    ]
+}
+
+// queues a new delay. It is timelocked as well, so users get notice before the delay is shortened
+// only owner can execute
+fn execute_update_timelock_delay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delay: u64,
+    reason: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let operation = schedule_timelock(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        TimelockAction::UpdateDelay { delay },
+        reason,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "update_timelock_delay")
+        .add_attributes(timelock_attributes(&operation)))
+}
+
+// applies a queued operation once its delay has passed, anyone can execute
+fn execute_timelock(deps: DepsMut, env: Env, id: u64) -> Result<Response, ContractError> {
+    let operation = TIMELOCK_OPERATIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::TimelockNotFound { id })?;
+    if env.block.time < operation.executable_at {
+        return Err(ContractError::TimelockNotReady {
+            id,
+            executable_at: operation.executable_at,
+        });
+    }
+    TIMELOCK_OPERATIONS.remove(deps.storage, id);
+
+    // the change is recorded on behalf of the proposer
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let TimelockOperation {
+        action,
+        proposer,
+        reason,
+        ..
+    } = operation;
+    let attrs = match action {
+        TimelockAction::UpdateDependencies {
+            swap_router_contract,
+            converter_contract,
+            osor_entrypoint_contract,
+            token_factory_addr,
+        } => {
+            let before = to_json_string(&query_config(deps.as_ref())?)?;
+            CONFIG.update(deps.storage, |mut config| -> StdResult<Config> {
+                if let Some(swap_router_contract) = swap_router_contract {
+                    config.swap_router_contract = RouterController(swap_router_contract);
+                }
+                if let Some(converter_contract) = converter_contract {
+                    config.converter_contract = ConverterController(converter_contract);
+                }
+                if let Some(osor_entrypoint_contract) = osor_entrypoint_contract {
+                    config.osor_entrypoint_contract = osor_entrypoint_contract;
+                }
+                if let Some(token_factory_addr) = token_factory_addr {
+                    config.token_factory_addr = token_factory_addr;
+                }
+                Ok(config)
+            })?;
+            let after = to_json_string(&query_config(deps.as_ref())?)?;
+            append_audit_entry(
+                deps.storage,
+                &env.block,
+                &proposer,
+                AuditAction::UpdateConfig,
+                "config".to_string(),
+                reason,
+                Some(before),
+                Some(after),
+            )?;
+            vec![]
+        }
+        TimelockAction::OverrideChannelBalance {
+            channel_id,
+            ibc_denom,
+            outstanding,
+            total_sent,
+        } => apply_override_channel_balance(
+            deps.storage,
+            &env.block,
+            &proposer,
+            channel_id,
+            ibc_denom,
+            outstanding,
+            total_sent,
+            reason,
+        )?,
+        TimelockAction::UpdateDelay { delay } => {
+            let before = TIMELOCK_DELAY
+                .may_load(deps.storage)?
+                .unwrap_or(DEFAULT_TIMELOCK_DELAY);
+            TIMELOCK_DELAY.save(deps.storage, &delay)?;
+            append_audit_entry(
+                deps.storage,
+                &env.block,
+                &proposer,
+                AuditAction::UpdateConfig,
+                "timelock_delay".to_string(),
+                reason,
+                Some(before.to_string()),
+                Some(delay.to_string()),
+            )?;
+            vec![attr("delay", delay.to_string())]
+        }
+        TimelockAction::WithdrawAsset { coin, receiver } => {
+            msgs.push(apply_withdraw_asset(
+                deps.storage,
+                &env.block,
+                &proposer,
+                coin,
+                receiver,
+                reason,
+            )?);
+            vec![]
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_timelock")
+        .add_attribute("timelock_id", id.to_string())
+        .add_attributes(attrs)
+        .add_messages(msgs))
+}
+
+// only guardians can execute
+fn execute_cancel_timelock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Guardian)?;
+    let operation = TIMELOCK_OPERATIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::TimelockNotFound { id })?;
+    TIMELOCK_OPERATIONS.remove(deps.storage, id);
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::CancelTimelock,
+        id.to_string(),
+        reason,
+        Some(to_json_string(&operation)?),
+        None,
     )?;
+
     Ok(Response::new().add_attributes(vec![
-        ("action", "override_channel_balance"),
-        ("channel_id", &channel_id),
-        ("ibc_denom", &ibc_denom),
-        ("new_outstanding", &outstanding.to_string()),
-        ("total_sent", &total_sent.unwrap_or_default().to_string()),
+        ("action", "cancel_timelock"),
+        ("timelock_id", &id.to_string()),
     ]))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_increase_channel_balance_ibc_receive(
     deps: DepsMut,
     caller: Addr,
     contract_addr: Addr,
+    block: &BlockInfo,
     dst_channel_id: String,
     ibc_denom: String,
     remote_amount: Uint128,
@@ -268,18 +970,35 @@ pub fn handle_increase_channel_balance_ibc_receive(
     let config = CONFIG.load(deps.storage)?;
     is_caller_contract(caller, contract_addr.clone())?;
     // will have to increase balance here because if this tx fails then it will be reverted, and the balance on the remote chain will also be reverted
-    increase_channel_balance(deps.storage, &dst_channel_id, &ibc_denom, remote_amount)?;
+    increase_channel_balance(
+        deps.storage,
+        &dst_channel_id,
+        &ibc_denom,
+        remote_amount,
+        block,
+    )?;
 
     let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
     let pair_mapping = ics20_denoms()
         .load(deps.storage, &ibc_denom)
         .map_err(|_| ContractError::NotOnMappingList {})?;
 
-    let mint_amount = convert_remote_to_local(
+    let (mint_amount, dust) = convert_remote_to_local_exact(
         remote_amount,
         pair_mapping.remote_decimals,
         pair_mapping.asset_info_decimals,
     )?;
+    // inbound dust cannot be returned to the remote sender, so the refund policy keeps it as well
+    if !dust.is_zero()
+        && matches!(
+            pair_mapping.dust_policy,
+            DustPolicy::Refund | DustPolicy::Accumulate
+        )
+    {
+        accumulate_dust(deps.storage, &ibc_denom, dust, Uint128::zero())?;
+    }
+    // the channel balance has been increased above, so the new outstanding must back what we mint
+    increase_minted_supply(deps.storage, &pair_mapping, mint_amount)?;
     let mint_msg = build_mint_mapping_msg(
         config.token_factory_addr.to_string(),
         pair_mapping.is_mint_burn,
@@ -311,10 +1030,12 @@ pub fn handle_increase_channel_balance_ibc_receive(
         .add_messages(cosmos_msgs))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_reduce_channel_balance_ibc_receive(
     storage: &mut dyn Storage,
     caller: Addr,
     contract_addr: Addr,
+    block: &BlockInfo,
     src_channel_id: String,
     ibc_denom: String,
     remote_amount: Uint128,
@@ -323,8 +1044,14 @@ pub fn handle_reduce_channel_balance_ibc_receive(
     let config = CONFIG.load(storage)?;
     is_caller_contract(caller, contract_addr.clone())?;
     // because we are transferring back, we reduce the channel's balance
-    reduce_channel_balance(storage, src_channel_id.as_str(), &ibc_denom, remote_amount)
-        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    reduce_channel_balance(
+        storage,
+        src_channel_id.as_str(),
+        &ibc_denom,
+        remote_amount,
+        block,
+    )
+    .map_err(|err| StdError::generic_err(err.to_string()))?;
 
     // keep track of the single-step reply since we need ibc data to undo reducing channel balance and local data for refunding.
     // we use a different item to not override REPLY_ARGS
@@ -349,6 +1076,7 @@ pub fn handle_reduce_channel_balance_ibc_receive(
         pair_mapping.remote_decimals,
         pair_mapping.asset_info_decimals,
     )?;
+    reduce_minted_supply(storage, &pair_mapping, burn_amount)?;
     let burn_msg = build_burn_mapping_msg(
         config.token_factory_addr.to_string(),
         pair_mapping.is_mint_burn,
@@ -374,11 +1102,12 @@ pub fn handle_reduce_channel_balance_ibc_receive(
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     default_timeout: Option<u64>,
-    default_gas_limit: Option<u64>,
+    default_gas_limit: Option<UpdateValue<u64>>,
     swap_router_contract: Option<String>,
-    admin: Option<String>,
+    admin: Option<UpdateValue<String>>,
     token_fee: Option<Vec<TokenFee>>,
     fee_receiver: Option<String>,
     relayer_fee_receiver: Option<String>,
@@ -386,48 +1115,74 @@ pub fn update_config(
     converter_contract: Option<String>,
     osor_entrypoint_contract: Option<String>,
     token_factory_addr: Option<String>,
+    reason: String,
 ) -> Result<Response, ContractError> {
     ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
-    if let Some(token_fee) = token_fee {
-        for fee in token_fee {
-            TOKEN_FEE.save(deps.storage, &fee.token_denom, &fee.ratio)?;
-        }
-    }
-    if let Some(relayer_fee) = relayer_fee {
-        for fee in relayer_fee {
-            RELAYER_FEE.save(deps.storage, &fee.prefix, &fee.fee)?;
-        }
-    }
+    let before = to_json_string(&query_config(deps.as_ref())?)?;
+    save_fees(deps.storage, token_fee, relayer_fee)?;
     CONFIG.update(deps.storage, |mut config| -> StdResult<Config> {
         if let Some(default_timeout) = default_timeout {
             config.default_timeout = default_timeout;
         }
-        if let Some(swap_router_contract) = swap_router_contract {
-            config.swap_router_contract = RouterController(swap_router_contract);
-        }
         if let Some(fee_receiver) = fee_receiver {
             config.token_fee_receiver = deps.api.addr_validate(&fee_receiver)?;
         }
         if let Some(relayer_fee_receiver) = relayer_fee_receiver {
             config.relayer_fee_receiver = deps.api.addr_validate(&relayer_fee_receiver)?;
         }
-        if let Some(converter_contract) = converter_contract {
-            config.converter_contract = ConverterController(converter_contract);
-        }
-        if let Some(osor_entrypoint_contract) = osor_entrypoint_contract {
-            config.osor_entrypoint_contract = osor_entrypoint_contract;
+        if let Some(default_gas_limit) = default_gas_limit {
+            config.default_gas_limit = default_gas_limit.into_option();
         }
-        if let Some(token_factory_addr) = token_factory_addr {
-            config.token_factory_addr = deps.api.addr_validate(&token_factory_addr)?;
-        }
-        config.default_gas_limit = default_gas_limit;
         Ok(config)
     })?;
-    if let Some(admin) = admin {
-        let admin = deps.api.addr_validate(&admin)?;
-        ADMIN.execute_update_admin::<Empty, Empty>(deps, info, Some(admin))?;
+    // changes of core dependencies are queued so that users get notice before they take effect
+    let mut res = Response::default().add_attribute("action", "update_config");
+    if swap_router_contract.is_some()
+        || converter_contract.is_some()
+        || osor_entrypoint_contract.is_some()
+        || token_factory_addr.is_some()
+    {
+        let operation = schedule_timelock(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            TimelockAction::UpdateDependencies {
+                swap_router_contract,
+                converter_contract,
+                osor_entrypoint_contract,
+                token_factory_addr: token_factory_addr
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?,
+            },
+            reason.clone(),
+        )?;
+        res = res.add_attributes(timelock_attributes(&operation));
+    }
+    // the new owner takes over once it accepts the ownership, unset cancels a pending transfer
+    if let Some(admin) = admin.map(UpdateValue::into_option) {
+        let pending_owner = admin
+            .map(|admin| deps.api.addr_validate(&admin))
+            .transpose()?;
+        update_pending_owner(
+            deps.storage,
+            &env.block,
+            &info.sender,
+            pending_owner,
+            reason.clone(),
+        )?;
     }
-    Ok(Response::default().add_attribute("action", "update_config"))
+    let after = to_json_string(&query_config(deps.as_ref())?)?;
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::UpdateConfig,
+        "config".to_string(),
+        reason,
+        Some(before),
+        Some(after),
+    )?;
+    Ok(res)
 }
 
 pub fn execute_receive(
@@ -535,12 +1290,48 @@ pub fn execute_transfer_back_to_remote_chain(
     if amount.is_empty() {
         return Err(ContractError::NoFunds {});
     }
+    // cw20 tokens must be on the allow list, unless there is a default gas limit for them
+    check_gas_limit(deps.storage, &amount)?;
+    // otherwise anyone could make the bridge call an arbitrary contract
+    let callback = msg
+        .callback
+        .as_ref()
+        .map(|callback| deps.api.addr_validate(callback))
+        .transpose()?;
+    if let Some(callback) = &callback {
+        if *callback != sender {
+            return Err(ContractError::CallbackNotSender {
+                callback: callback.to_string(),
+                sender: sender.to_string(),
+            });
+        }
+    }
     let config = CONFIG.load(deps.storage)?;
 
     // should be in form port/channel/denom
     let mappings =
         get_mappings_from_asset_info(deps.as_ref().storage, amount.into_asset_info(deps.api)?)?;
 
+    // let the contract pick the channel and remote denom for the requested destination
+    let msg = match msg.remote_prefix.clone() {
+        Some(remote_prefix) => {
+            let (local_channel_id, remote_denom) = select_transfer_route(
+                deps.storage,
+                &env,
+                &mappings,
+                &msg.local_channel_id,
+                &remote_prefix,
+                amount.amount(),
+            )?;
+            TransferBackMsg {
+                local_channel_id,
+                remote_denom,
+                ..msg
+            }
+        }
+        None => msg,
+    };
+
     // parse denom & compare with user input. Should not use string.includes() because hacker can fake a port that has the same remote denom to return true
     let mapping = mappings
         .into_iter()
@@ -557,6 +1348,26 @@ pub fn execute_transfer_back_to_remote_chain(
             }
         })
         .ok_or(ContractError::MappingPairNotFound {})?;
+    // checked before the fees are deducted, so a transfer drained by fees cannot go through a closed route either
+    if !mapping.pair_mapping.status.can_send() {
+        return Err(ContractError::MappingSendDisabled {
+            ibc_denom: mapping.key,
+        });
+    }
+    // ensure the requested channel is registered
+    if !CHANNEL_INFO.has(deps.storage, &msg.local_channel_id) {
+        return Err(ContractError::NoSuchChannel {
+            id: msg.local_channel_id,
+        });
+    }
+
+    // the bounds apply to what the user sends, so a dust transfer entirely drained by fees is rejected as well
+    let (gross_remote, _) = split_decimals(
+        amount.amount(),
+        mapping.pair_mapping.asset_info_decimals,
+        mapping.pair_mapping.remote_decimals,
+    )?;
+    assert_transfer_amount(&mapping.pair_mapping, gross_remote)?;
 
     // if found mapping, then deduct fee based on mapping
     let fee_data = process_deduct_fee(
@@ -604,12 +1415,6 @@ pub fn execute_transfer_back_to_remote_chain(
     }
 
     let ibc_denom = mapping.key;
-    // ensure the requested channel is registered
-    if !CHANNEL_INFO.has(deps.storage, &msg.local_channel_id) {
-        return Err(ContractError::NoSuchChannel {
-            id: msg.local_channel_id,
-        });
-    }
 
     // delta from user is in seconds
     let timeout = match msg.timeout {
@@ -618,11 +1423,38 @@ pub fn execute_transfer_back_to_remote_chain(
     };
 
     // need to convert decimal of cw20 to remote decimal before transferring
-    let amount_remote = convert_local_to_remote(
+    let (amount_remote, dust) = convert_local_to_remote_exact(
         fee_data.deducted_amount,
         mapping.pair_mapping.remote_decimals,
         mapping.pair_mapping.asset_info_decimals,
     )?;
+    // local amount that is actually transferred, without the dust returned to the sender or kept for sweeping
+    let mut transfer_amount = fee_data.deducted_amount;
+    if !dust.is_zero() {
+        match mapping.pair_mapping.dust_policy {
+            DustPolicy::Truncate => {}
+            DustPolicy::Reject => {
+                return Err(ContractError::InexactAmount {
+                    amount: fee_data.deducted_amount,
+                    dust,
+                })
+            }
+            DustPolicy::Refund => {
+                transfer_amount -= dust;
+                cosmos_msgs.push(
+                    Amount::from_parts(
+                        parse_asset_info_denom(&mapping.pair_mapping.asset_info),
+                        dust,
+                    )
+                    .send_amount(sender.to_string(), None),
+                );
+            }
+            DustPolicy::Accumulate => {
+                transfer_amount -= dust;
+                accumulate_dust(deps.storage, &ibc_denom, Uint128::zero(), dust)?;
+            }
+        }
+    }
 
     // now this is processed in ack
     // // because we are transferring back, we reduce the channel's balance
@@ -631,6 +1463,13 @@ pub fn execute_transfer_back_to_remote_chain(
         &msg.local_channel_id,
         &ibc_denom,
         amount_remote,
+        &env.block,
+    )?;
+    add_pending_packet(
+        deps.storage,
+        &msg.local_channel_id,
+        &ibc_denom,
+        amount_remote,
     )?;
 
     // prepare ibc message
@@ -643,13 +1482,22 @@ pub fn execute_transfer_back_to_remote_chain(
         &msg.local_channel_id,
         timeout.into(),
     )?;
+    // the callback is saved with the sequence of the packet, which is known in the reply of its send
+    let send_msg = match callback {
+        Some(callback) => {
+            SEND_PACKET_CALLBACK.save(deps.storage, &callback)?;
+            SubMsg::reply_on_success(ibc_msg, SEND_PACKET_CALLBACK_ID)
+        }
+        None => SubMsg::new(ibc_msg),
+    };
 
     // build burn msg if the mechanism is mint/burn
+    reduce_minted_supply(deps.storage, &mapping.pair_mapping, transfer_amount)?;
     let burn_msg = build_burn_mapping_msg(
         config.token_factory_addr.to_string(),
         mapping.pair_mapping.is_mint_burn,
         mapping.pair_mapping.asset_info,
-        fee_data.deducted_amount,
+        transfer_amount,
         env.contract.address.to_string(),
     )?;
     if let Some(burn_msg) = burn_msg {
@@ -658,7 +1506,7 @@ pub fn execute_transfer_back_to_remote_chain(
 
     Ok(Response::new()
         .add_messages(cosmos_msgs)
-        .add_message(ibc_msg)
+        .add_submessage(send_msg)
         .add_attributes(attributes)
         .add_attributes(vec![
             ("denom", &ibc_denom),
@@ -666,6 +1514,68 @@ pub fn execute_transfer_back_to_remote_chain(
         ]))
 }
 
+/// Picks the first mapping of the remote prefix, optionally restricted to a local channel, that accepts the
+/// amount and whose channel has enough outstanding balance to cover it. Returns the local channel id and remote denom
+fn select_transfer_route(
+    storage: &dyn Storage,
+    env: &Env,
+    mappings: &[PairQuery],
+    local_channel_id: &str,
+    remote_prefix: &str,
+    amount_local: Uint128,
+) -> Result<(String, String), ContractError> {
+    let port_id = parse_ibc_wasm_port_id(env.contract.address.as_str());
+    let mut candidates = vec![];
+    for mapping in mappings {
+        let (port, channel_id, remote_denom) = parse_ibc_info_without_sanity_checks(&mapping.key)?;
+        if port != port_id
+            || (!local_channel_id.is_empty() && channel_id != local_channel_id)
+            || (convert_remote_denom_to_evm_prefix(remote_denom) != remote_prefix
+                && remote_denom != remote_prefix)
+            || !mapping.pair_mapping.status.can_send()
+        {
+            continue;
+        }
+        let (amount_remote, dust) = split_decimals(
+            amount_local,
+            mapping.pair_mapping.asset_info_decimals,
+            mapping.pair_mapping.remote_decimals,
+        )?;
+        // mappings that would reject the transfer are skipped, with the reason
+        if let Err(err) = assert_transfer_amount(&mapping.pair_mapping, amount_remote) {
+            candidates.push(format!("{}: {}", mapping.key, err));
+            continue;
+        }
+        if mapping.pair_mapping.dust_policy == DustPolicy::Reject && !dust.is_zero() {
+            let err = ContractError::InexactAmount {
+                amount: amount_local,
+                dust,
+            };
+            candidates.push(format!("{}: {}", mapping.key, err));
+            continue;
+        }
+        let outstanding = CHANNEL_REVERSE_STATE
+            .may_load(storage, (channel_id, &mapping.key))?
+            .unwrap_or_default()
+            .outstanding;
+        if outstanding >= amount_remote {
+            return Ok((channel_id.to_string(), remote_denom.to_string()));
+        }
+        candidates.push(format!(
+            "{}: Outstanding balance {} is too low",
+            mapping.key, outstanding
+        ));
+    }
+    if candidates.is_empty() {
+        return Err(ContractError::MappingPairNotFound {});
+    }
+    Err(ContractError::NoTransferRoute {
+        remote_prefix: remote_prefix.to_string(),
+        amount: amount_local,
+        candidates: candidates.join(", "),
+    })
+}
+
 pub fn build_burn_mapping_msg(
     token_factory: String,
     is_mint_burn: bool,
@@ -742,46 +1652,85 @@ pub fn build_mint_mapping_msg(
     }
 }
 
-// /// The gov contract can allow new contracts, or increase the gas limit on existing contracts.
-// /// It cannot block or reduce the limit to avoid forcible sticking tokens in the channel.
-// pub fn execute_allow(
-//     deps: DepsMut,
-//     _env: Env,
-//     info: MessageInfo,
-//     allow: AllowMsg,
-// ) -> Result<Response, ContractError> {
-//     ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+/// The gov contract can allow new contracts, or increase the gas limit on existing contracts.
+/// It cannot reduce a limit to avoid forcible sticking tokens in the channel, but it can cap a contract without one.
+pub fn execute_allow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    gas_limit: Option<u64>,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+
+    let contract = deps.api.addr_validate(&contract)?;
+    let before = ALLOW_LIST.may_load(deps.storage, &contract)?;
+    if let Some(old) = before.as_ref() {
+        // we must ensure it increases the limit, a contract without a limit can still be capped
+        if let (Some(old), Some(new)) = (old.gas_limit, gas_limit) {
+            if new < old {
+                return Err(ContractError::CannotLowerGas);
+            }
+        }
+    }
+    let after = AllowInfo { gas_limit };
+    ALLOW_LIST.save(deps.storage, &contract, &after)?;
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::UpdateAllowList,
+        contract.to_string(),
+        reason,
+        before.map(|allow| to_json_string(&allow)).transpose()?,
+        Some(to_json_string(&after)?),
+    )?;
 
-//     let contract = deps.api.addr_validate(&allow.contract)?;
-//     let set = AllowInfo {
-//         gas_limit: allow.gas_limit,
-//     };
-//     ALLOW_LIST.update(deps.storage, &contract, |old| {
-//         if let Some(old) = old {
-//             // we must ensure it increases the limit
-//             match (old.gas_limit, set.gas_limit) {
-//                 (None, Some(_)) => return Err(ContractError::CannotLowerGas),
-//                 (Some(old), Some(new)) if new < old => return Err(ContractError::CannotLowerGas),
-//                 _ => {}
-//             };
-//         }
-//         Ok(AllowInfo {
-//             gas_limit: allow.gas_limit,
-//         })
-//     })?;
-
-//     let gas = if let Some(gas) = allow.gas_limit {
-//         gas.to_string()
-//     } else {
-//         "None".to_string()
-//     };
+    let gas = if let Some(gas) = gas_limit {
+        gas.to_string()
+    } else {
+        "None".to_string()
+    };
 
-//     let res = Response::new()
-//         .add_attribute("action", "allow")
-//         .add_attribute("contract", allow.contract)
-//         .add_attribute("gas_limit", gas);
-//     Ok(res)
-// }
+    let res = Response::new()
+        .add_attribute("action", "allow")
+        .add_attribute("contract", contract)
+        .add_attribute("gas_limit", gas);
+    Ok(res)
+}
+
+/// The gov contract can remove contracts from the allow list, so they can no longer be sent to the remote chain.
+/// Receives and refunds of their tokens still go through, with the default gas limit.
+pub fn execute_disallow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+
+    let contract = deps.api.addr_validate(&contract)?;
+    let before = ALLOW_LIST
+        .may_load(deps.storage, &contract)?
+        .ok_or(ContractError::NotOnAllowList)?;
+    ALLOW_LIST.remove(deps.storage, &contract);
+    append_audit_entry(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AuditAction::UpdateAllowList,
+        contract.to_string(),
+        reason,
+        Some(to_json_string(&before)?),
+        None,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "disallow")
+        .add_attribute("contract", contract))
+}
 
 /// The gov contract can allow new contracts, or increase the gas limit on existing contracts.
 /// It cannot block or reduce the limit to avoid forcible sticking tokens in the channel.
@@ -791,38 +1740,246 @@ pub fn execute_update_mapping_pair(
     info: MessageInfo,
     mapping_pair_msg: UpdatePairMsg,
 ) -> Result<Response, ContractError> {
-    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+    validate_update_pair_msg(deps.storage, &env, &mapping_pair_msg)?;
 
+    let attrs = update_mapping_pair(deps.storage, &env, &info.sender, mapping_pair_msg)?;
+    let res = Response::new()
+        .add_attribute("action", "execute_update_mapping_pair")
+        .add_attributes(attrs);
+    Ok(res)
+}
+
+pub fn execute_batch_update_mapping_pairs(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pairs: Vec<UpdatePairMsg>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+    if pairs.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    // validate every entry before touching the state
+    let port_id = parse_ibc_wasm_port_id(env.contract.address.as_str());
+    let mut ibc_denoms = HashSet::new();
+    for pair in pairs.iter() {
+        validate_update_pair_msg(deps.storage, &env, pair)?;
+        let ibc_denom = get_key_ics20_ibc_denom(&port_id, &pair.local_channel_id, &pair.denom);
+        if !ibc_denoms.insert(ibc_denom.clone()) {
+            return Err(ContractError::DuplicateMappingPair { ibc_denom });
+        }
+    }
+
+    let mut events = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let attrs = update_mapping_pair(deps.storage, &env, &info.sender, pair)?;
+        events.push(Event::new("update_mapping_pair").add_attributes(attrs));
+    }
+    Ok(Response::new()
+        .add_attribute("action", "batch_update_mapping_pairs")
+        .add_attribute("count", events.len().to_string())
+        .add_events(events))
+}
+
+// empty fields of an update keep the value of the existing pair, so a limit is not dropped by accident
+fn merge_update_value<T>(update: Option<UpdateValue<T>>, old: Option<T>) -> Option<T> {
+    match update {
+        Some(update) => update.into_option(),
+        None => old,
+    }
+}
+
+// checks the pair as it will be saved, with the values kept from the existing pair
+fn validate_update_pair_msg(
+    storage: &dyn Storage,
+    env: &Env,
+    mapping_pair_msg: &UpdatePairMsg,
+) -> Result<(), ContractError> {
+    for decimals in [
+        mapping_pair_msg.remote_decimals,
+        mapping_pair_msg.local_asset_info_decimals,
+    ] {
+        if decimals > MAX_DECIMALS {
+            return Err(ContractError::DecimalsTooLarge {
+                decimals,
+                max: MAX_DECIMALS,
+            });
+        }
+    }
     let ibc_denom = get_key_ics20_ibc_denom(
         &parse_ibc_wasm_port_id(env.contract.address.as_str()),
         &mapping_pair_msg.local_channel_id,
         &mapping_pair_msg.denom,
     );
-
-    // if pair already exists in list, remove it and create a new one
-    if ics20_denoms().load(deps.storage, &ibc_denom).is_ok() {
-        ics20_denoms().remove(deps.storage, &ibc_denom)?;
+    let before = ics20_denoms().may_load(storage, &ibc_denom)?;
+    let min_amount = merge_update_value(
+        mapping_pair_msg.min_amount.clone(),
+        before.as_ref().and_then(|pair| pair.min_amount),
+    );
+    let max_amount = merge_update_value(
+        mapping_pair_msg.max_amount.clone(),
+        before.as_ref().and_then(|pair| pair.max_amount),
+    );
+    if let (Some(min_amount), Some(max_amount)) = (min_amount, max_amount) {
+        if min_amount > max_amount {
+            return Err(ContractError::InvalidAmountBounds {
+                min_amount,
+                max_amount,
+            });
+        }
     }
+    Ok(())
+}
 
-    ics20_denoms().save(
-        deps.storage,
-        &ibc_denom,
-        &MappingMetadata {
-            asset_info: mapping_pair_msg.local_asset_info.clone(),
-            remote_decimals: mapping_pair_msg.remote_decimals,
-            asset_info_decimals: mapping_pair_msg.local_asset_info_decimals,
-            is_mint_burn: mapping_pair_msg.is_mint_burn.unwrap_or_default(),
-        },
-    )?;
+// saves the mapping pair and returns the attributes describing the change. The caller must check the admin
+fn update_mapping_pair(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    mapping_pair_msg: UpdatePairMsg,
+) -> Result<Vec<Attribute>, ContractError> {
+    let ibc_denom = get_key_ics20_ibc_denom(
+        &parse_ibc_wasm_port_id(env.contract.address.as_str()),
+        &mapping_pair_msg.local_channel_id,
+        &mapping_pair_msg.denom,
+    );
 
-    let res = Response::new()
-        .add_attribute("action", "execute_update_mapping_pair")
-        .add_attribute("denom", mapping_pair_msg.denom)
-        .add_attribute(
+    // if pair already exists in list, remove it and create a new one
+    let before = ics20_denoms().may_load(storage, &ibc_denom)?;
+    let mut attrs = vec![
+        attr("denom", mapping_pair_msg.denom.clone()),
+        attr(
             "new_asset_info",
             mapping_pair_msg.local_asset_info.to_string(),
-        );
-    Ok(res)
+        ),
+    ];
+    if let Some(old_pair) = before.as_ref() {
+        // the outstanding balance and the refunds of pending packets are backed by the old asset and mode
+        let asset_changed = old_pair.asset_info != mapping_pair_msg.local_asset_info
+            || mapping_pair_msg
+                .is_mint_burn
+                .map_or(false, |is_mint_burn| is_mint_burn != old_pair.is_mint_burn);
+        if asset_changed {
+            let outstanding = CHANNEL_REVERSE_STATE
+                .may_load(storage, (&mapping_pair_msg.local_channel_id, &ibc_denom))?
+                .unwrap_or_default()
+                .outstanding;
+            let pending = PENDING_PACKETS
+                .may_load(storage, (&mapping_pair_msg.local_channel_id, &ibc_denom))?
+                .unwrap_or_default()
+                .count;
+            if !outstanding.is_zero() || pending > 0 {
+                return Err(ContractError::AssetChangeWithOutstanding {
+                    channel_id: mapping_pair_msg.local_channel_id,
+                    denom: ibc_denom,
+                    outstanding,
+                    pending,
+                });
+            }
+        }
+        let remote_decimals_changed = old_pair.remote_decimals != mapping_pair_msg.remote_decimals;
+        let local_decimals_changed =
+            old_pair.asset_info_decimals != mapping_pair_msg.local_asset_info_decimals;
+        if remote_decimals_changed || local_decimals_changed {
+            // outstanding is stored in remote units, so new decimals change the value of in-flight vouchers
+            let outstanding = CHANNEL_REVERSE_STATE
+                .may_load(storage, (&mapping_pair_msg.local_channel_id, &ibc_denom))?
+                .unwrap_or_default()
+                .outstanding;
+            let rescale = mapping_pair_msg.rescale_outstanding.unwrap_or_default();
+            if !outstanding.is_zero() && (local_decimals_changed || !rescale) {
+                return Err(ContractError::DecimalsChangeWithOutstanding {
+                    channel_id: mapping_pair_msg.local_channel_id,
+                    denom: ibc_denom,
+                    outstanding,
+                });
+            }
+            if remote_decimals_changed && rescale {
+                // a refund of a pending packet would add its amount in the old decimals
+                let pending = PENDING_PACKETS
+                    .may_load(storage, (&mapping_pair_msg.local_channel_id, &ibc_denom))?
+                    .unwrap_or_default()
+                    .count;
+                if pending > 0 {
+                    return Err(ContractError::RescaleWithPendingPackets {
+                        channel_id: mapping_pair_msg.local_channel_id,
+                        denom: ibc_denom,
+                        pending,
+                    });
+                }
+                rescale_channel_balance(
+                    storage,
+                    &mapping_pair_msg.local_channel_id,
+                    &ibc_denom,
+                    old_pair.remote_decimals,
+                    mapping_pair_msg.remote_decimals,
+                    &env.block,
+                )?;
+            }
+        }
+        attrs.extend([
+            attr("old_remote_decimals", old_pair.remote_decimals.to_string()),
+            attr(
+                "old_local_decimals",
+                old_pair.asset_info_decimals.to_string(),
+            ),
+        ]);
+        remove_mapping_pair(storage, &ibc_denom)?;
+    }
+    attrs.extend([
+        attr(
+            "new_remote_decimals",
+            mapping_pair_msg.remote_decimals.to_string(),
+        ),
+        attr(
+            "new_local_decimals",
+            mapping_pair_msg.local_asset_info_decimals.to_string(),
+        ),
+    ]);
+
+    // an update with empty fields cannot unpause the pair or change its dust policy either
+    let pair_mapping = MappingMetadata {
+        asset_info: mapping_pair_msg.local_asset_info,
+        remote_decimals: mapping_pair_msg.remote_decimals,
+        asset_info_decimals: mapping_pair_msg.local_asset_info_decimals,
+        is_mint_burn: mapping_pair_msg
+            .is_mint_burn
+            .or(before.as_ref().map(|pair| pair.is_mint_burn))
+            .unwrap_or_default(),
+        supply_cap: merge_update_value(
+            mapping_pair_msg.supply_cap,
+            before.as_ref().and_then(|pair| pair.supply_cap),
+        ),
+        dust_policy: mapping_pair_msg
+            .dust_policy
+            .or(before.as_ref().map(|pair| pair.dust_policy.clone()))
+            .unwrap_or_default(),
+        status: mapping_pair_msg
+            .status
+            .or(before.as_ref().map(|pair| pair.status.clone()))
+            .unwrap_or_default(),
+        min_amount: merge_update_value(
+            mapping_pair_msg.min_amount,
+            before.as_ref().and_then(|pair| pair.min_amount),
+        ),
+        max_amount: merge_update_value(
+            mapping_pair_msg.max_amount,
+            before.as_ref().and_then(|pair| pair.max_amount),
+        ),
+    };
+    save_mapping_pair(storage, &ibc_denom, &pair_mapping)?;
+    append_audit_entry(
+        storage,
+        &env.block,
+        sender,
+        AuditAction::UpdateMappingPair,
+        ibc_denom,
+        mapping_pair_msg.reason,
+        before.map(|pair| to_json_string(&pair)).transpose()?,
+        Some(to_json_string(&pair_mapping)?),
+    )?;
+    Ok(attrs)
 }
 
 pub fn execute_delete_mapping_pair(
@@ -831,15 +1988,20 @@ pub fn execute_delete_mapping_pair(
     info: MessageInfo,
     mapping_pair_msg: DeletePairMsg,
 ) -> Result<Response, ContractError> {
-    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
 
     let ibc_denom = get_key_ics20_ibc_denom(
         &parse_ibc_wasm_port_id(env.contract.address.as_str()),
         &mapping_pair_msg.local_channel_id,
         &mapping_pair_msg.denom,
     );
-
-    ics20_denoms().remove(deps.storage, &ibc_denom)?;
+    delete_mapping_pair(
+        deps.storage,
+        &env,
+        &info.sender,
+        ibc_denom,
+        mapping_pair_msg.reason,
+    )?;
 
     let res = Response::new()
         .add_attribute("action", "execute_delete_mapping_pair")
@@ -848,33 +2010,161 @@ pub fn execute_delete_mapping_pair(
     Ok(res)
 }
 
+// deleting a pair with outstanding balance would strand the vouchers on the remote chain, so it must be forced
+pub fn execute_batch_delete_mapping_pairs(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pairs: Vec<DeletePairMsg>,
+    force: bool,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MappingManager)?;
+    if pairs.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    // validate every entry before touching the state
+    let port_id = parse_ibc_wasm_port_id(env.contract.address.as_str());
+    let mut ibc_denoms = HashSet::new();
+    for pair in pairs.iter() {
+        let ibc_denom = get_key_ics20_ibc_denom(&port_id, &pair.local_channel_id, &pair.denom);
+        if !ics20_denoms().has(deps.storage, &ibc_denom) {
+            return Err(ContractError::MappingPairNotFound {});
+        }
+        if !force {
+            let outstanding = CHANNEL_REVERSE_STATE
+                .may_load(deps.storage, (&pair.local_channel_id, &ibc_denom))?
+                .unwrap_or_default()
+                .outstanding;
+            if !outstanding.is_zero() {
+                return Err(ContractError::DeleteWithOutstanding {
+                    ibc_denom,
+                    outstanding,
+                });
+            }
+        }
+        if !ibc_denoms.insert(ibc_denom.clone()) {
+            return Err(ContractError::DuplicateMappingPair { ibc_denom });
+        }
+    }
+
+    let mut events = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let ibc_denom = get_key_ics20_ibc_denom(&port_id, &pair.local_channel_id, &pair.denom);
+        delete_mapping_pair(deps.storage, &env, &info.sender, ibc_denom, pair.reason)?;
+        events.push(Event::new("delete_mapping_pair").add_attributes(vec![
+            ("local_channel_id", pair.local_channel_id),
+            ("original_denom", pair.denom),
+        ]));
+    }
+    Ok(Response::new()
+        .add_attribute("action", "batch_delete_mapping_pairs")
+        .add_attribute("count", events.len().to_string())
+        .add_attribute("force", force.to_string())
+        .add_events(events))
+}
+
+fn delete_mapping_pair(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    ibc_denom: String,
+    reason: String,
+) -> Result<(), ContractError> {
+    let before = ics20_denoms().may_load(storage, &ibc_denom)?;
+    remove_mapping_pair(storage, &ibc_denom)?;
+    append_audit_entry(
+        storage,
+        &env.block,
+        sender,
+        AuditAction::DeleteMappingPair,
+        ibc_denom,
+        reason,
+        before.map(|pair| to_json_string(&pair)).transpose()?,
+        None,
+    )?;
+    Ok(())
+}
+
 #[entry_point]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    // we don't need to save anything if migrating from the same version
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = get_contract_version(deps.storage)?;
+    if previous.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: previous.contract,
+        });
+    }
+    // downgrades are refused, the storage may already be in a newer format
+    let previous_version = migrations::parse_version(&previous.version)
+        .filter(|version| Some(*version) <= migrations::parse_version(CONTRACT_VERSION))
+        .ok_or_else(|| ContractError::CannotMigrateVersion {
+            previous_version: previous.version.clone(),
+        })?;
 
-    REFUND_INFO.save(deps.storage, &None)?;
-    REFUND_INFO_LIST.save(deps.storage, &vec![])?;
+    // only optional migrations can be skipped, the others keep the storage consistent with this version
+    if let Some(name) = msg.skip_migrations.iter().find(|skip| {
+        !migrations::MIGRATIONS
+            .iter()
+            .any(|(_, name, optional, _)| *optional && *name == skip.as_str())
+    }) {
+        return Err(ContractError::CannotSkipMigration { name: name.clone() });
+    }
 
-    Ok(Response::new())
+    // only the migrations introduced after the stored version run, in order
+    let mut applied = vec![];
+    for (version, name, _, migration) in migrations::MIGRATIONS {
+        let introduced = migrations::parse_version(version).unwrap_or_default();
+        if introduced <= previous_version || msg.skip_migrations.iter().any(|skip| skip == name) {
+            continue;
+        }
+        migration(deps.storage, &env.block)?;
+        applied.push(*name);
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "migrate"),
+        ("previous_version", &previous.version),
+        ("version", CONTRACT_VERSION),
+        ("migrations", &applied.join(",")),
+    ]))
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Port {} => to_json_binary(&query_port(deps)?),
         QueryMsg::ListChannels {} => to_json_binary(&query_list(deps)?),
         QueryMsg::Channel { id } => to_json_binary(&query_channel(deps, id)?),
-        QueryMsg::ChannelWithKey { channel_id, denom } => {
-            to_json_binary(&query_channel_with_key(deps, channel_id, denom)?)
-        }
+        QueryMsg::ChannelWithKey {
+            channel_id,
+            denom,
+            height,
+        } => to_json_binary(&query_channel_with_key(deps, channel_id, denom, height)?),
+        QueryMsg::ChannelBalanceHistory {
+            channel_id,
+            denom,
+            start_height,
+            end_height,
+            limit,
+        } => to_json_binary(&query_channel_balance_history(
+            deps,
+            channel_id,
+            denom,
+            start_height,
+            end_height,
+            limit,
+        )?),
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::ContractInfo {} => to_json_binary(&query_contract_info(deps)?),
         QueryMsg::Allowed { contract } => to_json_binary(&query_allowed(deps, contract)?),
         QueryMsg::ListAllowed {
             start_after,
             limit,
             order,
         } => to_json_binary(&list_allowed(deps, start_after, limit, order)?),
+        QueryMsg::HookContracts { start_after, limit } => {
+            to_json_binary(&list_hook_contracts(deps, start_after, limit)?)
+        }
         QueryMsg::PairMappings {
             start_after,
             limit,
@@ -884,11 +2174,75 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::PairMappingsFromAssetInfo { asset_info } => {
             to_json_binary(&get_mappings_from_asset_info(deps.storage, asset_info)?)
         }
+        QueryMsg::PairMappingDetails {
+            local_channel_id,
+            is_mint_burn,
+            status,
+            start_after,
+            limit,
+            order,
+        } => to_json_binary(&list_mapping_details(
+            deps,
+            &env,
+            local_channel_id,
+            is_mint_burn,
+            status,
+            start_after,
+            limit,
+            order,
+        )?),
+        QueryMsg::PairMappingDetailsFromAssetInfo {
+            asset_info,
+            start_after,
+            limit,
+            order,
+        } => to_json_binary(&list_mapping_details_from_asset_info(
+            deps,
+            asset_info,
+            start_after,
+            limit,
+            order,
+        )?),
         QueryMsg::Admin {} => to_json_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::Ownership {} => to_json_binary(&query_ownership(deps)?),
+        QueryMsg::Roles { address } => to_json_binary(&query_roles(deps, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_json_binary(&list_role_members(deps, role, start_after, limit)?),
+        QueryMsg::ListRoles { start_after, limit } => {
+            to_json_binary(&list_roles(deps, start_after, limit)?)
+        }
         QueryMsg::GetTransferTokenFee { remote_token_denom } => {
             to_json_binary(&TOKEN_FEE.load(deps.storage, &remote_token_denom)?)
         }
         QueryMsg::RefundInfoList {} => to_json_binary(&query_refund_info_list(deps)?),
+        QueryMsg::Reconcile {
+            channel_id,
+            ibc_denom,
+        } => to_json_binary(&query_reconcile(deps, &env, channel_id, ibc_denom)?),
+        QueryMsg::ReconcileAll { start_after, limit } => {
+            to_json_binary(&query_reconcile_all(deps, &env, start_after, limit)?)
+        }
+        QueryMsg::AuditLog {
+            action,
+            start_after,
+            limit,
+            order,
+        } => to_json_binary(&query_audit_log(deps, action, start_after, limit, order)?),
+        QueryMsg::MintedSupply { asset_info } => to_json_binary(
+            &MINTED_SUPPLY
+                .may_load(deps.storage, &asset_info.to_string())?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::Dust { ibc_denom } => to_json_binary(&query_dust(deps, ibc_denom)?),
+        QueryMsg::TimelockOperation { id } => {
+            to_json_binary(&TIMELOCK_OPERATIONS.load(deps.storage, id)?)
+        }
+        QueryMsg::TimelockOperations { start_after, limit } => {
+            to_json_binary(&list_timelock_operations(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -909,27 +2263,48 @@ fn query_list(deps: Deps) -> StdResult<ListChannelsResponse> {
 // make public for ibc tests
 pub fn query_channel(deps: Deps, id: String) -> StdResult<ChannelResponse> {
     let info = CHANNEL_INFO.load(deps.storage, &id)?;
-    // this returns Vec<(outstanding, total)>
     let channel_state = CHANNEL_REVERSE_STATE;
-    let state = channel_state
+    let states = channel_state
         .prefix(&id)
         .range(deps.storage, None, None, Order::Ascending)
-        .map(|r| {
-            // this denom is
-            r.map(|(denom, v)| {
-                let outstanding = Amount::from_parts(denom.clone(), v.outstanding);
-                let total = Amount::from_parts(denom, v.total_sent);
-                (outstanding, total)
-            })
-        })
         .collect::<StdResult<Vec<_>>>()?;
-    // we want (Vec<outstanding>, Vec<total>)
-    let (balances, total_sent): (Vec<Amount>, Vec<Amount>) = state.into_iter().unzip();
+
+    let mut balances = Vec::with_capacity(states.len());
+    let mut total_sent = Vec::with_capacity(states.len());
+    let mut stats = Vec::with_capacity(states.len());
+    for (denom, v) in states {
+        balances.push(Amount::from_parts(denom.clone(), v.outstanding));
+        total_sent.push(Amount::from_parts(denom.clone(), v.total_sent));
+        stats.push(ChannelStats {
+            denom,
+            total_received: v.total_received,
+            total_returned: v.total_returned,
+            total_refunded: v.total_refunded,
+            last_activity: v.last_activity,
+            transfer_count: v.transfer_count,
+        });
+    }
 
     Ok(ChannelResponse {
         info,
         balances,
         total_sent,
+        stats,
+    })
+}
+
+pub fn query_dust(deps: Deps, ibc_denom: String) -> StdResult<DustResponse> {
+    let pair_mapping = ics20_denoms().load(deps.storage, &ibc_denom)?;
+    let balance = DUST.may_load(deps.storage, &ibc_denom)?.unwrap_or_default();
+    let (from_remote, _) = split_decimals(
+        balance.remote,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )?;
+    Ok(DustResponse {
+        sweepable: balance.local + from_remote,
+        ibc_denom,
+        balance,
     })
 }
 
@@ -937,16 +2312,22 @@ pub fn query_channel_with_key(
     deps: Deps,
     channel_id: String,
     denom: String,
+    height: Option<u64>,
 ) -> StdResult<ChannelWithKeyResponse> {
     let info = CHANNEL_INFO.load(deps.storage, &channel_id)?;
+    let channel_state = match height {
+        // state at the beginning of the block, before any change made at that height
+        Some(height) => {
+            assert_channel_history_height(deps.storage, height)?;
+            CHANNEL_REVERSE_STATE
+                .may_load_at_height(deps.storage, (&channel_id, &denom), height)?
+                .ok_or_else(|| StdError::not_found(type_name::<ChannelState>()))?
+        }
+        None => CHANNEL_REVERSE_STATE.load(deps.storage, (&channel_id, &denom))?,
+    };
     // this returns Vec<(outstanding, total)>
-    let (balance, total_sent) = CHANNEL_REVERSE_STATE
-        .load(deps.storage, (&channel_id, &denom))
-        .map(|channel_state| {
-            let outstanding = Amount::from_parts(denom.clone(), channel_state.outstanding);
-            let total = Amount::from_parts(denom, channel_state.total_sent);
-            (outstanding, total)
-        })?;
+    let balance = Amount::from_parts(denom.clone(), channel_state.outstanding);
+    let total_sent = Amount::from_parts(denom, channel_state.total_sent);
 
     Ok(ChannelWithKeyResponse {
         info,
@@ -955,6 +2336,134 @@ pub fn query_channel_with_key(
     })
 }
 
+// channel states were not snapshotted before CHANNEL_HISTORY_START, a lookup below it would return the
+// current state instead of the one at that height
+fn assert_channel_history_height(storage: &dyn Storage, height: u64) -> StdResult<()> {
+    let history_start = CHANNEL_HISTORY_START.may_load(storage)?.unwrap_or_default();
+    if height < history_start {
+        return Err(StdError::generic_err(format!(
+            "Channel history is only available from height {}",
+            history_start
+        )));
+    }
+    Ok(())
+}
+
+/// Lists the changes of a channel state, at most one per block, from the changelog of the snapshot map
+pub fn query_channel_balance_history(
+    deps: Deps,
+    channel_id: String,
+    denom: String,
+    start_height: Option<u64>,
+    end_height: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ChannelBalanceHistoryResponse> {
+    if let Some(start_height) = start_height {
+        assert_channel_history_height(deps.storage, start_height)?;
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_height.map(Bound::inclusive);
+
+    // the changelog only stores the state before each change, so we load one more entry to know the state
+    // after the last change we return. We don't bound the end for the same reason
+    let changelog = CHANNEL_REVERSE_STATE
+        .changelog()
+        .prefix((&channel_id, &denom))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut changes = vec![];
+    for (i, (height, change)) in changelog.iter().take(limit).enumerate() {
+        if end_height.map_or(false, |end_height| *height > end_height) {
+            break;
+        }
+        let after = match changelog.get(i + 1) {
+            Some((_, next_change)) => next_change.old.clone(),
+            // no later change, so the current state is the one after this change
+            None => CHANNEL_REVERSE_STATE.may_load(deps.storage, (&channel_id, &denom))?,
+        };
+        changes.push(ChannelBalanceChange {
+            height: *height,
+            before: change.old.clone(),
+            after,
+        });
+    }
+
+    Ok(ChannelBalanceHistoryResponse {
+        channel_id,
+        denom,
+        changes,
+    })
+}
+
+pub fn query_reconcile(
+    deps: Deps,
+    env: &Env,
+    channel_id: String,
+    ibc_denom: String,
+) -> StdResult<ReconcileResponse> {
+    let pair_mapping = ics20_denoms().load(deps.storage, &ibc_denom)?;
+    let state = CHANNEL_REVERSE_STATE
+        .may_load(deps.storage, (&channel_id, &ibc_denom))?
+        .unwrap_or_default();
+
+    let outstanding = convert_outstanding_to_local(state.outstanding, &pair_mapping);
+    let total_outstanding = get_local_outstanding_of_asset(deps.storage, &pair_mapping.asset_info)?;
+    let holdings = query_asset_holdings(
+        &deps.querier,
+        &env.contract.address,
+        &pair_mapping.asset_info,
+        pair_mapping.is_mint_burn,
+    )?;
+
+    Ok(ReconcileResponse {
+        channel_id,
+        ibc_denom,
+        asset_info: pair_mapping.asset_info,
+        is_mint_burn: pair_mapping.is_mint_burn,
+        outstanding,
+        total_outstanding,
+        holdings,
+        surplus: holdings.saturating_sub(total_outstanding),
+        deficit: total_outstanding.saturating_sub(holdings),
+    })
+}
+
+pub fn query_reconcile_all(
+    deps: Deps,
+    env: &Env,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<ListReconcileResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(channel_id, ibc_denom)| Bound::exclusive((channel_id.as_str(), ibc_denom.as_str())));
+
+    // the page is bounded by the channel states scanned, not the ones returned, so a page may hold fewer entries
+    let keys = CHANNEL_REVERSE_STATE
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if keys.len() == limit {
+        keys.last().cloned()
+    } else {
+        None
+    };
+
+    let entries = keys
+        .into_iter()
+        // channel states without a mapping cannot be converted to a local asset, skip them
+        .filter(|(_, ibc_denom)| ics20_denoms().has(deps.storage, ibc_denom))
+        .map(|(channel_id, ibc_denom)| query_reconcile(deps, env, channel_id, ibc_denom))
+        .collect::<StdResult<_>>()?;
+    Ok(ListReconcileResponse {
+        entries,
+        next_start_after,
+    })
+}
+
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     let admin = ADMIN.get(deps)?.unwrap_or_else(|| Addr::unchecked(""));
@@ -982,10 +2491,26 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .collect::<StdResult<_>>()?,
         converter_contract: cfg.converter_contract.addr(),
         osor_entrypoint_contract: cfg.osor_entrypoint_contract,
+        token_factory_addr: cfg.token_factory_addr,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+        timelock_delay: TIMELOCK_DELAY
+            .may_load(deps.storage)?
+            .unwrap_or(DEFAULT_TIMELOCK_DELAY),
     };
     Ok(res)
 }
 
+fn query_contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
+    let version = get_contract_version(deps.storage)?;
+    Ok(ContractInfoResponse {
+        contract: version.contract,
+        version: version.version,
+        features: FEATURES.iter().map(|feature| feature.to_string()).collect(),
+        channel_count: CHANNEL_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        mapping_count: MAPPING_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
 fn query_allowed(deps: Deps, contract: String) -> StdResult<AllowedResponse> {
     let addr = deps.api.addr_validate(&contract)?;
     let info = ALLOW_LIST.may_load(deps.storage, &addr)?;
@@ -1005,6 +2530,8 @@ fn query_allowed(deps: Deps, contract: String) -> StdResult<AllowedResponse> {
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
+// keeps the decimal conversions of a mapping within u128
+const MAX_DECIMALS: u8 = 18;
 
 fn list_allowed(
     deps: Deps,
@@ -1029,6 +2556,85 @@ fn list_allowed(
     Ok(ListAllowedResponse { allow })
 }
 
+fn list_hook_contracts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    HOOK_CONTRACT_ALLOW_LIST
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|addr| addr.into_string()))
+        .collect()
+}
+
+fn query_ownership(deps: Deps) -> StdResult<OwnershipResponse> {
+    Ok(OwnershipResponse {
+        owner: ADMIN.get(deps)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+    })
+}
+
+fn query_roles(deps: Deps, address: String) -> StdResult<RolesResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let mut roles = vec![];
+    for role in Role::ALL {
+        if has_role(deps, &address, role)? {
+            roles.push(role);
+        }
+    }
+    Ok(RolesResponse { roles })
+}
+
+// members granted the role explicitly, the owner is not listed
+fn list_role_members(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|addr| addr.into_string()))
+        .collect()
+}
+
+fn list_roles(
+    deps: Deps,
+    start_after: Option<(Role, String)>,
+    limit: Option<u32>,
+) -> StdResult<ListRolesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|(role, addr)| -> StdResult<_> { Ok((role, deps.api.addr_validate(&addr)?)) })
+        .transpose()?;
+    let start = start_after
+        .as_ref()
+        .map(|(role, addr)| Bound::exclusive((role.as_str(), addr)));
+
+    let grants = ROLES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (role, address) = item?;
+            let role = Role::parse(&role)
+                .ok_or_else(|| StdError::generic_err(format!("unknown role {}", role)))?;
+            Ok(RoleGrant { role, address })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(ListRolesResponse { grants })
+}
+
 fn list_cw20_mapping(
     deps: Deps,
     start_after: Option<String>,
@@ -1053,6 +2659,180 @@ fn list_cw20_mapping(
     Ok(ListMappingResponse { pairs })
 }
 
+#[allow(clippy::too_many_arguments)]
+fn list_mapping_details(
+    deps: Deps,
+    env: &Env,
+    local_channel_id: Option<String>,
+    is_mint_burn: Option<bool>,
+    status: Option<MappingStatus>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<u8>,
+) -> StdResult<ListMappingDetailResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = map_order(order);
+    // keys are <port>/<channel>/<denom>, so the pairs of a channel sit between "<port>/<channel>/"
+    // and "<port>/<channel>0", '0' being the character after '/'
+    let port_id = parse_ibc_wasm_port_id(env.contract.address.as_str());
+    let channel_start = local_channel_id
+        .as_ref()
+        .map(|channel_id| format!("{}/{}/", port_id, channel_id));
+    let channel_end = local_channel_id
+        .as_ref()
+        .map(|channel_id| format!("{}/{}0", port_id, channel_id));
+    let (min, max) = match order {
+        Order::Ascending => {
+            let min = match (start_after.as_deref(), channel_start.as_deref()) {
+                (Some(start), Some(channel_start)) if start < channel_start => {
+                    Some(Bound::inclusive(channel_start))
+                }
+                (Some(start), _) => Some(Bound::exclusive(start)),
+                (None, channel_start) => channel_start.map(Bound::inclusive),
+            };
+            (min, channel_end.as_deref().map(Bound::exclusive))
+        }
+        Order::Descending => {
+            let max = match (start_after.as_deref(), channel_end.as_deref()) {
+                (Some(start), Some(channel_end)) if start > channel_end => {
+                    Some(Bound::exclusive(channel_end))
+                }
+                (Some(start), _) => Some(Bound::exclusive(start)),
+                (None, channel_end) => channel_end.map(Bound::exclusive),
+            };
+            (channel_start.as_deref().map(Bound::inclusive), max)
+        }
+    };
+    // the page is bounded by the mappings scanned, not the ones returned, so a page may hold fewer pairs
+    let mappings = ics20_denoms()
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if mappings.len() == limit {
+        mappings.last().map(|(key, _)| key.clone())
+    } else {
+        None
+    };
+
+    let pairs = mappings
+        .into_iter()
+        .filter(|(_, pair_mapping)| {
+            is_mint_burn.map_or(true, |mode| pair_mapping.is_mint_burn == mode)
+                && status
+                    .as_ref()
+                    .map_or(true, |status| &pair_mapping.status == status)
+        })
+        .map(|(key, pair_mapping)| mapping_detail(deps, key, pair_mapping))
+        .collect::<StdResult<_>>()?;
+    Ok(ListMappingDetailResponse {
+        pairs,
+        next_start_after,
+    })
+}
+
+fn list_mapping_details_from_asset_info(
+    deps: Deps,
+    asset_info: AssetInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<u8>,
+) -> StdResult<ListMappingDetailResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = map_order(order);
+    let (min, max) = match order {
+        Order::Ascending => (start_after.map(Bound::exclusive), None),
+        Order::Descending => (None, start_after.map(Bound::exclusive)),
+    };
+    let pairs: Vec<_> = ics20_denoms()
+        .idx
+        .asset_info
+        .prefix(asset_info.to_string())
+        .range(deps.storage, min, max, order)
+        .take(limit)
+        .map(|item| item.and_then(|(key, pair_mapping)| mapping_detail(deps, key, pair_mapping)))
+        .collect::<StdResult<_>>()?;
+    let next_start_after = if pairs.len() == limit {
+        pairs.last().map(|pair| pair.key.clone())
+    } else {
+        None
+    };
+    Ok(ListMappingDetailResponse {
+        pairs,
+        next_start_after,
+    })
+}
+
+// joins a mapping with the balance of its channel and the token fee of its remote denom
+fn mapping_detail(
+    deps: Deps,
+    key: String,
+    pair_mapping: MappingMetadata,
+) -> StdResult<PairMappingDetail> {
+    let (_, channel_id, remote_denom) = parse_ibc_info_without_sanity_checks(&key)?;
+    let state = CHANNEL_REVERSE_STATE
+        .may_load(deps.storage, (channel_id, &key))?
+        .unwrap_or_default();
+    let token_fee = TOKEN_FEE.may_load(deps.storage, remote_denom)?;
+    Ok(PairMappingDetail {
+        outstanding: state.outstanding,
+        total_sent: state.total_sent,
+        token_fee,
+        key,
+        pair_mapping,
+    })
+}
+
+fn query_audit_log(
+    deps: Deps,
+    action: Option<AuditAction>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    order: Option<u8>,
+) -> StdResult<ListAuditLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = map_order(order);
+    let (min, max) = match order {
+        Order::Ascending => (start_after.map(Bound::exclusive), None),
+        Order::Descending => (None, start_after.map(Bound::exclusive)),
+    };
+    let entries = match action {
+        Some(action) => audit_log()
+            .idx
+            .action
+            .prefix(action.to_string())
+            .range(deps.storage, min, max, order)
+            .take(limit)
+            .map(|item| item.map(|(_, entry)| entry))
+            .collect::<StdResult<_>>()?,
+        None => audit_log()
+            .range(deps.storage, min, max, order)
+            .take(limit)
+            .map(|item| item.map(|(_, entry)| entry))
+            .collect::<StdResult<_>>()?,
+    };
+    Ok(ListAuditLogResponse { entries })
+}
+
+fn list_timelock_operations(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TimelockOperationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let operations = TIMELOCK_OPERATIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, operation)| operation))
+        .collect::<StdResult<_>>()?;
+    Ok(TimelockOperationsResponse {
+        delay: TIMELOCK_DELAY
+            .may_load(deps.storage)?
+            .unwrap_or(DEFAULT_TIMELOCK_DELAY),
+        operations,
+    })
+}
+
 fn get_mapping_from_key(deps: Deps, ibc_denom: String) -> StdResult<PairQuery> {
     let result = ics20_denoms().load(deps.storage, &ibc_denom)?;
     Ok(PairQuery {
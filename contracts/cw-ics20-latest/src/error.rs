@@ -2,10 +2,12 @@ use std::num::TryFromIntError;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{OverflowError, StdError, Timestamp, Uint128};
 use cw_controllers::AdminError;
 use cw_utils::PaymentError;
 
+use crate::state::Role;
+
 /// Never is a placeholder to ensure we don't return any errors
 #[derive(Error, Debug)]
 pub enum Never {}
@@ -60,6 +62,9 @@ pub enum ContractError {
     #[error("Cannot migrate from unsupported version: {previous_version}")]
     CannotMigrateVersion { previous_version: String },
 
+    #[error("Migration {name} is not optional and cannot be skipped")]
+    CannotSkipMigration { name: String },
+
     #[error("Got a submessage reply with unknown id: {id}")]
     UnknownReplyId { id: u64 },
 
@@ -86,6 +91,148 @@ pub enum ContractError {
 
     #[error("User cannot close channel")]
     CannotClose {},
+
+    #[error(
+        "Minted supply {minted} of {asset} would exceed its outstanding balance {outstanding}"
+    )]
+    MintExceedsOutstanding {
+        asset: String,
+        minted: Uint128,
+        outstanding: Uint128,
+    },
+
+    #[error("A reason is required for admin interventions")]
+    EmptyAuditReason {},
+
+    #[error("Minted supply {minted} of {asset} would exceed its supply cap {supply_cap}")]
+    SupplyCapExceeded {
+        asset: String,
+        minted: Uint128,
+        supply_cap: Uint128,
+    },
+
+    #[error(
+        "Cannot change the decimals of {denom} while {outstanding} is outstanding on channel {channel_id}"
+    )]
+    DecimalsChangeWithOutstanding {
+        channel_id: String,
+        denom: String,
+        outstanding: Uint128,
+    },
+
+    #[error("Amount {amount} cannot be converted exactly, {dust} would be lost as dust")]
+    InexactAmount { amount: Uint128, dust: Uint128 },
+
+    #[error("No dust to sweep for {ibc_denom}")]
+    NoDustToSweep { ibc_denom: String },
+
+    #[error("Receiving is disabled for mapping {ibc_denom}")]
+    MappingReceiveDisabled { ibc_denom: String },
+
+    #[error("Sending is disabled for mapping {ibc_denom}")]
+    MappingSendDisabled { ibc_denom: String },
+
+    #[error("Transfer amount {amount} is below the minimum {min_amount}")]
+    AmountBelowMinimum {
+        amount: Uint128,
+        min_amount: Uint128,
+    },
+
+    #[error("Transfer amount {amount} is above the maximum {max_amount}")]
+    AmountAboveMaximum {
+        amount: Uint128,
+        max_amount: Uint128,
+    },
+
+    #[error("Minimum amount {min_amount} is greater than maximum amount {max_amount}")]
+    InvalidAmountBounds {
+        min_amount: Uint128,
+        max_amount: Uint128,
+    },
+
+    #[error("Batch must contain at least one entry")]
+    EmptyBatch {},
+
+    #[error("Mapping pair {ibc_denom} appears more than once in the batch")]
+    DuplicateMappingPair { ibc_denom: String },
+
+    #[error("Cannot delete mapping pair {ibc_denom} with outstanding balance {outstanding}")]
+    DeleteWithOutstanding {
+        ibc_denom: String,
+        outstanding: Uint128,
+    },
+
+    #[error(
+        "No mapping for remote prefix {remote_prefix} can transfer {amount}. Rejected candidates: \
+         [{candidates}]"
+    )]
+    NoTransferRoute {
+        remote_prefix: String,
+        amount: Uint128,
+        candidates: String,
+    },
+
+    #[error("Contract {contract} is not allowed to be called by ibc hooks")]
+    HookContractNotAllowed { contract: String },
+
+    #[error("Invalid memo: {reason}")]
+    InvalidMemo { reason: String },
+
+    #[error("Amount {amount} after fees is less than the minimum output {min_out} of the memo")]
+    MinOutNotMet { amount: Uint128, min_out: Uint128 },
+
+    #[error("Unauthorized: sender does not have the {role} role")]
+    MissingRole { role: Role },
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Only the pending owner can accept the ownership")]
+    NotPendingOwner {},
+
+    #[error("Timelocked operation {id} not found")]
+    TimelockNotFound { id: u64 },
+
+    #[error("Timelocked operation {id} cannot be executed before {executable_at}")]
+    TimelockNotReady { id: u64, executable_at: Timestamp },
+
+    #[error("Cannot withdraw {amount} {denom}, only a surplus of {surplus} is not owed to users")]
+    WithdrawExceedsSurplus {
+        denom: String,
+        amount: Uint128,
+        surplus: Uint128,
+    },
+
+    #[error("The callback contract {callback} must be the sender {sender} of the transfer")]
+    CallbackNotSender { callback: String, sender: String },
+
+    #[error("Cannot rescale {amount} of {denom} on channel {channel_id} without losing precision")]
+    InexactRescale {
+        channel_id: String,
+        denom: String,
+        amount: Uint128,
+    },
+
+    #[error("Cannot rescale {denom} on channel {channel_id} while {pending} packets are pending")]
+    RescaleWithPendingPackets {
+        channel_id: String,
+        denom: String,
+        pending: u64,
+    },
+
+    #[error(
+        "Cannot change the asset or mint-burn mode of {denom} while {outstanding} is outstanding \
+         and {pending} packets are pending on channel {channel_id}"
+    )]
+    AssetChangeWithOutstanding {
+        channel_id: String,
+        denom: String,
+        outstanding: Uint128,
+        pending: u64,
+    },
+
+    #[error("Decimals {decimals} are greater than the maximum of {max}")]
+    DecimalsTooLarge { decimals: u8, max: u8 },
 }
 
 impl From<FromUtf8Error> for ContractError {
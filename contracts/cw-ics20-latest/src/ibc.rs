@@ -3,11 +3,11 @@ use std::ops::Mul;
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
     attr, entry_point, from_json, to_json_binary, wasm_execute, Api, Binary, Coin, CosmosMsg,
-    Decimal, Deps, DepsMut, Env, Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel,
+    Decimal, DepsMut, Env, Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel,
     IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcMsg, IbcOrder,
     IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse,
     IbcTimeout, Order, QuerierWrapper, Reply, Response, StdError, StdResult, Storage, SubMsg,
-    SubMsgResult, Uint128,
+    SubMsgResponse, SubMsgResult, Uint128,
 };
 
 use cw20_ics20_msg::helper::{
@@ -22,12 +22,16 @@ use crate::contract::build_mint_mapping_msg;
 use crate::error::{ContractError, Never};
 use crate::msg::{ExecuteMsg, RegisterDenomMsg};
 use crate::state::{
-    get_key_ics20_ibc_denom, ics20_denoms, undo_reduce_channel_balance, RefundInfo, ALLOW_LIST,
-    CHANNEL_INFO, CONFIG, REFUND_INFO, REFUND_INFO_LIST, RELAYER_FEE, TOKEN_FEE,
+    assert_minted_supply, assert_transfer_amount, get_key_ics20_ibc_denom, ics20_denoms,
+    restore_minted_supply, save_channel_info, save_mapping_pair, save_pending_callback,
+    settle_pending_packet, take_pending_callback, undo_reduce_channel_balance, RefundInfo,
+    ALLOW_LIST, CHANNEL_INFO, CONFIG, MINTED_SUPPLY, REFUND_INFO, REFUND_INFO_LIST, RELAYER_FEE,
+    SEND_PACKET_CALLBACK, TOKEN_FEE,
 };
-use cw20_ics20_msg::amount::{convert_remote_to_local, Amount};
-use cw20_ics20_msg::msg::FeeData;
-use cw20_ics20_msg::state::{ChannelInfo, MappingMetadata, Ratio};
+use cw20_ics20_msg::amount::{convert_remote_to_local, convert_remote_to_local_exact, Amount};
+use cw20_ics20_msg::memo::{MemoAction, OraichainMemo};
+use cw20_ics20_msg::msg::{FeeData, IbcLifecycleCallbackMsg};
+use cw20_ics20_msg::state::{ChannelInfo, DustPolicy, MappingMetadata, MappingStatus, Ratio};
 
 pub const ICS20_VERSION: &str = "ics20-1";
 pub const ICS20_ORDERING: IbcOrder = IbcOrder::Unordered;
@@ -92,12 +96,18 @@ pub fn ack_fail(err: String) -> Binary {
 pub const NATIVE_RECEIVE_ID: u64 = 1338;
 pub const REFUND_FAILURE_ID: u64 = 1340;
 pub const UNIVERSAL_SWAP_ERROR_ID: u64 = 1344;
+pub const LIFECYCLE_CALLBACK_FAILURE_ID: u64 = 1345;
+pub const SEND_PACKET_CALLBACK_ID: u64 = 1346;
+
+// gas of a lifecycle callback or a memo contract call when the config has no default gas limit. Running
+// out of gas without a limit would abort the whole ack, timeout or receive instead of failing the call
+pub const DEFAULT_CALLBACK_GAS_LIMIT: u64 = 500_000;
 
 #[entry_point]
 pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
     match reply.result {
         SubMsgResult::Err(err) => handle_reply_error(deps, err, reply.id),
-        SubMsgResult::Ok(_) => handle_reply_success(deps, reply.id),
+        SubMsgResult::Ok(res) => handle_reply_success(deps, res, reply.id),
     }
 }
 
@@ -134,23 +144,64 @@ fn handle_reply_error(deps: DepsMut, err: String, id: u64) -> Result<Response, C
                 .add_attribute("error_trying_to_call_entrypoint_for_universal_swap", err))
         }
 
+        // the ack or timeout is still processed when the callback contract fails
+        LIFECYCLE_CALLBACK_FAILURE_ID => Ok(Response::new()
+            .add_attribute("action", "lifecycle_callback_failure")
+            .add_attribute("error_trying_to_call_lifecycle_callback", err)),
+
         _ => Err(ContractError::UnknownReplyId { id }),
     }
 }
 
-fn handle_reply_success(deps: DepsMut, id: u64) -> Result<Response, ContractError> {
+fn handle_reply_success(
+    deps: DepsMut,
+    res: SubMsgResponse,
+    id: u64,
+) -> Result<Response, ContractError> {
     match id {
         NATIVE_RECEIVE_ID | REFUND_FAILURE_ID => {
             REFUND_INFO.save(deps.storage, &None)?;
             Ok(Response::default())
         }
 
-        UNIVERSAL_SWAP_ERROR_ID => Ok(Response::default()),
+        UNIVERSAL_SWAP_ERROR_ID | LIFECYCLE_CALLBACK_FAILURE_ID => Ok(Response::default()),
+
+        // the packet has been sent, its callback now waits for the ack or timeout of its sequence
+        SEND_PACKET_CALLBACK_ID => {
+            let callback = SEND_PACKET_CALLBACK.load(deps.storage)?;
+            SEND_PACKET_CALLBACK.remove(deps.storage);
+            let (channel_id, sequence) = parse_send_packet_event(&res)?;
+            save_pending_callback(deps.storage, &channel_id, sequence, &callback)?;
+            Ok(Response::new()
+                .add_attribute("action", "save_lifecycle_callback")
+                .add_attribute("packet_sequence", sequence.to_string()))
+        }
 
         _ => Err(ContractError::UnknownReplyId { id }),
     }
 }
 
+// reads the source channel and the sequence of a sent packet from the send_packet event of the ibc module
+fn parse_send_packet_event(res: &SubMsgResponse) -> StdResult<(String, u64)> {
+    let event = res
+        .events
+        .iter()
+        .find(|event| event.ty == "send_packet")
+        .ok_or_else(|| StdError::generic_err("Missing send_packet event"))?;
+    let attribute = |key: &str| {
+        event
+            .attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .ok_or_else(|| StdError::generic_err(format!("Missing send_packet attribute {}", key)))
+    };
+    let sequence = attribute("packet_sequence")?
+        .parse::<u64>()
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok((attribute("packet_src_channel")?, sequence))
+}
+
 #[entry_point]
 /// enforces ordering and versioning constraints
 pub fn ibc_channel_open(
@@ -178,7 +229,7 @@ pub fn ibc_channel_connect(
         counterparty_endpoint: channel.counterparty_endpoint,
         connection_id: channel.connection_id,
     };
-    CHANNEL_INFO.save(deps.storage, &info.id, &info)?;
+    save_channel_info(deps.storage, &info)?;
 
     Ok(IbcBasicResponse::default())
 }
@@ -353,6 +404,21 @@ fn handle_ibc_packet_receive_native_remote_chain(
     relayer: &str,
 ) -> Result<IbcReceiveResponse, ContractError> {
     let config = CONFIG.load(storage)?;
+    // a json memo is parsed first so that a malformed one fails the packet before any state changes
+    let memo = msg
+        .memo
+        .as_deref()
+        .and_then(OraichainMemo::parse)
+        .transpose()
+        .map_err(|err| ContractError::InvalidMemo {
+            reason: err.to_string(),
+        })?;
+    if let Some(receiver) = memo.as_ref().and_then(|memo| memo.receiver.as_ref()) {
+        api.addr_validate(receiver)
+            .map_err(|err| ContractError::InvalidMemo {
+                reason: err.to_string(),
+            })?;
+    }
     let mut cosmos_msgs: Vec<CosmosMsg> = vec![];
     let ibc_packet_amount = msg.amount.to_string();
     let attributes: Vec<(&str, &str)> = vec![
@@ -367,8 +433,8 @@ fn handle_ibc_packet_receive_native_remote_chain(
 
     // key in form transfer/channel-0/foo
     let ibc_denom = get_key_ics20_ibc_denom(&packet.dest.port_id, &packet.dest.channel_id, denom);
-    let pair_mapping = match ics20_denoms().load(storage, &ibc_denom) {
-        Ok(pair_mapping) => pair_mapping,
+    let (pair_mapping, auto_registered) = match ics20_denoms().load(storage, &ibc_denom) {
+        Ok(pair_mapping) => (pair_mapping, false),
         Err(_) => {
             let (prefix, denom) =
                 denom
@@ -403,21 +469,47 @@ fn handle_ibc_packet_receive_native_remote_chain(
                 remote_decimals: 1, // Since we don't know metadata of remote_chain token, we set it to 1 in both decimals
                 asset_info_decimals: 1,
                 is_mint_burn: true, // Always mint burn if we don't know the metadata
+                supply_cap: None,
+                dust_policy: DustPolicy::default(),
+                status: MappingStatus::default(),
+                min_amount: None,
+                max_amount: None,
             };
-            ics20_denoms().save(storage, &ibc_denom, &new_metadata)?;
-            new_metadata
+            (new_metadata, true)
         }
     };
-    let initial_receive_asset_info = pair_mapping.asset_info;
+    // fail the packet so the remote chain refunds the sender
+    if !pair_mapping.status.can_receive() {
+        return Err(ContractError::MappingReceiveDisabled { ibc_denom });
+    }
+    assert_transfer_amount(&pair_mapping, msg.amount)?;
+    let initial_receive_asset_info = pair_mapping.asset_info.clone();
+    let (local_amount, dust) = convert_remote_to_local_exact(
+        msg.amount,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )?;
+    // other policies keep or drop the dust when the channel balance is increased
+    if !dust.is_zero() && pair_mapping.dust_policy == DustPolicy::Reject {
+        return Err(ContractError::InexactAmount {
+            amount: msg.amount,
+            dust,
+        });
+    }
     let to_send = Amount::from_parts(
         parse_asset_info_denom(&initial_receive_asset_info),
-        convert_remote_to_local(
-            msg.amount,
-            pair_mapping.remote_decimals,
-            pair_mapping.asset_info_decimals,
-        )?,
+        local_amount,
     );
 
+    // fail the packet early if the mint would break the supply invariant. The channel balance is only increased in the self-call below
+    if pair_mapping.is_mint_burn {
+        let minted = MINTED_SUPPLY
+            .may_load(storage, &initial_receive_asset_info.to_string())?
+            .unwrap_or_default()
+            .checked_add(to_send.amount())?;
+        assert_minted_supply(storage, &pair_mapping, minted, to_send.amount())?;
+    }
+
     // increase channel balance submsg. We increase it first before doing other tasks
     cosmos_msgs.push(
         wasm_execute(
@@ -443,6 +535,20 @@ fn handle_ibc_packet_receive_native_remote_chain(
         &config.swap_router_contract,
     )?;
 
+    if let Some(min_out) = memo.as_ref().and_then(|memo| memo.min_out) {
+        if fee_data.deducted_amount < min_out {
+            return Err(ContractError::MinOutNotMet {
+                amount: fee_data.deducted_amount,
+                min_out,
+            });
+        }
+    }
+    // the mapping is only saved once the packet passed every check, a failed packet drops the register denom
+    // msg and must not leave a mapping of a denom that was never created
+    if auto_registered {
+        save_mapping_pair(storage, &ibc_denom, &pair_mapping)?;
+    }
+
     // if the fees have consumed all user funds, we send all the fees to our token fee receiver
     if fee_data.deducted_amount.is_zero() {
         return Ok(IbcReceiveResponse::new()
@@ -466,13 +572,47 @@ fn handle_ibc_packet_receive_native_remote_chain(
         cosmos_msgs.push(fee_data.relayer_fee.send_amount(relayer.to_string(), None))
     }
     let new_deducted_to_send = Amount::from_parts(to_send.denom(), fee_data.deducted_amount);
-    let sub_msgs = get_follow_up_msgs(
-        storage,
-        api,
-        msg.receiver.clone(),
-        new_deducted_to_send,
-        msg.memo.clone(),
-    )?;
+    // json memos pick the follow up action explicitly, other memos are forwarded as they are
+    let sub_msgs = match memo {
+        Some(memo) => {
+            let receiver = memo.receiver.unwrap_or_else(|| msg.receiver.clone());
+            match memo.action {
+                MemoAction::Transfer => {
+                    get_follow_up_msgs(storage, api, receiver, new_deducted_to_send, None)?
+                }
+                MemoAction::UniversalSwap => get_follow_up_msgs(
+                    storage,
+                    api,
+                    receiver,
+                    new_deducted_to_send,
+                    memo.swap_memo,
+                )?,
+                // the call runs with the receive, if it fails the whole receive is reverted and acked with
+                // an error, so the sender is refunded on the remote chain. It is always capped, so running
+                // out of gas fails the call instead of aborting the receive
+                MemoAction::ContractCall => {
+                    let gas_limit = match get_gas_limit(storage, &new_deducted_to_send)? {
+                        Some(gas_limit) => gas_limit,
+                        None => CONFIG
+                            .load(storage)?
+                            .default_gas_limit
+                            .unwrap_or(DEFAULT_CALLBACK_GAS_LIMIT),
+                    };
+                    vec![SubMsg {
+                        gas_limit: Some(gas_limit),
+                        ..SubMsg::new(new_deducted_to_send.send_amount(receiver, memo.msg))
+                    }]
+                }
+            }
+        }
+        None => get_follow_up_msgs(
+            storage,
+            api,
+            msg.receiver.clone(),
+            new_deducted_to_send,
+            msg.memo.clone(),
+        )?,
+    };
 
     let res = IbcReceiveResponse::new()
         .set_ack(ack_success())
@@ -496,10 +636,13 @@ pub fn get_follow_up_msgs(
 ) -> Result<Vec<SubMsg>, ContractError> {
     let config = CONFIG.load(storage)?;
     let mut sub_msgs: Vec<SubMsg> = vec![];
-    let send_only_sub_msg = SubMsg::reply_always(
-        to_send.send_amount(orai_receiver.clone(), None),
-        NATIVE_RECEIVE_ID,
-    );
+    let send_only_sub_msg = SubMsg {
+        gas_limit: get_gas_limit(storage, &to_send)?,
+        ..SubMsg::reply_always(
+            to_send.send_amount(orai_receiver.clone(), None),
+            NATIVE_RECEIVE_ID,
+        )
+    };
     let mut will_universal_swap = true;
     if memo.is_none()
         || memo.clone().unwrap().is_empty()
@@ -522,27 +665,38 @@ pub fn get_follow_up_msgs(
                 memo: memo.unwrap(),
             })?),
         );
-        // currently, we not auto refund with universal swap
+        // currently, we not auto refund with universal swap. The cw20 gas limit is not applied here, it
+        // would also limit the swap that the send triggers
         let sub_msg = SubMsg::reply_on_error(swap_then_post_action_msg, UNIVERSAL_SWAP_ERROR_ID);
         sub_msgs.push(sub_msg);
     }
     Ok(sub_msgs)
 }
 
-pub fn check_gas_limit(deps: Deps, amount: &Amount) -> Result<Option<u64>, ContractError> {
+/// Gas limit of submessages executing the cw20 contract of the amount, so a malicious cw20 cannot burn the relayer's gas.
+/// Contracts on the allow list use their own limit, others use the default one or are not limited
+/// without it. Native amounts are not limited. Receives and refunds use it, they must not fail
+pub fn get_gas_limit(storage: &dyn Storage, amount: &Amount) -> StdResult<Option<u64>> {
     match amount {
-        Amount::Cw20(coin) => {
-            // if cw20 token, use the registered gas limit, or error if not whitelisted
-            let addr = deps.api.addr_validate(coin.address.as_str())?;
-            let allowed = ALLOW_LIST.may_load(deps.storage, &addr)?;
-            match allowed {
-                Some(allow) => Ok(allow.gas_limit),
-                None => match CONFIG.load(deps.storage)?.default_gas_limit {
-                    Some(base) => Ok(Some(base)),
-                    None => Err(ContractError::NotOnAllowList),
-                },
-            }
-        }
+        Amount::Cw20(coin) => match ALLOW_LIST.may_load(storage, &coin.address)? {
+            Some(allow) => Ok(allow.gas_limit),
+            None => Ok(CONFIG.load(storage)?.default_gas_limit),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Same as get_gas_limit, but cw20 contracts off the allow list are rejected even with a default gas limit.
+/// Transfers to the remote chain use it, so only allowed cw20 tokens can be bridged
+pub fn check_gas_limit(
+    storage: &dyn Storage,
+    amount: &Amount,
+) -> Result<Option<u64>, ContractError> {
+    match amount {
+        Amount::Cw20(coin) => ALLOW_LIST
+            .may_load(storage, &coin.address)?
+            .map(|allow| allow.gas_limit)
+            .ok_or(ContractError::NotOnAllowList),
         _ => Ok(None),
     }
 }
@@ -717,7 +871,7 @@ pub fn collect_fee_msgs(
 /// This entrypoint is called when we receive an acknowledgement packet from a remote chain
 pub fn ibc_packet_ack(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     // Design decision: should we trap error like in receive?
@@ -725,7 +879,7 @@ pub fn ibc_packet_ack(
     let ics20msg: Ics20Ack = from_json(&msg.acknowledgement.data)?;
     match ics20msg {
         Ics20Ack::Result(_) => on_packet_success(deps, msg.original_packet),
-        Ics20Ack::Error(err) => on_packet_failure(deps, msg.original_packet, err),
+        Ics20Ack::Error(err) => on_packet_failure(deps, env, msg.original_packet, err),
     }
 }
 
@@ -733,16 +887,49 @@ pub fn ibc_packet_ack(
 /// return fund to original sender (same as failure in ibc_packet_ack)
 pub fn ibc_packet_timeout(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     let packet = msg.packet;
-    on_packet_failure(deps, packet, "timeout".to_string())
+    on_packet_failure(deps, env, packet, "timeout".to_string())
+}
+
+// notifies the callback contract of the transfer, if it has one
+fn build_lifecycle_callback_msg(
+    storage: &mut dyn Storage,
+    packet: &IbcPacket,
+    success: bool,
+    refunded_amount: Uint128,
+) -> Result<Option<SubMsg>, ContractError> {
+    let Some(callback) = take_pending_callback(storage, &packet.src.channel_id, packet.sequence)?
+    else {
+        return Ok(None);
+    };
+    let callback_msg = wasm_execute(
+        callback,
+        &IbcLifecycleCallbackMsg::IbcLifecycleComplete {
+            channel_id: packet.src.channel_id.clone(),
+            sequence: packet.sequence,
+            success,
+            refunded_amount,
+        },
+        vec![],
+    )?;
+    let gas_limit = CONFIG
+        .load(storage)?
+        .default_gas_limit
+        .unwrap_or(DEFAULT_CALLBACK_GAS_LIMIT);
+    Ok(Some(SubMsg {
+        gas_limit: Some(gas_limit),
+        ..SubMsg::reply_on_error(callback_msg, LIFECYCLE_CALLBACK_FAILURE_ID)
+    }))
 }
 
 // update the balance stored on this (channel, denom) index
-fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
-    let msg: Ics20Packet = from_json(packet.data)?;
+fn on_packet_success(deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
+    let msg: Ics20Packet = from_json(&packet.data)?;
+    settle_pending_packet(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
+    let callback_msg = build_lifecycle_callback_msg(deps.storage, &packet, true, Uint128::zero())?;
 
     // similar event messages like ibctransfer module
     let attributes = vec![
@@ -758,7 +945,9 @@ fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicRespon
     //     attributes.push(attr("memo", memo));
     // }
 
-    Ok(IbcBasicResponse::new().add_attributes(attributes))
+    Ok(IbcBasicResponse::new()
+        .add_submessages(callback_msg)
+        .add_attributes(attributes))
 }
 
 // return the tokens to sender
@@ -768,22 +957,41 @@ fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicRespon
 // FOLLOW_UP_IBC_SEND_FAILURE_ID failed to send ibc packet. This one has successfully sent
 fn on_packet_failure(
     deps: DepsMut,
+    env: Env,
     packet: IbcPacket,
     err: String,
 ) -> Result<IbcBasicResponse, ContractError> {
     let msg: Ics20Packet = from_json(&packet.data)?;
+    settle_pending_packet(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
 
     // in case that the denom is not in the mapping list, meaning that it is not transferred back, but transfer originally from this local chain
-    if ics20_denoms().may_load(deps.storage, &msg.denom)?.is_none() {
-        return Ok(IbcBasicResponse::new());
-    }
+    let Some(pair_mapping) = ics20_denoms().may_load(deps.storage, &msg.denom)? else {
+        let callback_msg =
+            build_lifecycle_callback_msg(deps.storage, &packet, false, Uint128::zero())?;
+        return Ok(IbcBasicResponse::new().add_submessages(callback_msg));
+    };
 
-    let sub_msg = handle_packet_refund(deps.storage, &msg.sender, &msg.denom, msg.amount, true)?;
     // since we reduce the channel's balance optimistically when transferring back, we undo reduce it again when receiving failed ack
-    undo_reduce_channel_balance(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
+    // this must happen before the refund so that the outstanding balance backs the re-minted tokens
+    undo_reduce_channel_balance(
+        deps.storage,
+        &packet.src.channel_id,
+        &msg.denom,
+        msg.amount,
+        &env.block,
+    )?;
+    let sub_msg = handle_packet_refund(deps.storage, &msg.sender, &msg.denom, msg.amount, true)?;
+    // notified after the refund so that the sender already holds the refunded funds
+    let refunded_amount = convert_remote_to_local(
+        msg.amount,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )?;
+    let callback_msg = build_lifecycle_callback_msg(deps.storage, &packet, false, refunded_amount)?;
 
     let res = IbcBasicResponse::new()
         .add_submessage(sub_msg)
+        .add_submessages(callback_msg)
         .add_attribute("action", "acknowledge")
         .add_attribute("sender", msg.sender)
         .add_attribute("receiver", msg.receiver)
@@ -815,9 +1023,14 @@ pub fn handle_packet_refund(
     )?;
 
     // check if mint_burn mechanism, then mint token for packet sender, if not, send from contract
-    let denom = parse_asset_info_denom(&pair_mapping.asset_info);
-    let send_amount_msg = Amount::from_parts(denom.clone(), local_amount)
-        .send_amount(packet_sender.to_string(), None);
+    let refund_amount = Amount::from_parts(
+        parse_asset_info_denom(&pair_mapping.asset_info),
+        local_amount,
+    );
+    let send_amount_msg = refund_amount.send_amount(packet_sender.to_string(), None);
+    if with_mint_burn {
+        restore_minted_supply(storage, &pair_mapping, local_amount)?;
+    }
     let cosmos_msg = match build_mint_mapping_msg(
         config.token_factory_addr.to_string(),
         pair_mapping.is_mint_burn,
@@ -836,15 +1049,19 @@ pub fn handle_packet_refund(
     };
 
     // save temp refund info, we use this info in handle reply enpoint
+    let gas_limit = get_gas_limit(storage, &refund_amount)?;
     let temp_refund_info = RefundInfo {
-        amount: Amount::from_parts(denom, local_amount),
+        amount: refund_amount,
         receiver: packet_sender.to_string(),
     };
     REFUND_INFO.save(storage, &Some(temp_refund_info))?;
 
     // used submsg here & reply on error. This means that if the refund process fails => tokens will be locked in this IBC Wasm contract. We will manually handle that case. No retry
     // similar event messages like ibctransfer module
-    Ok(SubMsg::reply_always(cosmos_msg, REFUND_FAILURE_ID))
+    Ok(SubMsg {
+        gas_limit,
+        ..SubMsg::reply_always(cosmos_msg, REFUND_FAILURE_ID)
+    })
 }
 
 pub fn build_ibc_send_packet(
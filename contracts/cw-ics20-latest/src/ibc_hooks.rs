@@ -1,12 +1,27 @@
-use cosmwasm_std::{Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{
+    Binary, ChannelResponse, Coin, CosmosMsg, Deps, DepsMut, Env, IbcQuery, MessageInfo, Response,
+};
 
 use cw20_ics20_msg::{
-    amount::Amount, converter::ConvertType, helper::parse_asset_info_denom, ibc_hooks::HookMethods,
+    amount::Amount,
+    converter::ConvertType,
+    helper::parse_asset_info_denom,
+    ibc_hooks::{
+        HookMethods, IbcHooksContractCall, IbcHooksTransfer, IbcHooksTransferToRemote,
+        IbcHooksUniversalSwap,
+    },
+    msg::TransferBackMsg,
 };
 use cw_utils::one_coin;
 use oraiswap::asset::AssetInfo;
 
-use crate::{ibc::get_follow_up_msgs, state::CONFIG, ContractError};
+use crate::{
+    contract::execute_transfer_back_to_remote_chain,
+    ibc::get_follow_up_msgs,
+    query_helper::get_destination_info_on_orai,
+    state::{CHANNEL_INFO, CONFIG, HOOK_CONTRACT_ALLOW_LIST},
+    ContractError,
+};
 
 pub fn ibc_hooks_receive(
     deps: DepsMut,
@@ -20,21 +35,22 @@ pub fn ibc_hooks_receive(
         HookMethods::UniversalSwap => {
             ibc_hooks_universal_swap(deps, env, info, orai_receiver, args)
         }
+        HookMethods::Transfer => ibc_hooks_transfer(deps, env, info, args),
+        HookMethods::ContractCall => ibc_hooks_contract_call(deps, env, info, args),
+        HookMethods::TransferToRemote => {
+            ibc_hooks_transfer_to_remote(deps, env, info, orai_receiver, args)
+        }
     }
 }
 
-pub fn ibc_hooks_universal_swap(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    orai_receiver: String,
-    args: Binary,
-) -> Result<Response, ContractError> {
+// converts the received coin from its source asset, returns the convert msg and the asset to deliver
+fn convert_hook_funds(
+    deps: &DepsMut,
+    info: &MessageInfo,
+) -> Result<(Coin, Option<CosmosMsg>, Amount), ContractError> {
     let config = CONFIG.load(deps.storage)?;
     // check exactly one coin was sent
-    let source_coin = one_coin(&info)?;
-
-    let mut msgs: Vec<CosmosMsg> = vec![];
+    let source_coin = one_coin(info)?;
 
     let (msg, to_send) = config.converter_contract.process_convert(
         &deps.querier,
@@ -45,6 +61,69 @@ pub fn ibc_hooks_universal_swap(
         ConvertType::FromSource,
     )?;
 
+    let amount = Amount::from_parts(parse_asset_info_denom(&to_send.info), to_send.amount);
+    Ok((source_coin, msg, amount))
+}
+
+// rejects malformed universal swap args before any funds are converted or forwarded
+fn validate_universal_swap_args(
+    deps: Deps,
+    env: &Env,
+    args: &Binary,
+) -> Result<IbcHooksUniversalSwap, ContractError> {
+    let hook_args = IbcHooksUniversalSwap::from_json(deps.api, args)?;
+    let channel_id = &hook_args.destination_channel;
+    // destination is Oraichain
+    if channel_id.is_empty() {
+        return Ok(hook_args);
+    }
+
+    // channels of this contract must have a mapping of the destination denom
+    if CHANNEL_INFO.has(deps.storage, channel_id) {
+        let (_, pair_mapping) = get_destination_info_on_orai(
+            deps.storage,
+            deps.api,
+            env,
+            channel_id,
+            &hook_args.destination_denom,
+        );
+        if pair_mapping.is_none() {
+            return Err(ContractError::MappingPairNotFound {});
+        }
+        return Ok(hook_args);
+    }
+
+    // otherwise it must be an open channel of the transfer module
+    let transfer_channel = deps
+        .querier
+        .query::<ChannelResponse>(
+            &IbcQuery::Channel {
+                channel_id: channel_id.clone(),
+                port_id: Some("transfer".to_string()),
+            }
+            .into(),
+        )
+        .ok()
+        .and_then(|res| res.channel);
+    if transfer_channel.is_none() {
+        return Err(ContractError::NoSuchChannel {
+            id: channel_id.clone(),
+        });
+    }
+    Ok(hook_args)
+}
+
+pub fn ibc_hooks_universal_swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    orai_receiver: String,
+    args: Binary,
+) -> Result<Response, ContractError> {
+    validate_universal_swap_args(deps.as_ref(), &env, &args)?;
+    let (source_coin, msg, to_send) = convert_hook_funds(&deps, &info)?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
     if let Some(msg) = msg {
         msgs.push(msg);
     }
@@ -52,7 +131,7 @@ pub fn ibc_hooks_universal_swap(
         deps.storage,
         deps.api,
         orai_receiver,
-        Amount::from_parts(parse_asset_info_denom(&to_send.info), to_send.amount),
+        to_send,
         Some(args.to_base64()),
     )?;
 
@@ -65,3 +144,96 @@ pub fn ibc_hooks_universal_swap(
         .add_messages(msgs)
         .add_submessages(sub_msgs))
 }
+
+pub fn ibc_hooks_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    args: Binary,
+) -> Result<Response, ContractError> {
+    let hook_args = IbcHooksTransfer::from_json(deps.api, &args)?;
+    let (source_coin, msg, to_send) = convert_hook_funds(&deps, &info)?;
+
+    let mut msgs: Vec<CosmosMsg> = msg.into_iter().collect();
+    msgs.push(to_send.send_amount(hook_args.receiver.clone(), None));
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "receive_ibc_hooks_transfer"),
+            ("receiver", hook_args.receiver.as_str()),
+            ("denom", source_coin.denom.as_str()),
+            ("amount", &source_coin.amount.to_string()),
+        ])
+        .add_messages(msgs))
+}
+
+pub fn ibc_hooks_contract_call(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    args: Binary,
+) -> Result<Response, ContractError> {
+    let hook_args = IbcHooksContractCall::from_json(deps.api, &args)?;
+    let contract = deps.api.addr_validate(&hook_args.contract)?;
+    if !HOOK_CONTRACT_ALLOW_LIST.has(deps.storage, &contract) {
+        return Err(ContractError::HookContractNotAllowed {
+            contract: hook_args.contract,
+        });
+    }
+    let (source_coin, msg, to_send) = convert_hook_funds(&deps, &info)?;
+
+    // native funds are attached to the execute msg, cw20 funds are sent with the payload
+    let mut msgs: Vec<CosmosMsg> = msg.into_iter().collect();
+    msgs.push(to_send.send_amount(contract.to_string(), Some(hook_args.msg)));
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "receive_ibc_hooks_contract_call"),
+            ("contract", contract.as_str()),
+            ("denom", source_coin.denom.as_str()),
+            ("amount", &source_coin.amount.to_string()),
+        ])
+        .add_messages(msgs))
+}
+
+pub fn ibc_hooks_transfer_to_remote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    orai_receiver: String,
+    args: Binary,
+) -> Result<Response, ContractError> {
+    let hook_args = IbcHooksTransferToRemote::from_json(&args)?;
+    // the receiver on Oraichain is the sender of the outgoing transfer
+    let sender = deps.api.addr_validate(&orai_receiver)?;
+    let (source_coin, msg, to_send) = convert_hook_funds(&deps, &info)?;
+
+    let res = execute_transfer_back_to_remote_chain(
+        deps,
+        env,
+        TransferBackMsg {
+            local_channel_id: hook_args.local_channel_id,
+            remote_address: hook_args.remote_address,
+            remote_denom: hook_args.remote_denom,
+            timeout: hook_args.timeout,
+            memo: hook_args.memo,
+            remote_prefix: None,
+            callback: None,
+        },
+        to_send,
+        sender,
+    )?;
+
+    // convert first so that the converted funds are available for the transfer
+    let msgs: Vec<CosmosMsg> = msg.into_iter().collect();
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "receive_ibc_hooks_transfer_to_remote"),
+            ("denom", source_coin.denom.as_str()),
+            ("amount", &source_coin.amount.to_string()),
+        ])
+        .add_messages(msgs)
+        .add_submessages(res.messages)
+        .add_attributes(res.attributes))
+}
@@ -84,3 +84,152 @@
 //         Ok(())
 //     }
 // }
+
+use crate::ContractError;
+use cosmwasm_std::{BlockInfo, Storage};
+
+// v3 adds per-direction statistics to the channel state. Before that, every inbound transfer increased
+// total_sent, so it is the best estimate we have of the cumulative inbound amount
+pub mod v3 {
+    use crate::query_helper::get_local_outstanding_of_asset;
+    use crate::state::{
+        ics20_denoms, ALLOW_LIST, CHANNEL_COUNT, CHANNEL_HISTORY_START, CHANNEL_INFO,
+        CHANNEL_REVERSE_STATE, CONFIG, MAPPING_COUNT, MINTED_SUPPLY, REFUND_INFO, REFUND_INFO_LIST,
+    };
+    use crate::ContractError;
+    use cosmwasm_std::{BlockInfo, Order, StdResult, Storage};
+    use cw20_ics20_msg::state::AllowInfo;
+    use oraiswap::asset::AssetInfo;
+
+    pub fn migrate_channel_stats(
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        let states = CHANNEL_REVERSE_STATE
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((channel, denom), mut state) in states {
+            if !state.total_received.is_zero() || state.total_sent.is_zero() {
+                continue;
+            }
+            state.total_received = state.total_sent;
+            CHANNEL_REVERSE_STATE.save(storage, (&channel, &denom), &state, block.height)?;
+        }
+        Ok(())
+    }
+
+    // the channel states were a plain map before, so they have no changelog for older heights. History
+    // lookups start from the upgrade
+    pub fn migrate_channel_history_start(
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        if !CHANNEL_HISTORY_START.exists(storage) {
+            CHANNEL_HISTORY_START.save(storage, &block.height)?;
+        }
+        Ok(())
+    }
+
+    // older versions reset the refund list on every upgrade, pending refunds are now kept. A refund info left
+    // in the temporary store is moved to the list so that it can still be withdrawn
+    pub fn migrate_refund_info(
+        storage: &mut dyn Storage,
+        _block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        let mut refunds = REFUND_INFO_LIST.may_load(storage)?.unwrap_or_default();
+        if let Some(refund) = REFUND_INFO.may_load(storage)?.flatten() {
+            refunds.push(refund);
+        }
+        REFUND_INFO_LIST.save(storage, &refunds)?;
+        REFUND_INFO.save(storage, &None)?;
+        Ok(())
+    }
+
+    // the minted supply of mint-burn assets was not tracked before. Every minted token is backed by the
+    // outstanding balance of the asset, so that is where the supply cap and the invariant start from
+    pub fn migrate_minted_supply(
+        storage: &mut dyn Storage,
+        _block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        let mappings = ics20_denoms()
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (_, pair_mapping) in mappings {
+            let key = pair_mapping.asset_info.to_string();
+            if !pair_mapping.is_mint_burn || MINTED_SUPPLY.has(storage, &key) {
+                continue;
+            }
+            let outstanding = get_local_outstanding_of_asset(storage, &pair_mapping.asset_info)?;
+            MINTED_SUPPLY.save(storage, &key, &outstanding)?;
+        }
+        Ok(())
+    }
+
+    // cw20 tokens off the allow list can no longer be sent, even with a default gas limit. Tokens that are
+    // already mapped are allowed with the gas limit they had, the default one. Without a default they have
+    // no limit, operators must cap them with Allow after the upgrade
+    pub fn migrate_allow_list(
+        storage: &mut dyn Storage,
+        _block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        let gas_limit = CONFIG.load(storage)?.default_gas_limit;
+        let mappings = ics20_denoms()
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (_, pair_mapping) in mappings {
+            let AssetInfo::Token { contract_addr } = pair_mapping.asset_info else {
+                continue;
+            };
+            if !ALLOW_LIST.has(storage, &contract_addr) {
+                ALLOW_LIST.save(storage, &contract_addr, &AllowInfo { gas_limit })?;
+            }
+        }
+        Ok(())
+    }
+
+    // channels and mapping pairs were counted by iterating them. Their counters start from what is stored
+    pub fn migrate_counts(
+        storage: &mut dyn Storage,
+        _block: &BlockInfo,
+    ) -> Result<(), ContractError> {
+        let channel_count = CHANNEL_INFO
+            .keys_raw(storage, None, None, Order::Ascending)
+            .count() as u64;
+        let mapping_count = ics20_denoms()
+            .keys_raw(storage, None, None, Order::Ascending)
+            .count() as u64;
+        CHANNEL_COUNT.save(storage, &channel_count)?;
+        MAPPING_COUNT.save(storage, &mapping_count)?;
+        Ok(())
+    }
+}
+
+/// A storage migration, runs when upgrading from a version older than the one that introduced it
+pub type Migration = fn(&mut dyn Storage, &BlockInfo) -> Result<(), ContractError>;
+
+/// Storage migrations in the order they must run, with the version that introduced them, their name and
+/// whether they are optional. Only optional migrations may be skipped on upgrade
+pub const MIGRATIONS: &[(&str, &str, bool, Migration)] = &[
+    (
+        "1.1.0",
+        "channel_history_start",
+        false,
+        v3::migrate_channel_history_start,
+    ),
+    ("1.1.0", "channel_stats", true, v3::migrate_channel_stats),
+    ("1.1.0", "refund_info", false, v3::migrate_refund_info),
+    ("1.1.0", "minted_supply", false, v3::migrate_minted_supply),
+    ("1.1.0", "counts", false, v3::migrate_counts),
+    ("1.1.0", "allow_list", false, v3::migrate_allow_list),
+];
+
+/// Parses a `major.minor.patch` version, any pre-release or build suffix is ignored
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(version)
+}
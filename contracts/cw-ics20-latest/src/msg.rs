@@ -1,13 +1,18 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary, Uint128};
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
 use cw20::Cw20ReceiveMsg;
+pub use cw20_ics20_msg::msg::UpdateValue;
 use cw20_ics20_msg::msg::{AllowedInfo, DeletePairMsg, TransferBackMsg, UpdatePairMsg};
 use oraiswap::asset::AssetInfo;
 
-use cw20_ics20_msg::state::{ChannelInfo, MappingMetadata, Ratio, RelayerFee, TokenFee};
+use cw20_ics20_msg::state::{
+    ChannelInfo, MappingMetadata, MappingStatus, Ratio, RelayerFee, TokenFee,
+};
 use cw20_ics20_msg::{amount::Amount, ibc_hooks::HookMethods};
 use token_bindings::Metadata;
-use crate::state::{RefundInfo};
+use crate::state::{
+    AuditAction, AuditEntry, ChannelState, DustBalance, RefundInfo, Role, TimelockOperation,
+};
 
 #[cw_serde]
 pub struct InitMsg {
@@ -37,7 +42,11 @@ pub struct AllowMsg {
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// names of optional storage migrations to skip, eg: when they were already applied by hand
+    #[serde(default)]
+    pub skip_migrations: Vec<String>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
@@ -48,13 +57,22 @@ pub enum ExecuteMsg {
     TransferToRemote(TransferBackMsg),
     UpdateMappingPair(UpdatePairMsg),
     DeleteMappingPair(DeletePairMsg),
-    /// This must be called by gov_contract, will allow a new cw20 token to be sent
-    // Allow(AllowMsg),
-    /// Change the admin (must be called by current admin)
+    /// Updates several mapping pairs at once. Every entry is validated before any of them is applied
+    BatchUpdateMappingPairs {
+        pairs: Vec<UpdatePairMsg>,
+    },
+    /// Deletes several mapping pairs at once. Pairs with outstanding balance are only deleted if forced
+    BatchDeleteMappingPairs {
+        pairs: Vec<DeletePairMsg>,
+        force: bool,
+    },
+    /// Updates the config, must be called by the owner. Setting admin proposes a new owner that has to accept it.
+    /// Changes of the swap router, converter, osor entrypoint and token factory are timelocked
+    /// Fields left out are unchanged, optional fields can be cleared with `unset`
     UpdateConfig {
-        admin: Option<String>,
+        admin: Option<UpdateValue<String>>,
         default_timeout: Option<u64>,
-        default_gas_limit: Option<u64>,
+        default_gas_limit: Option<UpdateValue<u64>>,
         swap_router_contract: Option<String>,
         token_fee: Option<Vec<TokenFee>>,
         relayer_fee: Option<Vec<RelayerFee>>,
@@ -63,6 +81,7 @@ pub enum ExecuteMsg {
         converter_contract: Option<String>,
         osor_entrypoint_contract: Option<String>,
         token_factory_addr: Option<String>,
+        reason: String,
     },
     // self-call msgs to deal with on_ibc_receive reentrancy error
     IncreaseChannelBalanceIbcReceive {
@@ -77,11 +96,13 @@ pub enum ExecuteMsg {
         amount: Uint128,
         local_receiver: String,
     },
+    /// Timelocked, takes effect once ExecuteTimelock is called after the delay
     OverrideChannelBalance {
         channel_id: String,
         ibc_denom: String,
         outstanding: Uint128,
         total_sent: Option<Uint128>,
+        reason: String,
     },
     IbcHooksReceive {
         func: HookMethods,
@@ -89,9 +110,84 @@ pub enum ExecuteMsg {
         args: Binary,
     },
     RegisterDenom(RegisterDenomMsg),
+    /// Withdraws the surplus of an asset that is not owed to users. Emergency withdrawals skip this check,
+    /// so they are queued by the owner and only take effect once the timelock delay has passed
     WithdrawAsset {
         coin: Amount,
         receiver: Option<Addr>,
+        #[serde(default)]
+        emergency: bool,
+        reason: String,
+    },
+    /// Sends the accumulated decimal conversion dust of a mapping to the receiver
+    SweepDust {
+        ibc_denom: String,
+        receiver: String,
+        reason: String,
+    },
+    /// Proposes a new owner, which becomes the owner once it calls AcceptOwnership
+    TransferOwnership {
+        new_owner: String,
+        reason: String,
+    },
+    AcceptOwnership {
+        reason: String,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+        reason: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+        reason: String,
+    },
+    /// Updates fees and fee receivers, must be called by a fee manager
+    UpdateFees {
+        token_fee: Option<Vec<TokenFee>>,
+        relayer_fee: Option<Vec<RelayerFee>>,
+        fee_receiver: Option<String>,
+        relayer_fee_receiver: Option<String>,
+        reason: String,
+    },
+    /// Disables a mapping pair in both directions, must be called by a pauser
+    PauseMappingPair {
+        ibc_denom: String,
+        reason: String,
+    },
+    /// Adds a cw20 contract to the allow list, increases its gas limit or caps it when it has none.
+    /// Must be called by a mapping manager
+    Allow {
+        contract: String,
+        gas_limit: Option<u64>,
+        reason: String,
+    },
+    /// Removes a cw20 contract from the allow list, it can no longer be sent to the remote chain.
+    /// Receives and refunds of it use the default gas limit
+    Disallow {
+        contract: String,
+        reason: String,
+    },
+    /// Allows or disallows a contract to be executed by the ibc hooks contract call method
+    UpdateHookContract {
+        contract: String,
+        allowed: bool,
+        reason: String,
+    },
+    /// Queues a new delay of timelocked operations, must be called by the owner
+    UpdateTimelockDelay {
+        delay: u64,
+        reason: String,
+    },
+    /// Applies a queued operation once its delay has passed, can be called by anyone
+    ExecuteTimelock {
+        id: u64,
+    },
+    /// Drops a queued operation, must be called by a guardian
+    CancelTimelock {
+        id: u64,
+        reason: String,
     },
     // TODO: this msg for test only
     // need to remove after testing is done
@@ -134,16 +230,57 @@ pub enum QueryMsg {
     #[returns(ChannelResponse)]
     Channel { id: String },
     /// Returns the details of the name channel, error if not created.
+    /// If height is set, returns the balance at the beginning of that block instead.
     #[returns(ChannelWithKeyResponse)]
-    ChannelWithKey { channel_id: String, denom: String },
-    /// Show the Config.
+    ChannelWithKey {
+        channel_id: String,
+        denom: String,
+        height: Option<u64>,
+    },
+    /// Lists the balance changes of a (channel, denom) over a height range, both ends inclusive
+    #[returns(ChannelBalanceHistoryResponse)]
+    ChannelBalanceHistory {
+        channel_id: String,
+        denom: String,
+        start_height: Option<u64>,
+        end_height: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Show the Config. Roles are listed by the paginated ListRoles and RoleMembers queries
     #[returns(ConfigResponse)]
     Config {},
+    /// Version, enabled features and storage counts of the contract
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
     #[returns(cw_controllers::AdminResponse)]
     Admin {},
+    #[returns(OwnershipResponse)]
+    Ownership {},
+    /// Roles held by the address. The owner holds every role
+    #[returns(RolesResponse)]
+    Roles { address: String },
+    /// Addresses granted the role, the owner is not listed
+    #[returns(Vec<String>)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Every role granted, ordered by role and address. The owner is not listed
+    #[returns(ListRolesResponse)]
+    ListRoles {
+        start_after: Option<(Role, String)>,
+        limit: Option<u32>,
+    },
     /// Query if a given cw20 contract is allowed.
     #[returns(AllowedResponse)]
     Allowed { contract: String },
+    /// Contracts that can be executed by the ibc hooks contract call method
+    #[returns(Vec<String>)]
+    HookContracts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// List all allowed cw20 contracts.
     #[returns(ListAllowedResponse)]
     ListAllowed {
@@ -162,10 +299,64 @@ pub enum QueryMsg {
     PairMapping { key: String },
     #[returns(Vec<PairQuery>)]
     PairMappingsFromAssetInfo { asset_info: AssetInfo },
+    /// Lists mappings with their channel balance and token fee, filtered by channel, mode and status.
+    /// The limit bounds the mappings scanned, a page may return fewer pairs
+    #[returns(ListMappingDetailResponse)]
+    PairMappingDetails {
+        local_channel_id: Option<String>,
+        is_mint_burn: Option<bool>,
+        status: Option<MappingStatus>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<u8>,
+    },
+    /// Paginated mappings of a local asset, with their channel balance and token fee
+    #[returns(ListMappingDetailResponse)]
+    PairMappingDetailsFromAssetInfo {
+        asset_info: AssetInfo,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<u8>,
+    },
     #[returns(Ratio)]
     GetTransferTokenFee { remote_token_denom: String },
     #[returns(Vec<RefundInfo>)]
     RefundInfoList {},
+    /// Compares the outstanding balance of a channel against what the contract actually holds
+    #[returns(ReconcileResponse)]
+    Reconcile {
+        channel_id: String,
+        ibc_denom: String,
+    },
+    /// Paginated sweep of Reconcile over every channel state that has a mapping.
+    /// The limit bounds the channel states scanned, a page may return fewer entries
+    #[returns(ListReconcileResponse)]
+    ReconcileAll {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Supply of a local asset minted through its mint-burn mappings
+    #[returns(Uint128)]
+    MintedSupply { asset_info: AssetInfo },
+    /// Decimal conversion dust accumulated by a mapping
+    #[returns(DustResponse)]
+    Dust { ibc_denom: String },
+    #[returns(TimelockOperation)]
+    TimelockOperation { id: u64 },
+    /// Queued operations in ascending order of id
+    #[returns(TimelockOperationsResponse)]
+    TimelockOperations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Paginated audit log of admin interventions, optionally filtered by action
+    #[returns(ListAuditLogResponse)]
+    AuditLog {
+        action: Option<AuditAction>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        order: Option<u8>,
+    },
 }
 
 #[cw_serde]
@@ -187,6 +378,21 @@ pub struct ChannelResponse {
     /// The total number of tokens that have been sent over this channel
     /// (even if many have been returned, so balance is low)
     pub total_sent: Vec<Amount>,
+    /// Per-denom transfer statistics of this channel
+    pub stats: Vec<ChannelStats>,
+}
+
+#[cw_serde]
+pub struct ChannelStats {
+    pub denom: String,
+    /// Cumulative amount received from the remote chain
+    pub total_received: Uint128,
+    /// Cumulative amount transferred back to the remote chain
+    pub total_returned: Uint128,
+    /// Part of total_returned that failed or timed out and was refunded
+    pub total_refunded: Uint128,
+    pub last_activity: Timestamp,
+    pub transfer_count: u64,
 }
 
 #[cw_serde]
@@ -200,11 +406,69 @@ pub struct ChannelWithKeyResponse {
     pub total_sent: Amount,
 }
 
+#[cw_serde]
+pub struct DustResponse {
+    pub ibc_denom: String,
+    pub balance: DustBalance,
+    /// amount that can be swept now, in local decimals
+    pub sweepable: Uint128,
+}
+
+#[cw_serde]
+pub struct TimelockOperationsResponse {
+    /// delay applied to newly queued operations, in seconds
+    pub delay: u64,
+    pub operations: Vec<TimelockOperation>,
+}
+
+#[cw_serde]
+pub struct ListAuditLogResponse {
+    pub entries: Vec<AuditEntry>,
+}
+
+#[cw_serde]
+pub struct ChannelBalanceChange {
+    pub height: u64,
+    /// channel state before the first change in this block, none if it did not exist yet
+    pub before: Option<ChannelState>,
+    /// channel state after the last change in this block
+    pub after: Option<ChannelState>,
+}
+
+#[cw_serde]
+pub struct ChannelBalanceHistoryResponse {
+    pub channel_id: String,
+    pub denom: String,
+    pub changes: Vec<ChannelBalanceChange>,
+}
+
 #[cw_serde]
 pub struct PortResponse {
     pub port_id: String,
 }
 
+#[cw_serde]
+pub struct OwnershipResponse {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct RoleGrant {
+    pub role: Role,
+    pub address: Addr,
+}
+
+#[cw_serde]
+pub struct ListRolesResponse {
+    pub grants: Vec<RoleGrant>,
+}
+
 #[cw_serde]
 pub struct ConfigResponse {
     pub default_timeout: u64,
@@ -218,6 +482,18 @@ pub struct ConfigResponse {
     pub relayer_fees: Vec<RelayerFeeResponse>,
     pub converter_contract: String,
     pub osor_entrypoint_contract: String,
+    pub token_factory_addr: Addr,
+    pub pending_owner: Option<Addr>,
+    pub timelock_delay: u64,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub contract: String,
+    pub version: String,
+    pub features: Vec<String>,
+    pub channel_count: u64,
+    pub mapping_count: u64,
 }
 
 #[cw_serde]
@@ -247,3 +523,47 @@ pub struct PairQuery {
     pub key: String,
     pub pair_mapping: MappingMetadata,
 }
+
+#[cw_serde]
+pub struct PairMappingDetail {
+    pub key: String,
+    pub pair_mapping: MappingMetadata,
+    /// balance of the mapping on its local channel, in remote decimals
+    pub outstanding: Uint128,
+    pub total_sent: Uint128,
+    /// token fee charged on the remote denom, if any
+    pub token_fee: Option<Ratio>,
+}
+
+#[cw_serde]
+pub struct ListMappingDetailResponse {
+    pub pairs: Vec<PairMappingDetail>,
+    /// Last mapping scanned by this page, to pass as start_after of the next one.
+    /// None once every mapping has been scanned
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct ReconcileResponse {
+    pub channel_id: String,
+    pub ibc_denom: String,
+    pub asset_info: AssetInfo,
+    pub is_mint_burn: bool,
+    /// Outstanding balance of this channel, converted to local decimals
+    pub outstanding: Uint128,
+    /// Outstanding balance of every channel mapped to the same local asset, converted to local decimals.
+    /// Holdings are shared between these channels, so surplus and deficit are computed against this total
+    pub total_outstanding: Uint128,
+    /// Contract balance for lock-unlock mappings, total supply for mint-burn mappings
+    pub holdings: Uint128,
+    pub surplus: Uint128,
+    pub deficit: Uint128,
+}
+
+#[cw_serde]
+pub struct ListReconcileResponse {
+    pub entries: Vec<ReconcileResponse>,
+    /// Last channel state scanned by this page, to pass as start_after of the next one.
+    /// None once every channel state has been scanned
+    pub next_start_after: Option<(String, String)>,
+}
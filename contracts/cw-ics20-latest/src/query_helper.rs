@@ -1,12 +1,18 @@
-use cosmwasm_std::{Api, Env, Order, StdResult, Storage};
-use cw20_ics20_msg::helper::{denom_to_asset_info, parse_ibc_wasm_port_id};
+use cosmwasm_std::{Addr, Api, Env, Order, QuerierWrapper, StdResult, Storage, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
+use cw20_ics20_msg::amount::{convert_remote_to_local, split_decimals};
+use cw20_ics20_msg::helper::{denom_to_asset_info, parse_asset_info_denom, parse_ibc_wasm_port_id};
 use cw20_ics20_msg::state::MappingMetadata;
 use oraiswap::asset::AssetInfo;
 use sha256::digest;
 
 use crate::{
+    ibc::parse_ibc_channel_without_sanity_checks,
     msg::PairQuery,
-    state::{get_key_ics20_ibc_denom, ics20_denoms},
+    state::{
+        get_key_ics20_ibc_denom, ics20_denoms, CHANNEL_REVERSE_STATE, DUST, PENDING_PACKETS,
+        REFUND_INFO_LIST,
+    },
 };
 
 pub fn get_mappings_from_asset_info(
@@ -71,3 +77,141 @@ pub fn get_destination_info_on_orai(
 
     (AssetInfo::NativeToken { denom: ibc_denom }, None)
 }
+
+// outstanding is stored in remote decimals. A zero or dust-only outstanding is worth nothing locally
+pub fn convert_outstanding_to_local(
+    outstanding: Uint128,
+    pair_mapping: &MappingMetadata,
+) -> Uint128 {
+    if outstanding.is_zero() {
+        return Uint128::zero();
+    }
+    convert_remote_to_local(
+        outstanding,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )
+    .unwrap_or_default()
+}
+
+// rounds up so that remote dust is still covered by local holdings. Unlike the display conversion,
+// an error is not swallowed since it would understate what the contract owes
+fn convert_obligation_to_local(
+    outstanding: Uint128,
+    pair_mapping: &MappingMetadata,
+) -> StdResult<Uint128> {
+    let (local_amount, dust) = split_decimals(
+        outstanding,
+        pair_mapping.remote_decimals,
+        pair_mapping.asset_info_decimals,
+    )?;
+    if dust.is_zero() {
+        return Ok(local_amount);
+    }
+    Ok(local_amount.checked_add(Uint128::one())?)
+}
+
+/// Sums the outstanding balance of every channel whose mapping points to the given local asset,
+/// converted to local decimals. This is what the contract owes to the remote chains for that asset.
+pub fn get_local_outstanding_of_asset(
+    storage: &dyn Storage,
+    asset_info: &AssetInfo,
+) -> StdResult<Uint128> {
+    let mut total = Uint128::zero();
+    for pair in get_mappings_from_asset_info(storage, asset_info.clone())? {
+        let channel_id = parse_ibc_channel_without_sanity_checks(&pair.key)?;
+        if let Some(state) =
+            CHANNEL_REVERSE_STATE.may_load(storage, (channel_id, pair.key.as_str()))?
+        {
+            total += convert_outstanding_to_local(state.outstanding, &pair.pair_mapping);
+        }
+    }
+    Ok(total)
+}
+
+/// Sums what the contract holds on behalf of users for a local asset: the outstanding balance of its lock-unlock
+/// mappings and the amount of their transfers back waiting for an ack or timeout, converted to local decimals and
+/// rounded up, the dust kept for sweeping and the refunds waiting to be retried.
+/// Mint-burn outstanding is backed by the supply, and their failed transfers back are re-minted
+pub fn get_local_obligations_of_asset(
+    storage: &dyn Storage,
+    asset_info: &AssetInfo,
+) -> StdResult<Uint128> {
+    let mut total = Uint128::zero();
+    for pair in get_mappings_from_asset_info(storage, asset_info.clone())? {
+        // outbound dust is kept by the contract for every mapping, inbound dust of mint-burn mappings is minted
+        let dust = DUST.may_load(storage, &pair.key)?.unwrap_or_default();
+        total = total.checked_add(dust.local)?;
+        if pair.pair_mapping.is_mint_burn {
+            continue;
+        }
+        let (dust_from_remote, _) = split_decimals(
+            dust.remote,
+            pair.pair_mapping.remote_decimals,
+            pair.pair_mapping.asset_info_decimals,
+        )?;
+        total = total.checked_add(dust_from_remote)?;
+
+        let channel_id = parse_ibc_channel_without_sanity_checks(&pair.key)?;
+        if let Some(state) =
+            CHANNEL_REVERSE_STATE.may_load(storage, (channel_id, pair.key.as_str()))?
+        {
+            total = total.checked_add(convert_obligation_to_local(
+                state.outstanding,
+                &pair.pair_mapping,
+            )?)?;
+        }
+        // already taken out of the outstanding balance, but refunded from the contract if they fail
+        if let Some(pending) =
+            PENDING_PACKETS.may_load(storage, (channel_id, pair.key.as_str()))?
+        {
+            total = total.checked_add(convert_obligation_to_local(
+                pending.amount,
+                &pair.pair_mapping,
+            )?)?;
+        }
+    }
+    let denom = parse_asset_info_denom(asset_info);
+    for refund in REFUND_INFO_LIST.may_load(storage)?.unwrap_or_default() {
+        if refund.amount.denom() == denom {
+            total = total.checked_add(refund.amount.amount())?;
+        }
+    }
+    Ok(total)
+}
+
+/// Returns what actually backs the outstanding balance of a local asset:
+/// the contract balance for lock-unlock mappings, the total supply for mint-burn mappings.
+pub fn query_asset_holdings(
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    asset_info: &AssetInfo,
+    is_mint_burn: bool,
+) -> StdResult<Uint128> {
+    match asset_info {
+        AssetInfo::NativeToken { denom } => {
+            if is_mint_burn {
+                Ok(querier.query_supply(denom)?.amount)
+            } else {
+                Ok(querier.query_balance(contract_addr, denom)?.amount)
+            }
+        }
+        AssetInfo::Token {
+            contract_addr: token_addr,
+        } => {
+            if is_mint_burn {
+                let res: TokenInfoResponse =
+                    querier.query_wasm_smart(token_addr, &Cw20QueryMsg::TokenInfo {})?;
+                Ok(res.total_supply)
+            } else {
+                let res: BalanceResponse = querier.query_wasm_smart(
+                    token_addr,
+                    &Cw20QueryMsg::Balance {
+                        address: contract_addr.to_string(),
+                    },
+                )?;
+                Ok(res.balance)
+            }
+        }
+    }
+}
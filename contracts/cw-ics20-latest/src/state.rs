@@ -2,22 +2,156 @@ use std::array;
 use std::fmt;
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
-use cw20_ics20_msg::amount::Amount;
+use cosmwasm_std::{Addr, BlockInfo, Deps, Empty, StdResult, Storage, Timestamp, Uint128};
+use cw20_ics20_msg::amount::{split_decimals, Amount};
 use cw20_ics20_msg::converter::ConverterController;
 use cw20_ics20_msg::state::{
     AllowInfo, ChannelInfo, ConvertReplyArgs, MappingMetadata, Ratio, ReplyArgs,
 };
 use cw_controllers::Admin;
-use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy};
 use oraiswap::router::RouterController;
 
+use crate::query_helper::get_local_outstanding_of_asset;
 use crate::ContractError;
 
+/// Owner of the contract. It holds every role
 pub const ADMIN: Admin = Admin::new("admin");
 
+// owner proposed by the current owner, becomes the owner once it accepts
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+
+// addresses granted a role, keyed by (role, address)
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// updates and deletes mapping pairs, registers denoms
+    MappingManager,
+    /// updates fees and sweeps dust
+    FeeManager,
+    /// disables mapping pairs
+    Pauser,
+    /// withdraws stuck assets to refund users
+    RefundOperator,
+    /// cancels timelocked operations
+    Guardian,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [
+        Role::MappingManager,
+        Role::FeeManager,
+        Role::Pauser,
+        Role::RefundOperator,
+        Role::Guardian,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::MappingManager => "mapping_manager",
+            Role::FeeManager => "fee_manager",
+            Role::Pauser => "pauser",
+            Role::RefundOperator => "refund_operator",
+            Role::Guardian => "guardian",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|r| r.as_str() == role)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+pub fn has_role(deps: Deps, addr: &Addr, role: Role) -> StdResult<bool> {
+    Ok(ADMIN.is_admin(deps, addr)? || ROLES.has(deps.storage, (role.as_str(), addr)))
+}
+
+pub fn assert_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if !has_role(deps, sender, role)? {
+        return Err(ContractError::MissingRole { role });
+    }
+    Ok(())
+}
+
 pub const CONFIG: Item<Config> = Item::new("ics20_config_v1.0.2");
 
+/// Delay of timelocked operations in seconds, used until the owner sets one
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 86400;
+
+pub const TIMELOCK_DELAY: Item<u64> = Item::new("timelock_delay");
+
+pub const TIMELOCK_COUNT: Item<u64> = Item::new("timelock_count");
+
+// queued operations by id, removed once executed or cancelled
+pub const TIMELOCK_OPERATIONS: Map<u64, TimelockOperation> = Map::new("timelock_operations");
+
+/// Changes of core dependencies of the bridge, which only take effect once the timelock delay has passed
+#[cw_serde]
+pub enum TimelockAction {
+    UpdateDependencies {
+        swap_router_contract: Option<String>,
+        converter_contract: Option<String>,
+        osor_entrypoint_contract: Option<String>,
+        token_factory_addr: Option<Addr>,
+    },
+    OverrideChannelBalance {
+        channel_id: String,
+        ibc_denom: String,
+        outstanding: Uint128,
+        total_sent: Option<Uint128>,
+    },
+    UpdateDelay {
+        delay: u64,
+    },
+    WithdrawAsset {
+        coin: Amount,
+        receiver: Addr,
+    },
+}
+
+#[cw_serde]
+pub struct TimelockOperation {
+    pub id: u64,
+    pub action: TimelockAction,
+    pub proposer: Addr,
+    pub reason: String,
+    /// anyone can execute the operation from this time on
+    pub executable_at: Timestamp,
+}
+
+pub fn schedule_timelock(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    proposer: &Addr,
+    action: TimelockAction,
+    reason: String,
+) -> Result<TimelockOperation, ContractError> {
+    if reason.trim().is_empty() {
+        return Err(ContractError::EmptyAuditReason {});
+    }
+    let delay = TIMELOCK_DELAY
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_TIMELOCK_DELAY);
+    let id = TIMELOCK_COUNT.may_load(storage)?.unwrap_or_default();
+    let operation = TimelockOperation {
+        id,
+        action,
+        proposer: proposer.clone(),
+        reason,
+        executable_at: block.time.plus_seconds(delay),
+    };
+    TIMELOCK_OPERATIONS.save(storage, id, &operation)?;
+    TIMELOCK_COUNT.save(storage, &(id + 1))?;
+    Ok(operation)
+}
+
 // Used to pass info from the ibc_packet_receive to the reply handler
 pub const REPLY_ARGS: Item<ReplyArgs> = Item::new("reply_args_v2");
 
@@ -28,13 +162,27 @@ pub const CONVERT_REPLY_ARGS: Item<ConvertReplyArgs> = Item::new("convert_reply_
 /// static info on one channel that doesn't change
 pub const CHANNEL_INFO: Map<&str, ChannelInfo> = Map::new("channel_info");
 
+// number of channels in CHANNEL_INFO and of mapping pairs in ics20_denoms, so the contract info query
+// does not iterate them. Seeded by a migration for contracts deployed before they were kept
+pub const CHANNEL_COUNT: Item<u64> = Item::new("channel_count");
+pub const MAPPING_COUNT: Item<u64> = Item::new("mapping_count");
+
 // /// Forward channel state is used when LOCAL chain initiates ibc transfer to remote chain
 // pub const CHANNEL_FORWARD_STATE: Map<(&str, &str), ChannelState> =
 //     Map::new("channel_forward_state");
 
-/// Reverse channel state is used when REMOTE chain initiates ibc transfer to local chain
-pub const CHANNEL_REVERSE_STATE: Map<(&str, &str), ChannelState> =
-    Map::new("channel_reverse_state");
+/// Reverse channel state is used when REMOTE chain initiates ibc transfer to local chain.
+/// Every change is snapshotted so we can look up the balance at a past height
+pub const CHANNEL_REVERSE_STATE: SnapshotMap<(&str, &str), ChannelState> = SnapshotMap::new(
+    "channel_reverse_state",
+    "channel_reverse_state__checkpoints",
+    "channel_reverse_state__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Height the snapshots of the reverse channel state start from. Changes made before it were not
+/// recorded, so the state at a lower height can't be looked up
+pub const CHANNEL_HISTORY_START: Item<u64> = Item::new("channel_history_start");
 
 /// Reverse channel state is used when LOCAL chain initiates ibc transfer to remote chain
 // pub const CHANNEL_FORWARD_STATE: Map<(&str, &str), ChannelState> =
@@ -43,12 +191,48 @@ pub const CHANNEL_REVERSE_STATE: Map<(&str, &str), ChannelState> =
 /// Every cw20 contract we allow to be sent is stored here, possibly with a gas_limit
 pub const ALLOW_LIST: Map<&Addr, AllowInfo> = Map::new("allow_list");
 
+/// Contracts that the ibc hooks contract call method is allowed to execute
+pub const HOOK_CONTRACT_ALLOW_LIST: Map<&Addr, Empty> = Map::new("hook_contract_allow_list");
+
 pub const TOKEN_FEE: Map<&str, Ratio> = Map::new("token_fee");
 
 // relayer fee. This fee depends on the network type, not token type
 // decimals of relayer fee should always be 10^6 because we use ORAI as relayer fee
 pub const RELAYER_FEE: Map<&str, Uint128> = Map::new("relayer_fee");
 
+// supply minted through mint-burn mappings, keyed by the local asset info. Reduced when burning
+pub const MINTED_SUPPLY: Map<&str, Uint128> = Map::new("minted_supply");
+
+// packets of transfers back waiting for their ack or timeout, keyed by (src channel, ibc denom).
+// Packets sent before this was tracked are not counted
+pub const PENDING_PACKETS: Map<(&str, &str), PendingPackets> = Map::new("pending_packets");
+
+// decimal conversion dust kept by mappings with the accumulate or refund dust policy, keyed by ibc denom
+pub const DUST: Map<&str, DustBalance> = Map::new("dust");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PendingPackets {
+    pub count: u64,
+    /// sum of their amounts, in remote decimals
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct DustBalance {
+    /// truncated from inbound transfers, in remote decimals
+    pub remote: Uint128,
+    /// kept from outbound transfers, in local decimals
+    pub local: Uint128,
+}
+
+// callback contracts of outgoing packets waiting for their ack or timeout, keyed by (src channel, packet sequence)
+pub const PENDING_CALLBACKS: Map<(&str, u64), Addr> = Map::new("pending_callbacks");
+
+// the sequence of a packet is only known in the reply of its send, the callback waits here until then
+pub const SEND_PACKET_CALLBACK: Item<Addr> = Item::new("send_packet_callback");
+
 // store refund info 
 pub const REFUND_INFO_LIST: Item<Vec<RefundInfo>> = Item::new("refund_info_list");
 
@@ -68,6 +252,63 @@ impl fmt::Display for RefundInfo {
     }
 }
 
+// number of entries in the audit log, used as the id of the next entry
+pub const AUDIT_LOG_COUNT: Item<u64> = Item::new("audit_log_count");
+
+#[cw_serde]
+pub enum AuditAction {
+    OverrideChannelBalance,
+    WithdrawAsset,
+    UpdateMappingPair,
+    DeleteMappingPair,
+    UpdateConfig,
+    SweepDust,
+    UpdateHookContract,
+    GrantRole,
+    RevokeRole,
+    TransferOwnership,
+    PauseMappingPair,
+    CancelTimelock,
+    UpdateAllowList,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self {
+            AuditAction::OverrideChannelBalance => "override_channel_balance",
+            AuditAction::WithdrawAsset => "withdraw_asset",
+            AuditAction::UpdateMappingPair => "update_mapping_pair",
+            AuditAction::DeleteMappingPair => "delete_mapping_pair",
+            AuditAction::UpdateConfig => "update_config",
+            AuditAction::SweepDust => "sweep_dust",
+            AuditAction::UpdateHookContract => "update_hook_contract",
+            AuditAction::GrantRole => "grant_role",
+            AuditAction::RevokeRole => "revoke_role",
+            AuditAction::TransferOwnership => "transfer_ownership",
+            AuditAction::PauseMappingPair => "pause_mapping_pair",
+            AuditAction::CancelTimelock => "cancel_timelock",
+            AuditAction::UpdateAllowList => "update_allow_list",
+        };
+        write!(f, "{}", action)
+    }
+}
+
+#[cw_serde]
+pub struct AuditEntry {
+    pub id: u64,
+    pub action: AuditAction,
+    pub actor: Addr,
+    /// what the action was applied to. Eg: the ibc denom of a mapping pair
+    pub subject: String,
+    pub reason: String,
+    /// json encoded value before the action, none if it did not exist
+    pub before: Option<String>,
+    /// json encoded value after the action, none if it has been removed
+    pub after: Option<String>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
 // // accumulated token fee
 // pub const TOKEN_FEE_ACCUMULATOR: Map<&str, Uint128> = Map::new("token_fee_accumulator");
 
@@ -102,11 +343,114 @@ pub fn ics20_denoms<'a>() -> IndexedMap<'a, &'a str, MappingMetadata, MappingMet
     IndexedMap::new("ics20_mapping_namespace", indexes)
 }
 
+/// Saves the info of a channel, counting it if it is new
+pub fn save_channel_info(storage: &mut dyn Storage, info: &ChannelInfo) -> StdResult<()> {
+    if !CHANNEL_INFO.has(storage, &info.id) {
+        let count = CHANNEL_COUNT.may_load(storage)?.unwrap_or_default();
+        CHANNEL_COUNT.save(storage, &(count + 1))?;
+    }
+    CHANNEL_INFO.save(storage, &info.id, info)
+}
+
+/// Saves a mapping pair, counting it if it is new
+pub fn save_mapping_pair(
+    storage: &mut dyn Storage,
+    ibc_denom: &str,
+    pair_mapping: &MappingMetadata,
+) -> StdResult<()> {
+    if ics20_denoms().may_load(storage, ibc_denom)?.is_none() {
+        let count = MAPPING_COUNT.may_load(storage)?.unwrap_or_default();
+        MAPPING_COUNT.save(storage, &(count + 1))?;
+    }
+    ics20_denoms().save(storage, ibc_denom, pair_mapping)
+}
+
+/// Removes a mapping pair, if it exists
+pub fn remove_mapping_pair(storage: &mut dyn Storage, ibc_denom: &str) -> StdResult<()> {
+    if ics20_denoms().may_load(storage, ibc_denom)?.is_some() {
+        let count = MAPPING_COUNT.may_load(storage)?.unwrap_or_default();
+        MAPPING_COUNT.save(storage, &count.saturating_sub(1))?;
+        ics20_denoms().remove(storage, ibc_denom)?;
+    }
+    Ok(())
+}
+
+pub struct AuditEntryIndexes<'a> {
+    pub action: MultiIndex<'a, String, AuditEntry, u64>,
+}
+
+impl<'a> IndexList<AuditEntry> for AuditEntryIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<AuditEntry>> + '_> {
+        let v: Vec<&dyn Index<AuditEntry>> = vec![&self.action];
+        Box::new(v.into_iter())
+    }
+}
+
+/// append-only log of admin interventions, indexed by action so it can be filtered
+pub fn audit_log<'a>() -> IndexedMap<'a, u64, AuditEntry, AuditEntryIndexes<'a>> {
+    let indexes = AuditEntryIndexes {
+        action: MultiIndex::new(
+            |_k, d| d.action.to_string(),
+            "audit_log_namespace",
+            "audit_log__action",
+        ),
+    };
+    IndexedMap::new("audit_log_namespace", indexes)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn append_audit_entry(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    actor: &Addr,
+    action: AuditAction,
+    subject: String,
+    reason: String,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<u64, ContractError> {
+    if reason.trim().is_empty() {
+        return Err(ContractError::EmptyAuditReason {});
+    }
+    let id = AUDIT_LOG_COUNT.may_load(storage)?.unwrap_or_default();
+    audit_log().save(
+        storage,
+        id,
+        &AuditEntry {
+            id,
+            action,
+            actor: actor.clone(),
+            subject,
+            reason,
+            before,
+            after,
+            height: block.height,
+            time: block.time,
+        },
+    )?;
+    AUDIT_LOG_COUNT.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
 #[cw_serde]
 #[derive(Default)]
 pub struct ChannelState {
     pub outstanding: Uint128,
     pub total_sent: Uint128,
+    /// cumulative amount received from the remote chain
+    #[serde(default)]
+    pub total_received: Uint128,
+    /// cumulative amount transferred back to the remote chain, including transfers that were refunded later
+    #[serde(default)]
+    pub total_returned: Uint128,
+    /// cumulative amount of transfers back that failed or timed out and were refunded
+    #[serde(default)]
+    pub total_refunded: Uint128,
+    /// block time of the last transfer in either direction
+    #[serde(default)]
+    pub last_activity: Timestamp,
+    #[serde(default)]
+    pub transfer_count: u64,
 }
 
 #[cw_serde]
@@ -127,13 +471,20 @@ pub fn increase_channel_balance(
     channel: &str,
     denom: &str, // should be ibc denom
     amount: Uint128,
+    block: &BlockInfo,
 ) -> Result<(), ContractError> {
-    let store = CHANNEL_REVERSE_STATE.key((channel, denom));
     // whatever error or not found, return default
-    let mut state = store.load(storage).unwrap_or_default();
+    let mut state = CHANNEL_REVERSE_STATE
+        .load(storage, (channel, denom))
+        .unwrap_or_default();
     state.outstanding += amount;
     state.total_sent += amount;
-    store.save(storage, &state).map_err(ContractError::Std)
+    state.total_received += amount;
+    state.last_activity = block.time;
+    state.transfer_count += 1;
+    CHANNEL_REVERSE_STATE
+        .save(storage, (channel, denom), &state, block.height)
+        .map_err(ContractError::Std)
 }
 
 pub fn reduce_channel_balance(
@@ -141,9 +492,9 @@ pub fn reduce_channel_balance(
     channel: &str,
     denom: &str, // should be ibc denom
     amount: Uint128,
+    block: &BlockInfo,
 ) -> Result<(), ContractError> {
-    let store = CHANNEL_REVERSE_STATE.key((channel, denom));
-    let Ok(mut state) = store.load(storage) else {
+    let Ok(mut state) = CHANNEL_REVERSE_STATE.load(storage, (channel, denom)) else {
         return Err(ContractError::NoSuchChannelState {
             id: channel.to_string(),
             denom: denom.to_string(),
@@ -158,8 +509,13 @@ pub fn reduce_channel_balance(
                 id: channel.to_string(),
                 denom: denom.to_string(),
             })?;
+    state.total_returned += amount;
+    state.last_activity = block.time;
+    state.transfer_count += 1;
 
-    store.save(storage, &state).map_err(ContractError::Std)
+    CHANNEL_REVERSE_STATE
+        .save(storage, (channel, denom), &state, block.height)
+        .map_err(ContractError::Std)
 }
 
 // only used for admin of the contract
@@ -169,31 +525,263 @@ pub fn override_channel_balance(
     denom: &str, // should be ibc denom
     outstanding: Uint128,
     total_sent: Option<Uint128>,
+    block: &BlockInfo,
 ) -> Result<(), ContractError> {
-    CHANNEL_REVERSE_STATE.update(storage, (channel, denom), |orig| -> StdResult<_> {
-        let mut state = orig.unwrap_or_default();
-        state.outstanding = outstanding;
-        if let Some(total_sent) = total_sent {
-            state.total_sent = total_sent;
-        }
-        Ok(state)
+    CHANNEL_REVERSE_STATE.update(
+        storage,
+        (channel, denom),
+        block.height,
+        |orig| -> StdResult<_> {
+            let mut state = orig.unwrap_or_default();
+            state.outstanding = outstanding;
+            if let Some(total_sent) = total_sent {
+                state.total_sent = total_sent;
+            }
+            Ok(state)
+        },
+    )?;
+    Ok(())
+}
+
+pub fn accumulate_dust(
+    storage: &mut dyn Storage,
+    ibc_denom: &str,
+    remote: Uint128,
+    local: Uint128,
+) -> StdResult<DustBalance> {
+    DUST.update(storage, ibc_denom, |dust| -> StdResult<_> {
+        let mut dust = dust.unwrap_or_default();
+        dust.remote = dust.remote.checked_add(remote)?;
+        dust.local = dust.local.checked_add(local)?;
+        Ok(dust)
+    })
+}
+
+pub fn save_pending_callback(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    sequence: u64,
+    callback: &Addr,
+) -> StdResult<()> {
+    PENDING_CALLBACKS.save(storage, (channel_id, sequence), callback)
+}
+
+// removes the callback waiting for the packet with this sequence on the channel
+pub fn take_pending_callback(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    sequence: u64,
+) -> StdResult<Option<Addr>> {
+    let key = (channel_id, sequence);
+    let callback = PENDING_CALLBACKS.may_load(storage, key)?;
+    PENDING_CALLBACKS.remove(storage, key);
+    Ok(callback)
+}
+
+pub fn add_pending_packet(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    PENDING_PACKETS.update(storage, (channel, denom), |pending| -> StdResult<_> {
+        let mut pending = pending.unwrap_or_default();
+        pending.count += 1;
+        pending.amount = pending.amount.checked_add(amount)?;
+        Ok(pending)
     })?;
     Ok(())
 }
 
+// called on the ack or timeout of a packet. Packets sent before the upgrade have nothing to settle
+pub fn settle_pending_packet(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    match PENDING_PACKETS.may_load(storage, (channel, denom))? {
+        Some(pending) if pending.count > 1 => PENDING_PACKETS.save(
+            storage,
+            (channel, denom),
+            &PendingPackets {
+                count: pending.count - 1,
+                amount: pending.amount.saturating_sub(amount),
+            },
+        ),
+        Some(_) => {
+            PENDING_PACKETS.remove(storage, (channel, denom));
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+// only used for admin of the contract when the remote decimals of a mapping change.
+// The channel state is kept in remote units, so every amount is converted to the new decimals.
+// Refunds of pending packets would use the old decimals, so the caller must check there are none
+pub fn rescale_channel_balance(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str, // should be ibc denom
+    from_decimals: u8,
+    to_decimals: u8,
+    block: &BlockInfo,
+) -> Result<(), ContractError> {
+    let Some(mut state) = CHANNEL_REVERSE_STATE.may_load(storage, (channel, denom))? else {
+        return Ok(());
+    };
+    // a rounded balance would no longer match the vouchers on the remote chain
+    let rescale = |amount: Uint128| -> Result<Uint128, ContractError> {
+        let (rescaled, remainder) = split_decimals(amount, from_decimals, to_decimals)?;
+        if !remainder.is_zero() {
+            return Err(ContractError::InexactRescale {
+                channel_id: channel.to_string(),
+                denom: denom.to_string(),
+                amount,
+            });
+        }
+        Ok(rescaled)
+    };
+    state.outstanding = rescale(state.outstanding)?;
+    state.total_sent = rescale(state.total_sent)?;
+    state.total_received = rescale(state.total_received)?;
+    state.total_returned = rescale(state.total_returned)?;
+    state.total_refunded = rescale(state.total_refunded)?;
+    CHANNEL_REVERSE_STATE.save(storage, (channel, denom), &state, block.height)?;
+    Ok(())
+}
+
 // this is like increase, but it only "un-subtracts" (= adds) outstanding, not total_sent
-// calling `reduce_channel_balance` and then `undo_reduce_channel_balance` should leave state unchanged.
+// calling `reduce_channel_balance` and then `undo_reduce_channel_balance` should leave the outstanding balance unchanged.
+// The failed transfer stays in total_returned and is also recorded in total_refunded
 pub fn undo_reduce_channel_balance(
     storage: &mut dyn Storage,
     channel: &str,
     denom: &str,
     amount: Uint128,
+    block: &BlockInfo,
 ) -> Result<(), ContractError> {
-    CHANNEL_REVERSE_STATE.update(storage, (channel, denom), |orig| -> StdResult<_> {
-        let mut state = orig.unwrap_or_default();
-        state.outstanding += amount;
-        Ok(state)
-    })?;
+    CHANNEL_REVERSE_STATE.update(
+        storage,
+        (channel, denom),
+        block.height,
+        |orig| -> StdResult<_> {
+            let mut state = orig.unwrap_or_default();
+            state.outstanding += amount;
+            state.total_refunded += amount;
+            state.last_activity = block.time;
+            Ok(state)
+        },
+    )?;
+    Ok(())
+}
+
+/// Checks that `minted` of a mint-burn mapping's asset is backed by the outstanding balance of its channels,
+/// plus `pending_outstanding` (local decimals) that has not been added to the channel state yet, and that it stays under the supply cap.
+pub fn assert_minted_supply(
+    storage: &dyn Storage,
+    pair_mapping: &MappingMetadata,
+    minted: Uint128,
+    pending_outstanding: Uint128,
+) -> Result<(), ContractError> {
+    let outstanding = get_local_outstanding_of_asset(storage, &pair_mapping.asset_info)?
+        .checked_add(pending_outstanding)?;
+    if minted > outstanding {
+        return Err(ContractError::MintExceedsOutstanding {
+            asset: pair_mapping.asset_info.to_string(),
+            minted,
+            outstanding,
+        });
+    }
+    if let Some(supply_cap) = pair_mapping.supply_cap {
+        if minted > supply_cap {
+            return Err(ContractError::SupplyCapExceeded {
+                asset: pair_mapping.asset_info.to_string(),
+                minted,
+                supply_cap,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks a single transfer of `remote_amount` (remote decimals) against the bounds of its mapping
+pub fn assert_transfer_amount(
+    pair_mapping: &MappingMetadata,
+    remote_amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(min_amount) = pair_mapping.min_amount {
+        if remote_amount < min_amount {
+            return Err(ContractError::AmountBelowMinimum {
+                amount: remote_amount,
+                min_amount,
+            });
+        }
+    }
+    if let Some(max_amount) = pair_mapping.max_amount {
+        if remote_amount > max_amount {
+            return Err(ContractError::AmountAboveMaximum {
+                amount: remote_amount,
+                max_amount,
+            });
+        }
+    }
+    Ok(())
+}
+
+// must be called after the channel balance has been increased, so the new outstanding backs the mint
+pub fn increase_minted_supply(
+    storage: &mut dyn Storage,
+    pair_mapping: &MappingMetadata,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if !pair_mapping.is_mint_burn {
+        return Ok(());
+    }
+    let key = pair_mapping.asset_info.to_string();
+    let minted = MINTED_SUPPLY
+        .may_load(storage, &key)?
+        .unwrap_or_default()
+        .checked_add(amount)?;
+    assert_minted_supply(storage, pair_mapping, minted, Uint128::zero())?;
+    MINTED_SUPPLY.save(storage, &key, &minted)?;
+    Ok(())
+}
+
+// refunds mint back tokens that were counted before the packet burned them, so neither the supply cap nor the
+// outstanding invariant are checked again. Otherwise a lowered cap would block the ack or timeout of the packet
+pub fn restore_minted_supply(
+    storage: &mut dyn Storage,
+    pair_mapping: &MappingMetadata,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if !pair_mapping.is_mint_burn {
+        return Ok(());
+    }
+    MINTED_SUPPLY.update(
+        storage,
+        &pair_mapping.asset_info.to_string(),
+        |minted| -> StdResult<_> { Ok(minted.unwrap_or_default().checked_add(amount)?) },
+    )?;
+    Ok(())
+}
+
+// the supply of upgraded contracts is seeded from their outstanding balance and may undercount, so we saturate
+// instead of failing the burn
+pub fn reduce_minted_supply(
+    storage: &mut dyn Storage,
+    pair_mapping: &MappingMetadata,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if !pair_mapping.is_mint_burn {
+        return Ok(());
+    }
+    MINTED_SUPPLY.update(
+        storage,
+        &pair_mapping.asset_info.to_string(),
+        |minted| -> StdResult<_> { Ok(minted.unwrap_or_default().saturating_sub(amount)) },
+    )?;
     Ok(())
 }
 
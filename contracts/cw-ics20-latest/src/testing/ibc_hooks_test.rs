@@ -1,14 +1,20 @@
-use cosmwasm_std::{coin, coins, Addr, BankMsg, Binary, CosmosMsg, SubMsg};
+use anybuf::Anybuf;
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    coin, coins, from_json, Addr, Api, BankMsg, Binary, DepsMut, StdError, SubMsg, WasmMsg,
+};
 use cosmwasm_testing_util::mock::MockContract;
 use cosmwasm_vm::testing::MockInstanceOptions;
 use cw20_ics20_msg::{ibc_hooks::HookMethods, msg::UpdatePairMsg, state::Ratio};
 use oraiswap::asset::AssetInfo;
 
 use crate::{
-    ibc::NATIVE_RECEIVE_ID,
-    msg::{AllowMsg, ExecuteMsg, InitMsg},
+    contract::{execute, query},
+    ibc::UNIVERSAL_SWAP_ERROR_ID,
+    msg::{AllowMsg, ExecuteMsg, InitMsg, QueryMsg},
     state::TOKEN_FEE,
-    testing::test_helpers::{DEFAULT_TIMEOUT, WASM_BYTES},
+    testing::test_helpers::{setup, DEFAULT_TIMEOUT, WASM_BYTES},
+    ContractError,
 };
 
 const SENDER: &str = "orai1gkr56hlnx9vc7vncln2dkd896zfsqjn300kfq0";
@@ -84,14 +90,21 @@ fn test_ibc_hooks_receive() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     contract_instance
         .execute(ExecuteMsg::UpdateMappingPair(pair), SENDER, &[])
         .unwrap();
 
-    // case 1: empty args
-    let res = contract_instance
+    // case 1: empty args are rejected before any funds are forwarded
+    contract_instance
         .execute(
             ExecuteMsg::IbcHooksReceive {
                 func: HookMethods::UniversalSwap,
@@ -101,17 +114,7 @@ fn test_ibc_hooks_receive() {
             SENDER,
             &vec![coin(100_000_000_000, "ibc/orai")],
         )
-        .unwrap();
-    assert_eq!(
-        res.0.messages,
-        vec![SubMsg::reply_on_error(
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: "orai1gkr56hlnx9vc7vncln2dkd896zfsqjn300kfq0".to_string(),
-                amount: vec![coin(100_000_000_000, "ibc/orai")]
-            }),
-            NATIVE_RECEIVE_ID
-        )]
-    );
+        .unwrap_err();
 
     // assert_eq!(
     //     res.0.attributes,
@@ -198,3 +201,186 @@ fn test_ibc_hooks_receive() {
     //     ]
     // );
 }
+
+#[test]
+fn test_ibc_hooks_contract_call() {
+    let mut deps = setup(&["channel-0"], &[]);
+    let hook_contract = "hook_contract";
+    let args = Binary::from(
+        Anybuf::new()
+            .append_bytes(
+                1,
+                deps.api
+                    .addr_canonicalize(hook_contract)
+                    .unwrap()
+                    .as_slice(),
+            )
+            .append_bytes(2, br#"{"ping":{}}"#)
+            .as_bytes(),
+    );
+    let hook_msg = |func: HookMethods, args: Binary| ExecuteMsg::IbcHooksReceive {
+        func,
+        orai_receiver: SENDER.to_string(),
+        args,
+    };
+    let info = mock_info("ibc_hooks", &coins(100, "orai"));
+
+    assert_eq!(
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            hook_msg(HookMethods::ContractCall, args.clone())
+        )
+        .unwrap_err(),
+        ContractError::HookContractNotAllowed {
+            contract: hook_contract.to_string()
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateHookContract {
+            contract: hook_contract.to_string(),
+            allowed: true,
+            reason: "integrate dapp".to_string(),
+        },
+    )
+    .unwrap();
+    let hook_contracts: Vec<String> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HookContracts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(hook_contracts, vec![hook_contract.to_string()]);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        hook_msg(HookMethods::ContractCall, args),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: hook_contract.to_string(),
+            msg: Binary::from(br#"{"ping":{}}"#),
+            funds: coins(100, "orai"),
+        })]
+    );
+
+    // plain transfer to the receiver in the args
+    let args = Binary::from(
+        Anybuf::new()
+            .append_bytes(
+                1,
+                deps.api.addr_canonicalize("receiver").unwrap().as_slice(),
+            )
+            .as_bytes(),
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        hook_msg(HookMethods::Transfer, args),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(100, "orai"),
+        })]
+    );
+}
+
+#[test]
+fn test_ibc_hooks_universal_swap_validation() {
+    let mut deps = setup(&["channel-0"], &[]);
+    let api = deps.api;
+    let universal_swap_args = |channel_id: &str, denom: &str| {
+        Binary::from(
+            Anybuf::new()
+                .append_bytes(1, api.addr_canonicalize(SENDER).unwrap().as_slice())
+                .append_string(2, "0x1234")
+                .append_string(3, channel_id)
+                .append_string(4, denom)
+                .as_bytes(),
+        )
+    };
+    let universal_swap = |deps: DepsMut, args: Binary| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("ibc_hooks", &coins(100, "orai")),
+            ExecuteMsg::IbcHooksReceive {
+                func: HookMethods::UniversalSwap,
+                orai_receiver: SENDER.to_string(),
+                args,
+            },
+        )
+    };
+
+    assert_eq!(
+        universal_swap(deps.as_mut(), universal_swap_args("channel-0", "")).unwrap_err(),
+        ContractError::Std(StdError::generic_err("Require destination denom in memo"))
+    );
+    // channel of this contract without a mapping of the destination denom
+    assert_eq!(
+        universal_swap(
+            deps.as_mut(),
+            universal_swap_args("channel-0", "oraib0x1234")
+        )
+        .unwrap_err(),
+        ContractError::MappingPairNotFound {}
+    );
+    // unknown channel
+    assert_eq!(
+        universal_swap(deps.as_mut(), universal_swap_args("channel-99", "uatom")).unwrap_err(),
+        ContractError::NoSuchChannel {
+            id: "channel-99".to_string()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: "channel-0".to_string(),
+            denom: "oraib0x1234".to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    let res = universal_swap(
+        deps.as_mut(),
+        universal_swap_args("channel-0", "oraib0x1234"),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, UNIVERSAL_SWAP_ERROR_ID);
+    // destination is Oraichain
+    universal_swap(deps.as_mut(), universal_swap_args("", "orai")).unwrap();
+}
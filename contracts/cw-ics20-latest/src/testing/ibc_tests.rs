@@ -3,9 +3,9 @@ use std::ops::Sub;
 use std::vec;
 
 use cosmwasm_std::{
-    wasm_execute, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Event,
-    IbcChannelConnectMsg, IbcChannelOpenMsg, Reply, Response, StdError, StdResult, SubMsgResponse,
-    SubMsgResult,
+    attr, wasm_execute, Addr, Attribute, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, Decimal,
+    Deps, DepsMut, Event, IbcChannelConnectMsg, IbcChannelOpenMsg, Reply, Response, StdError,
+    StdResult, SubMsgResponse, SubMsgResult,
 };
 use cosmwasm_testing_util::mock::MockContract;
 use cosmwasm_vm::testing::MockInstanceOptions;
@@ -14,44 +14,58 @@ use cw20_ics20_msg::helper::get_full_denom;
 use cw_controllers::AdminError;
 use oraiswap::asset::AssetInfo;
 use oraiswap::router::RouterController;
+use proptest::prelude::*;
 use token_bindings::Metadata;
 
 use crate::ibc::{
-    convert_remote_denom_to_evm_prefix, deduct_fee, deduct_relayer_fee, deduct_token_fee,
-    get_follow_up_msgs, get_swap_token_amount_out_from_orai, handle_packet_refund,
-    ibc_packet_receive, parse_ibc_channel_without_sanity_checks,
-    parse_ibc_denom_without_sanity_checks, parse_ibc_info_without_sanity_checks,
-    parse_voucher_denom, reply, Ics20Ack, Ics20Packet, ICS20_VERSION, NATIVE_RECEIVE_ID,
-    REFUND_FAILURE_ID,
+    ack_fail, check_gas_limit, convert_remote_denom_to_evm_prefix, deduct_fee, deduct_relayer_fee,
+    deduct_token_fee, get_follow_up_msgs, get_gas_limit, get_swap_token_amount_out_from_orai,
+    handle_packet_refund, ibc_packet_ack, ibc_packet_receive, ibc_packet_timeout,
+    parse_ibc_channel_without_sanity_checks, parse_ibc_denom_without_sanity_checks,
+    parse_ibc_info_without_sanity_checks, parse_voucher_denom, process_deduct_fee, reply, Ics20Ack,
+    Ics20Packet, DEFAULT_CALLBACK_GAS_LIMIT, ICS20_VERSION, LIFECYCLE_CALLBACK_FAILURE_ID,
+    NATIVE_RECEIVE_ID, REFUND_FAILURE_ID, SEND_PACKET_CALLBACK_ID, UNIVERSAL_SWAP_ERROR_ID,
 };
-use crate::query_helper::get_destination_info_on_orai;
+use crate::migrations;
+use crate::query_helper::{get_destination_info_on_orai, get_local_obligations_of_asset};
 use crate::testing::test_helpers::*;
 use cosmwasm_std::{
-    from_json, to_json_binary, IbcEndpoint, IbcMsg, IbcPacket, IbcPacketReceiveMsg, SubMsg,
-    Timestamp, Uint128, WasmMsg,
+    from_json, to_json_binary, to_json_string, IbcAcknowledgement, IbcEndpoint, IbcMsg, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, Order, SubMsg, Timestamp, Uint128,
+    WasmMsg,
 };
 
 use crate::error::ContractError;
 use crate::state::{
-    get_key_ics20_ibc_denom, ics20_denoms, increase_channel_balance, reduce_channel_balance,
-    Config, RefundInfo, ADMIN, CHANNEL_REVERSE_STATE, CONFIG, REFUND_INFO, REFUND_INFO_LIST,
-    RELAYER_FEE, REPLY_ARGS, TOKEN_FEE,
+    accumulate_dust, add_pending_packet, get_key_ics20_ibc_denom, ics20_denoms,
+    increase_channel_balance, increase_minted_supply, reduce_channel_balance,
+    settle_pending_packet, undo_reduce_channel_balance, AuditAction, AuditEntry, ChannelState,
+    Config, DustBalance, RefundInfo, Role, TimelockAction, TimelockOperation, ADMIN, ALLOW_LIST,
+    CHANNEL_COUNT, CHANNEL_HISTORY_START, CHANNEL_REVERSE_STATE, CONFIG, DEFAULT_TIMELOCK_DELAY,
+    MAPPING_COUNT, MINTED_SUPPLY, REFUND_INFO, REFUND_INFO_LIST, RELAYER_FEE, REPLY_ARGS,
+    TOKEN_FEE,
 };
 use cw20::{Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw20_ics20_msg::amount::{convert_remote_to_local, Amount};
-use cw20_ics20_msg::state::{MappingMetadata, Ratio, RelayerFee, TokenFee};
+use cw20_ics20_msg::state::{
+    AllowInfo, DustPolicy, MappingMetadata, MappingStatus, Ratio, RelayerFee, TokenFee,
+};
 
 use crate::contract::{
     build_burn_mapping_msg, build_mint_mapping_msg, execute, handle_override_channel_balance,
-    query, query_channel, query_channel_with_key, sudo,
+    migrate, query, query_channel, query_channel_with_key, sudo,
 };
 use crate::msg::{
-    AllowMsg, ChannelResponse, ConfigResponse, ExecuteMsg, InitMsg, ListChannelsResponse,
-    ListMappingResponse, PairQuery, QueryMsg, RegisterDenomMsg, SudoMsg,
+    AllowMsg, AllowedResponse, ChannelBalanceChange, ChannelBalanceHistoryResponse,
+    ChannelResponse, ChannelStats, ConfigResponse, ContractInfoResponse, DustResponse, ExecuteMsg,
+    InitMsg, ListAuditLogResponse, ListChannelsResponse, ListMappingDetailResponse,
+    ListMappingResponse, ListReconcileResponse, ListRolesResponse, MigrateMsg, OwnershipResponse,
+    PairMappingDetail, PairQuery, QueryMsg, ReconcileResponse, RegisterDenomMsg, RoleGrant,
+    RolesResponse, SudoMsg, TimelockOperationsResponse, UpdateValue,
 };
-use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{coins, to_json_vec};
-use cw20_ics20_msg::msg::{DeletePairMsg, TransferBackMsg, UpdatePairMsg};
+use cw20_ics20_msg::msg::{DeletePairMsg, IbcLifecycleCallbackMsg, TransferBackMsg, UpdatePairMsg};
 
 const SENDER: &str = "orai1gkr56hlnx9vc7vncln2dkd896zfsqjn300kfq0";
 const CONTRACT: &str = "orai19p43y0tqnr5qlhfwnxft2u5unph5yn60y7tuvu";
@@ -94,45 +108,76 @@ fn check_packet_json() {
     assert_eq!(expected, encdoded.as_str());
 }
 
-// #[test]
-// fn check_gas_limit_handles_all_cases() {
-//     let send_channel = "channel-9";
-//     let allowed = "foobar";
-//     let allowed_gas = 777666;
-//     let mut deps = setup(&[send_channel], &[(allowed, allowed_gas)]);
-
-//     // allow list will get proper gas
-//     let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, allowed)).unwrap();
-//     assert_eq!(limit, Some(allowed_gas));
-
-//     // non-allow list will error
-//     let random = "tokenz";
-//     check_gas_limit(deps.as_ref(), &Amount::cw20(500, random)).unwrap_err();
-
-//     // add default_gas_limit
-//     let def_limit = 54321;
-//     migrate(
-//         deps.as_mut(),
-//         mock_env(),
-//         MigrateMsg {
-//             // default_gas_limit: Some(def_limit),
-//             // token_fee_receiver: "receiver".to_string(),
-//             // relayer_fee_receiver: "relayer_fee_receiver".to_string(),
-//             // default_timeout: 100u64,
-//             // fee_denom: "orai".to_string(),
-//             // swap_router_contract: "foobar".to_string(),
-//         },
-//     )
-//     .unwrap();
+#[test]
+fn check_gas_limit_handles_all_cases() {
+    let send_channel = "channel-9";
+    let allowed = "foobar";
+    let allowed_gas = 777666;
+    let mut deps = setup(&[send_channel], &[(allowed, allowed_gas)]);
 
-//     // allow list still gets proper gas
-//     let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, allowed)).unwrap();
-//     assert_eq!(limit, Some(allowed_gas));
+    // allow list will get proper gas
+    let limit = check_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(allowed)),
+    )
+    .unwrap();
+    assert_eq!(limit, Some(allowed_gas));
 
-//     // non-allow list will now get default
-//     let limit = check_gas_limit(deps.as_ref(), &Amount::cw20(500, random)).unwrap();
-//     assert_eq!(limit, Some(def_limit));
-// }
+    // non-allow list is rejected
+    let random = "tokenz";
+    let err = check_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(random)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotOnAllowList);
+    // receives and refunds are not limited instead, they must not fail
+    let limit = get_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(random)),
+    )
+    .unwrap();
+    assert_eq!(limit, None);
+
+    // add default_gas_limit
+    let def_limit = 54321;
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.default_gas_limit = Some(def_limit);
+            Ok(config)
+        })
+        .unwrap();
+
+    // allow list still gets proper gas
+    let limit = check_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(allowed)),
+    )
+    .unwrap();
+    assert_eq!(limit, Some(allowed_gas));
+
+    // non-allow list is still rejected, but receives and refunds get the default
+    let err = check_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(random)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotOnAllowList);
+    let limit = get_gas_limit(
+        deps.as_ref().storage,
+        &Amount::cw20(500u128.into(), Addr::unchecked(random)),
+    )
+    .unwrap();
+    assert_eq!(limit, Some(def_limit));
+
+    // native tokens are never limited
+    let limit = check_gas_limit(
+        deps.as_ref().storage,
+        &Amount::native(500u128.into(), "orai".to_string()),
+    )
+    .unwrap();
+    assert_eq!(limit, None);
+}
 
 // test remote chain send native token to local chain
 fn mock_receive_packet_remote_to_local(
@@ -298,7 +343,12 @@ fn send_native_from_remote_mapping_not_found() {
             },
             remote_decimals: 1,
             asset_info_decimals: 1,
-            is_mint_burn: true
+            is_mint_burn: true,
+            supply_cap: None,
+            dust_policy: DustPolicy::Truncate,
+            status: MappingStatus::Active,
+            min_amount: None,
+            max_amount: None
         }
     );
 }
@@ -337,6 +387,13 @@ fn proper_checks_on_execute_native_transfer_back_to_remote() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     let _ = execute(
@@ -354,6 +411,8 @@ fn proper_checks_on_execute_native_transfer_back_to_remote() {
         remote_denom: denom.to_string(),
         timeout: Some(DEFAULT_TIMEOUT),
         memo: None,
+        remote_prefix: None,
+        callback: None,
     };
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
@@ -362,8 +421,19 @@ fn proper_checks_on_execute_native_transfer_back_to_remote() {
         msg: to_json_binary(&transfer).unwrap(),
     });
 
-    // insufficient funds case because we need to receive from remote chain first
+    // cw20 tokens off the allow list cannot be sent
     let info = mock_info(cw20_raw_denom, &[]);
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::NotOnAllowList);
+    ALLOW_LIST
+        .save(
+            deps.as_mut().storage,
+            &token_addr,
+            &AllowInfo { gas_limit: None },
+        )
+        .unwrap();
+
+    // insufficient funds case because we need to receive from remote chain first
     let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
     assert_eq!(
         res.unwrap_err(),
@@ -473,6 +543,17 @@ fn proper_checks_on_execute_native_transfer_back_to_remote() {
             get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel, denom)
         )]
     );
+    assert_eq!(
+        chan.stats,
+        vec![ChannelStats {
+            denom: get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel, denom),
+            total_received: amount,
+            total_returned: amount - fee_amount,
+            total_refunded: Uint128::zero(),
+            last_activity: mock_env().block.time,
+            transfer_count: 2,
+        }]
+    );
 
     // mapping pair error with wrong voucher denom
     let pair = UpdatePairMsg {
@@ -484,6 +565,13 @@ fn proper_checks_on_execute_native_transfer_back_to_remote() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     execute(
@@ -586,6 +674,13 @@ fn send_from_remote_to_local_receive_happy_path() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     contract_instance
@@ -958,6 +1053,13 @@ fn test_query_pair_mapping_by_asset_info() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     // works with proper funds
@@ -1042,6 +1144,13 @@ fn test_update_cw20_mapping() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     // works with proper funds
@@ -1050,7 +1159,12 @@ fn test_update_cw20_mapping() {
     // unauthorized case
     let info = mock_info("foobar", &coins(1234567, "ucosm"));
     let res_err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
-    assert_eq!(res_err, ContractError::Admin(AdminError::NotAdmin {}));
+    assert_eq!(
+        res_err,
+        ContractError::MissingRole {
+            role: Role::MappingManager
+        }
+    );
 
     let info = mock_info("gov", &coins(1234567, "ucosm"));
     execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
@@ -1117,6 +1231,214 @@ fn test_update_cw20_mapping() {
     )
 }
 
+#[test]
+fn test_update_mapping_decimals() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let mut update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 18,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
+    };
+    let update_pair = |deps: DepsMut, update: &UpdatePairMsg| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::UpdateMappingPair(update.clone()),
+        )
+    };
+    update_pair(deps.as_mut(), &update).unwrap();
+
+    // decimals can be changed freely while nothing is outstanding
+    update.remote_decimals = 12;
+    let res = update_pair(deps.as_mut(), &update).unwrap();
+    assert!(res.attributes.contains(&attr("old_remote_decimals", "18")));
+    assert!(res.attributes.contains(&attr("new_remote_decimals", "12")));
+
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(3_000_000_000_000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+
+    // other fields can still be updated
+    update.supply_cap = Some(UpdateValue::Set(Uint128::from(100u128)));
+    update_pair(deps.as_mut(), &update).unwrap();
+
+    // the outstanding balance is backed by the current asset and mode, so neither can change
+    let asset_err = ContractError::AssetChangeWithOutstanding {
+        channel_id: local_channel_id.to_string(),
+        denom: ibc_denom.clone(),
+        outstanding: Uint128::from(3_000_000_000_000u128),
+        pending: 0,
+    };
+    let mut asset_update = update.clone();
+    asset_update.local_asset_info = AssetInfo::NativeToken {
+        denom: "atom".to_string(),
+    };
+    assert_eq!(
+        update_pair(deps.as_mut(), &asset_update).unwrap_err(),
+        asset_err
+    );
+    let mut mode_update = update.clone();
+    mode_update.is_mint_burn = Some(true);
+    assert_eq!(
+        update_pair(deps.as_mut(), &mode_update).unwrap_err(),
+        asset_err
+    );
+
+    let outstanding_err = ContractError::DecimalsChangeWithOutstanding {
+        channel_id: local_channel_id.to_string(),
+        denom: ibc_denom.clone(),
+        outstanding: Uint128::from(3_000_000_000_000u128),
+    };
+    update.remote_decimals = 18;
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        outstanding_err
+    );
+    // local decimals cannot be rescaled since the balance is kept in remote units
+    update.remote_decimals = 12;
+    update.local_asset_info_decimals = 18;
+    update.rescale_outstanding = Some(true);
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        outstanding_err
+    );
+
+    // remote decimals change with rescale converts the channel balance
+    update.remote_decimals = 18;
+    update.local_asset_info_decimals = 6;
+    update_pair(deps.as_mut(), &update).unwrap();
+    let state = CHANNEL_REVERSE_STATE
+        .load(deps.as_ref().storage, (local_channel_id, &ibc_denom))
+        .unwrap();
+    assert_eq!(
+        state.outstanding,
+        Uint128::from(3_000_000_000_000_000_000u128)
+    );
+    assert_eq!(
+        state.total_sent,
+        Uint128::from(3_000_000_000_000_000_000u128)
+    );
+    assert_eq!(
+        ics20_denoms()
+            .load(deps.as_ref().storage, &ibc_denom)
+            .unwrap()
+            .remote_decimals,
+        18
+    );
+
+    // decimals are bounded so that conversions cannot overflow
+    update.remote_decimals = 19;
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        ContractError::DecimalsTooLarge {
+            decimals: 19,
+            max: 18
+        }
+    );
+
+    // a balance that the new decimals cannot represent is not rounded
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::one(),
+        &mock_env().block,
+    )
+    .unwrap();
+    update.remote_decimals = 12;
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        ContractError::InexactRescale {
+            channel_id: local_channel_id.to_string(),
+            denom: ibc_denom.clone(),
+            amount: Uint128::from(3_000_000_000_000_000_001u128),
+        }
+    );
+    reduce_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::one(),
+        &mock_env().block,
+    )
+    .unwrap();
+
+    // a pending packet would be refunded in the old decimals
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &coins(1_000_000, "orai")),
+        ExecuteMsg::TransferToRemote(TransferBackMsg {
+            local_channel_id: local_channel_id.to_string(),
+            remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+            remote_denom: "earth".to_string(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            memo: None,
+            remote_prefix: None,
+            callback: None,
+        }),
+    )
+    .unwrap();
+    let packet = match res.messages.last().unwrap().msg.clone() {
+        CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => IbcPacket::new(
+            data,
+            IbcEndpoint {
+                port_id: CONTRACT_PORT.to_string(),
+                channel_id: local_channel_id.to_string(),
+            },
+            IbcEndpoint {
+                port_id: REMOTE_PORT.to_string(),
+                channel_id: "channel-1234".to_string(),
+            },
+            1,
+            Timestamp::from_nanos(DEFAULT_TIMEOUT).into(),
+        ),
+        msg => panic!("Unexpected return message: {:?}", msg),
+    };
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        ContractError::RescaleWithPendingPackets {
+            channel_id: local_channel_id.to_string(),
+            denom: ibc_denom.clone(),
+            pending: 1,
+        }
+    );
+    ibc_packet_timeout(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketTimeoutMsg::new(packet, Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    update_pair(deps.as_mut(), &update).unwrap();
+    assert_eq!(
+        CHANNEL_REVERSE_STATE
+            .load(deps.as_ref().storage, (local_channel_id, &ibc_denom))
+            .unwrap()
+            .outstanding,
+        Uint128::from(3_000_000_000_000_000u128)
+    );
+}
+
 #[test]
 fn test_delete_cw20_mapping() {
     let mut deps = setup(&["channel-3", "channel-7"], &[]);
@@ -1131,6 +1453,13 @@ fn test_delete_cw20_mapping() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     // works with proper funds
@@ -1161,6 +1490,7 @@ fn test_delete_cw20_mapping() {
     let delete = DeletePairMsg {
         local_channel_id: "mars-channel".to_string(),
         denom: "earth".to_string(),
+        reason: "delete mapping pair".to_string(),
     };
 
     let mut msg = ExecuteMsg::DeleteMappingPair(delete.clone());
@@ -1168,7 +1498,12 @@ fn test_delete_cw20_mapping() {
     // unauthorized delete case
     let info = mock_info("foobar", &coins(1234567, "ucosm"));
     let delete_err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
-    assert_eq!(delete_err, ContractError::Admin(AdminError::NotAdmin {}));
+    assert_eq!(
+        delete_err,
+        ContractError::MissingRole {
+            role: Role::MappingManager
+        }
+    );
 
     let info = mock_info("gov", &coins(1234567, "ucosm"));
 
@@ -1405,6 +1740,13 @@ fn proper_checks_on_execute_cw20_transfer_back_to_remote() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     let _ = execute(
@@ -1422,6 +1764,8 @@ fn proper_checks_on_execute_cw20_transfer_back_to_remote() {
         remote_denom: denom.to_string(),
         timeout: Some(DEFAULT_TIMEOUT),
         memo: None,
+        remote_prefix: None,
+        callback: None,
     };
 
     let msg = ExecuteMsg::TransferToRemote(transfer.clone());
@@ -1539,6 +1883,13 @@ fn proper_checks_on_execute_cw20_transfer_back_to_remote() {
         remote_decimals: 18u8,
         local_asset_info_decimals: 18u8,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     execute(
@@ -1560,7 +1911,7 @@ fn test_update_config() {
     // arrange
     let mut deps = setup(&[], &[]);
     let new_config = ExecuteMsg::UpdateConfig {
-        admin: Some("helloworld".to_string()),
+        admin: Some(UpdateValue::Set("helloworld".to_string())),
         default_timeout: Some(1),
         default_gas_limit: None,
         swap_router_contract: Some("new_router".to_string()),
@@ -1589,6 +1940,7 @@ fn test_update_config() {
         converter_contract: Some("new_converter".to_string()),
         osor_entrypoint_contract: Some("new_osor_contract".to_string()),
         token_factory_addr: Some("new_token_factory_addr".to_string()),
+        reason: "update config".to_string(),
     };
     // unauthorized case
     let unauthorized_info = mock_info(&String::from("somebody"), &[]);
@@ -1603,6 +1955,12 @@ fn test_update_config() {
     // valid case
     let info = mock_info(&String::from("gov"), &[]);
     execute(deps.as_mut(), mock_env(), info, new_config).unwrap();
+    // dependencies only change once the timelock delay has passed
+    let config: ConfigResponse =
+        from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.default_timeout, 1);
+    assert_eq!(config.swap_router_contract, "router".to_string());
+    execute_timelock_after_delay(deps.as_mut(), mock_env(), 0);
     let config: ConfigResponse =
         from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
     assert_eq!(config.default_gas_limit, None);
@@ -1628,6 +1986,31 @@ fn test_update_config() {
     assert_eq!(config.relayer_fees.len(), 1);
     assert_eq!(config.relayer_fees[0].prefix, "foo".to_string());
     assert_eq!(config.relayer_fees[0].amount, Uint128::from(1000000u64));
+    assert_eq!(
+        config.token_factory_addr,
+        Addr::unchecked("new_token_factory_addr")
+    );
+    assert_eq!(config.pending_owner, Some(Addr::unchecked("helloworld")));
+    // the proposed owner is audited like a transfer of the ownership
+    let res: ListAuditLogResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AuditLog {
+                action: Some(AuditAction::TransferOwnership),
+                start_after: None,
+                limit: None,
+                order: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.entries.len(), 1);
+    assert_eq!(res.entries[0].subject, "pending_owner");
+    assert_eq!(res.entries[0].reason, "update config");
+    assert_eq!(res.entries[0].before, None);
+    assert_eq!(res.entries[0].after, Some("helloworld".to_string()));
 }
 
 #[test]
@@ -1678,6 +2061,13 @@ fn test_handle_packet_refund() {
         remote_decimals: 6,
         local_asset_info_decimals: 6,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     let msg = ExecuteMsg::UpdateMappingPair(update.clone());
@@ -1783,6 +2173,15 @@ fn test_handle_packet_refund() {
     let msg = ExecuteMsg::UpdateMappingPair(update.clone());
     let info = mock_info("gov", &coins(1234567, "ucosm"));
     execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+    // the re-minted amount must be backed by the outstanding balance of the channel
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &mapping_denom,
+        amount,
+        &mock_env().block,
+    )
+    .unwrap();
     let result =
         handle_packet_refund(deps.as_mut().storage, sender, &mapping_denom, amount, true).unwrap();
     assert_eq!(
@@ -1828,6 +2227,13 @@ fn test_increase_channel_balance_ibc_receive() {
         remote_decimals: 6,
         local_asset_info_decimals: 6,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     execute(
         deps.as_mut(),
@@ -1903,6 +2309,13 @@ fn test_reduce_channel_balance_ibc_receive() {
         remote_decimals: 6,
         local_asset_info_decimals: 6,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     execute(
         deps.as_mut(),
@@ -1973,17 +2386,30 @@ fn test_query_channel_balance_with_key() {
     let amount = Uint128::from(10u128);
     let reduce_amount = Uint128::from(1u128);
     let mut deps = setup(&[channel], &[]);
-    increase_channel_balance(deps.as_mut().storage, channel, ibc_denom, amount).unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        amount,
+        &mock_env().block,
+    )
+    .unwrap();
     reduce_channel_balance(
         deps.as_mut().storage,
         channel,
         ibc_denom,
         Uint128::from(1u128),
+        &mock_env().block,
     )
     .unwrap();
 
-    let result =
-        query_channel_with_key(deps.as_ref(), channel.to_string(), ibc_denom.to_string()).unwrap();
+    let result = query_channel_with_key(
+        deps.as_ref(),
+        channel.to_string(),
+        ibc_denom.to_string(),
+        None,
+    )
+    .unwrap();
     assert_eq!(
         result.balance,
         Amount::from_parts(
@@ -1997,6 +2423,89 @@ fn test_query_channel_balance_with_key() {
     );
 }
 
+#[test]
+fn test_channel_stats() {
+    let channel = "foo-channel";
+    let ibc_denom = "port/channel/denom";
+    let mut deps = setup(&[channel], &[]);
+    let mut env = mock_env();
+
+    increase_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(10u128),
+        &env.block,
+    )
+    .unwrap();
+    env.block.height += 1;
+    env.block.time = env.block.time.plus_seconds(5);
+    reduce_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(4u128),
+        &env.block,
+    )
+    .unwrap();
+    // the transfer back times out, so the balance is refunded
+    env.block.height += 1;
+    env.block.time = env.block.time.plus_seconds(5);
+    undo_reduce_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(4u128),
+        &env.block,
+    )
+    .unwrap();
+
+    let chan = query_channel(deps.as_ref(), channel.to_string()).unwrap();
+    assert_eq!(
+        chan.balances,
+        vec![Amount::from_parts(
+            ibc_denom.to_string(),
+            Uint128::from(10u128)
+        )]
+    );
+    assert_eq!(
+        chan.stats,
+        vec![ChannelStats {
+            denom: ibc_denom.to_string(),
+            total_received: Uint128::from(10u128),
+            total_returned: Uint128::from(4u128),
+            total_refunded: Uint128::from(4u128),
+            last_activity: env.block.time,
+            transfer_count: 2,
+        }]
+    );
+
+    // states saved before the stats existed get their inbound total from total_sent
+    let legacy_denom = "port/channel/legacy";
+    CHANNEL_REVERSE_STATE
+        .save(
+            deps.as_mut().storage,
+            (channel, legacy_denom),
+            &ChannelState {
+                outstanding: Uint128::from(7u128),
+                total_sent: Uint128::from(20u128),
+                ..Default::default()
+            },
+            env.block.height,
+        )
+        .unwrap();
+    migrations::v3::migrate_channel_stats(deps.as_mut().storage, &env.block).unwrap();
+    let state = CHANNEL_REVERSE_STATE
+        .load(deps.as_ref().storage, (channel, legacy_denom))
+        .unwrap();
+    assert_eq!(state.total_received, Uint128::from(20u128));
+    // migrated states are left untouched
+    let state = CHANNEL_REVERSE_STATE
+        .load(deps.as_ref().storage, (channel, ibc_denom))
+        .unwrap();
+    assert_eq!(state.total_received, Uint128::from(10u128));
+}
+
 #[test]
 fn test_handle_override_channel_balance() {
     // fixture
@@ -2006,16 +2515,25 @@ fn test_handle_override_channel_balance() {
     let override_amount = Uint128::from(100u128);
     let total_sent_override = Uint128::from(1000u128);
     let mut deps = setup(&[channel], &[]);
-    increase_channel_balance(deps.as_mut().storage, channel, ibc_denom, amount).unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        amount,
+        &mock_env().block,
+    )
+    .unwrap();
 
     // unauthorized case
     let unauthorized = handle_override_channel_balance(
         deps.as_mut(),
+        mock_env(),
         mock_info("attacker", &vec![]),
         channel.to_string(),
         ibc_denom.to_string(),
         amount,
         None,
+        "override channel balance".to_string(),
     )
     .unwrap_err();
     assert_eq!(unauthorized, ContractError::Admin(AdminError::NotAdmin {}));
@@ -2023,18 +2541,26 @@ fn test_handle_override_channel_balance() {
     // execution, valid case
     handle_override_channel_balance(
         deps.as_mut(),
+        mock_env(),
         mock_info("gov", &vec![]),
         channel.to_string(),
         ibc_denom.to_string(),
         override_amount,
         Some(total_sent_override),
+        "override channel balance".to_string(),
     )
     .unwrap();
+    execute_timelock_after_delay(deps.as_mut(), mock_env(), 0);
 
     // we query to validate the result after overriding
 
-    let result =
-        query_channel_with_key(deps.as_ref(), channel.to_string(), ibc_denom.to_string()).unwrap();
+    let result = query_channel_with_key(
+        deps.as_ref(),
+        channel.to_string(),
+        ibc_denom.to_string(),
+        None,
+    )
+    .unwrap();
     assert_eq!(
         result.balance,
         Amount::from_parts(ibc_denom.to_string(), override_amount)
@@ -2058,6 +2584,13 @@ fn test_get_destination_info_on_orai() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
 
     // works with proper funds
@@ -2111,7 +2644,12 @@ fn test_get_destination_info_on_orai() {
                 },
                 remote_decimals: 18,
                 asset_info_decimals: 18,
-                is_mint_burn: false
+                is_mint_burn: false,
+                supply_cap: None,
+                dust_policy: DustPolicy::Truncate,
+                status: MappingStatus::Active,
+                min_amount: None,
+                max_amount: None
             }
         })
     );
@@ -2155,6 +2693,13 @@ fn test_build_mint_mapping_msg() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     let msg = ExecuteMsg::UpdateMappingPair(update.clone());
     let info = mock_info("gov", &coins(1234567, "ucosm"));
@@ -2243,6 +2788,13 @@ fn test_build_burn_mapping_msg() {
         remote_decimals: 18,
         local_asset_info_decimals: 18,
         is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     let msg = ExecuteMsg::UpdateMappingPair(update.clone());
     let info = mock_info("gov", &coins(1234567, "ucosm"));
@@ -2336,6 +2888,13 @@ fn test_increase_channel_balance_ibc_receive_with_mint_burn() {
         remote_decimals: 18,
         local_asset_info_decimals: 6,
         is_mint_burn: Some(true),
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     execute(
         deps.as_mut(),
@@ -2433,6 +2992,13 @@ fn test_reduce_channel_balance_ibc_receive_with_mint_burn() {
         remote_decimals: 18,
         local_asset_info_decimals: 6,
         is_mint_burn: Some(true),
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
     };
     execute(
         deps.as_mut(),
@@ -2540,7 +3106,6 @@ pub fn test_get_follow_up_msg() {
     REFUND_INFO_LIST
         .save(deps_mut.storage, &refund_list)
         .unwrap();
-
     let orai_receiver = "orai123".to_string();
     let to_send = Amount::Cw20(Cw20CoinVerified {
         address: Addr::unchecked("cw20"),
@@ -2904,12 +3469,21 @@ fn test_withdraw_stuck_asset() {
                 amount: Uint128::new(1000000),
             }),
             receiver: Some(Addr::unchecked("receiver")),
+            emergency: false,
+            reason: "withdraw stuck asset".to_string(),
         },
     )
     .unwrap_err();
-    assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: Role::RefundOperator
+        }
+    );
 
     // case 2: success
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(1000000, "orai"));
     let res = execute(
         deps.as_mut(),
         mock_env(),
@@ -2920,6 +3494,8 @@ fn test_withdraw_stuck_asset() {
                 amount: Uint128::new(1000000),
             }),
             receiver: Some(Addr::unchecked("receiver")),
+            emergency: false,
+            reason: "withdraw stuck asset".to_string(),
         },
     )
     .unwrap();
@@ -3011,3 +3587,3149 @@ fn test_auto_refund() {
             .unwrap();
     assert_eq!(query_res.len(), 0);
 }
+
+#[test]
+fn test_query_reconcile() {
+    let local_channel_id = "channel-0";
+    let denom = "uatom";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel_id, denom);
+
+    // lock-unlock mapping from an 18 decimals remote token to a 6 decimals local token
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: denom.to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 18,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "update mapping pair".to_string(),
+        }),
+    )
+    .unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(5_000_000_000_000_000_000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    // a channel state without mapping cannot be reconciled
+    increase_channel_balance(
+        deps.as_mut().storage,
+        "channel-1",
+        "unknown",
+        Uint128::from(10u128),
+        &mock_env().block,
+    )
+    .unwrap();
+
+    // case 1: the contract holds less than it owes
+    deps.querier
+        .update_balance(mock_env().contract.address, coins(4_000_000, "orai"));
+    let res: ReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Reconcile {
+                channel_id: local_channel_id.to_string(),
+                ibc_denom: ibc_denom.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.outstanding, Uint128::from(5_000_000u128));
+    assert_eq!(res.total_outstanding, Uint128::from(5_000_000u128));
+    assert_eq!(res.holdings, Uint128::from(4_000_000u128));
+    assert_eq!(res.surplus, Uint128::zero());
+    assert_eq!(res.deficit, Uint128::from(1_000_000u128));
+
+    // the sweep only reports channel states that have a mapping
+    let list: ListReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReconcileAll {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(list.entries, vec![res.clone()]);
+    assert_eq!(list.next_start_after, None);
+
+    // a page scans at most limit channel states, skipped ones included
+    let list: ListReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReconcileAll {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(list.entries, vec![res]);
+    assert_eq!(
+        list.next_start_after,
+        Some((local_channel_id.to_string(), ibc_denom.clone()))
+    );
+    let list: ListReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReconcileAll {
+                start_after: list.next_start_after,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(list.entries, vec![]);
+    assert_eq!(
+        list.next_start_after,
+        Some(("channel-1".to_string(), "unknown".to_string()))
+    );
+
+    // case 2: the contract holds more than it owes
+    deps.querier
+        .update_balance(mock_env().contract.address, coins(6_000_000, "orai"));
+    let res: ReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Reconcile {
+                channel_id: local_channel_id.to_string(),
+                ibc_denom: ibc_denom.clone(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.surplus, Uint128::from(1_000_000u128));
+    assert_eq!(res.deficit, Uint128::zero());
+
+    // pagination starts after the given key
+    let list: ListReconcileResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReconcileAll {
+                start_after: Some((local_channel_id.to_string(), ibc_denom)),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(list.entries.len(), 0);
+}
+
+#[test]
+fn test_minted_supply_invariant() {
+    let local_channel_id = "channel-0";
+    let ibc_denom = "foobar";
+    let local_receiver = "receiver";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let local_asset_info = AssetInfo::Token {
+        contract_addr: Addr::unchecked("cw20"),
+    };
+    let ibc_denom_keys = format!(
+        "wasm.{}/{}/{}",
+        mock_env().contract.address.to_string(),
+        local_channel_id,
+        ibc_denom
+    );
+
+    let update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: ibc_denom.to_string(),
+        local_asset_info: local_asset_info.clone(),
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: Some(true),
+        supply_cap: Some(UpdateValue::Set(Uint128::from(1000u128))),
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(update),
+    )
+    .unwrap();
+
+    let increase_msg = |amount: u128| ExecuteMsg::IncreaseChannelBalanceIbcReceive {
+        dest_channel_id: local_channel_id.to_string(),
+        ibc_denom: ibc_denom_keys.clone(),
+        amount: Uint128::from(amount),
+        local_receiver: local_receiver.to_string(),
+    };
+    let query_minted_supply = |deps: Deps| -> Uint128 {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::MintedSupply {
+                    asset_info: local_asset_info.clone(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    // mint within the cap
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(mock_env().contract.address.as_str(), &[]),
+        increase_msg(800),
+    )
+    .unwrap();
+    assert_eq!(query_minted_supply(deps.as_ref()), Uint128::from(800u128));
+
+    // minting more than the outstanding balance fails
+    let pair_mapping = ics20_denoms()
+        .load(deps.as_ref().storage, &ibc_denom_keys)
+        .unwrap();
+    let err = increase_minted_supply(deps.as_mut().storage, &pair_mapping, Uint128::from(1u128))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MintExceedsOutstanding {
+            asset: local_asset_info.to_string(),
+            minted: Uint128::from(801u128),
+            outstanding: Uint128::from(800u128),
+        }
+    );
+
+    // minting over the cap fails, even if the outstanding balance backs it
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(mock_env().contract.address.as_str(), &[]),
+        increase_msg(300),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::SupplyCapExceeded {
+            asset: local_asset_info.to_string(),
+            minted: Uint128::from(1100u128),
+            supply_cap: Uint128::from(1000u128),
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "update mapping pair".to_string(),
+        }
+    );
+
+    // burning reduces the minted supply
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(mock_env().contract.address.as_str(), &[]),
+        ExecuteMsg::ReduceChannelBalanceIbcReceive {
+            src_channel_id: local_channel_id.to_string(),
+            ibc_denom: ibc_denom_keys.clone(),
+            amount: Uint128::from(300u128),
+            local_receiver: local_receiver.to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        MINTED_SUPPLY
+            .load(deps.as_ref().storage, &local_asset_info.to_string())
+            .unwrap(),
+        Uint128::from(500u128)
+    );
+}
+
+#[test]
+fn test_channel_balance_history() {
+    let channel = "foo-channel";
+    let ibc_denom = "port/channel/denom";
+    let mut deps = setup(&[channel], &[]);
+    // as if the contract was upgraded at height 100, nothing before it was snapshotted
+    CHANNEL_HISTORY_START
+        .save(deps.as_mut().storage, &100)
+        .unwrap();
+    let block_at = |height: u64| BlockInfo {
+        height,
+        ..mock_env().block
+    };
+
+    increase_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(100u128),
+        &block_at(100),
+    )
+    .unwrap();
+    // several changes in the same block are recorded as one
+    reduce_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(30u128),
+        &block_at(101),
+    )
+    .unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        channel,
+        ibc_denom,
+        Uint128::from(5u128),
+        &block_at(101),
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height = 105;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        ExecuteMsg::OverrideChannelBalance {
+            channel_id: channel.to_string(),
+            ibc_denom: ibc_denom.to_string(),
+            outstanding: Uint128::from(50u128),
+            total_sent: None,
+            reason: "override channel balance".to_string(),
+        },
+    )
+    .unwrap();
+    execute_timelock_after_delay(deps.as_mut(), env, 0);
+
+    let state = |outstanding: u128, total_sent: u128, total_returned: u128, transfer_count: u64| {
+        ChannelState {
+            outstanding: Uint128::from(outstanding),
+            total_sent: Uint128::from(total_sent),
+            total_received: Uint128::from(total_sent),
+            total_returned: Uint128::from(total_returned),
+            total_refunded: Uint128::zero(),
+            last_activity: mock_env().block.time,
+            transfer_count,
+        }
+    };
+
+    // balance at a given height is the one at the beginning of the block
+    query_channel_with_key(
+        deps.as_ref(),
+        channel.to_string(),
+        ibc_denom.to_string(),
+        Some(100),
+    )
+    .unwrap_err();
+    let err = query_channel_with_key(
+        deps.as_ref(),
+        channel.to_string(),
+        ibc_denom.to_string(),
+        Some(99),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Channel history is only available from height 100")
+    );
+    for (height, outstanding, total_sent) in [
+        (101, 100u128, 100u128),
+        (102, 75, 105),
+        (105, 75, 105),
+        (106, 50, 105),
+    ] {
+        let result = query_channel_with_key(
+            deps.as_ref(),
+            channel.to_string(),
+            ibc_denom.to_string(),
+            Some(height),
+        )
+        .unwrap();
+        assert_eq!(
+            result.balance,
+            Amount::from_parts(ibc_denom.to_string(), Uint128::from(outstanding))
+        );
+        assert_eq!(
+            result.total_sent,
+            Amount::from_parts(ibc_denom.to_string(), Uint128::from(total_sent))
+        );
+    }
+
+    let query_history = |start_height: Option<u64>, end_height: Option<u64>, limit: Option<u32>| {
+        let res: ChannelBalanceHistoryResponse = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::ChannelBalanceHistory {
+                    channel_id: channel.to_string(),
+                    denom: ibc_denom.to_string(),
+                    start_height,
+                    end_height,
+                    limit,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.changes
+    };
+
+    assert_eq!(
+        query_history(None, None, None),
+        vec![
+            ChannelBalanceChange {
+                height: 100,
+                before: None,
+                after: Some(state(100, 100, 0, 1)),
+            },
+            ChannelBalanceChange {
+                height: 101,
+                before: Some(state(100, 100, 0, 1)),
+                after: Some(state(75, 105, 30, 3)),
+            },
+            ChannelBalanceChange {
+                height: 105,
+                before: Some(state(75, 105, 30, 3)),
+                after: Some(state(50, 105, 30, 3)),
+            },
+        ]
+    );
+    assert_eq!(
+        query_history(Some(101), Some(104), None),
+        vec![ChannelBalanceChange {
+            height: 101,
+            before: Some(state(100, 100, 0, 1)),
+            after: Some(state(75, 105, 30, 3)),
+        }]
+    );
+    assert_eq!(
+        query_history(None, None, Some(1)),
+        vec![ChannelBalanceChange {
+            height: 100,
+            before: None,
+            after: Some(state(100, 100, 0, 1)),
+        }]
+    );
+
+    query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::ChannelBalanceHistory {
+            channel_id: channel.to_string(),
+            denom: ibc_denom.to_string(),
+            start_height: Some(99),
+            end_height: None,
+            limit: None,
+        },
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn test_audit_log() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "".to_string(),
+    };
+
+    // a reason is mandatory
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(update.clone()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::EmptyAuditReason {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            reason: "list earth".to_string(),
+            ..update
+        }),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::OverrideChannelBalance {
+            channel_id: local_channel_id.to_string(),
+            ibc_denom: ibc_denom.clone(),
+            outstanding: Uint128::from(100u128),
+            total_sent: None,
+            reason: "fix balance".to_string(),
+        },
+    )
+    .unwrap();
+    execute_timelock_after_delay(deps.as_mut(), mock_env(), 0);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::DeleteMappingPair(DeletePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            reason: "delist earth".to_string(),
+        }),
+    )
+    .unwrap();
+
+    let query_audit_log =
+        |action: Option<AuditAction>, start_after: Option<u64>, order: Option<u8>| {
+            let res: ListAuditLogResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::AuditLog {
+                        action,
+                        start_after,
+                        limit: None,
+                        order,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.entries
+        };
+
+    let entries = query_audit_log(None, None, None);
+    assert_eq!(
+        entries
+            .iter()
+            .map(|entry| (entry.id, entry.action.clone(), entry.reason.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, AuditAction::UpdateMappingPair, "list earth"),
+            (1, AuditAction::OverrideChannelBalance, "fix balance"),
+            (2, AuditAction::DeleteMappingPair, "delist earth"),
+        ]
+    );
+    assert_eq!(
+        entries[1],
+        AuditEntry {
+            id: 1,
+            action: AuditAction::OverrideChannelBalance,
+            actor: Addr::unchecked("gov"),
+            subject: format!("{}/{}", local_channel_id, ibc_denom),
+            reason: "fix balance".to_string(),
+            before: None,
+            after: Some(
+                to_json_string(&ChannelState {
+                    outstanding: Uint128::from(100u128),
+                    total_sent: Uint128::zero(),
+                    ..Default::default()
+                })
+                .unwrap()
+            ),
+            height: mock_env().block.height,
+            time: mock_env().block.time.plus_seconds(DEFAULT_TIMELOCK_DELAY),
+        }
+    );
+    assert_eq!(entries[2].before, entries[0].after);
+    assert_eq!(entries[2].after, None);
+
+    // filter by action
+    let entries = query_audit_log(Some(AuditAction::DeleteMappingPair), None, None);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, 2);
+
+    // paginate in descending order
+    let entries = query_audit_log(None, Some(2), Some(2));
+    assert_eq!(
+        entries.iter().map(|entry| entry.id).collect::<Vec<_>>(),
+        vec![1, 0]
+    );
+}
+
+#[test]
+fn test_dust_policy() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let earth_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let mars_denom = format!("{}/{}/mars", CONTRACT_PORT, local_channel_id);
+    let mut update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 18,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: Some(DustPolicy::Accumulate),
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
+    };
+    let update_pair = |deps: DepsMut, update: &UpdatePairMsg| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::UpdateMappingPair(update.clone()),
+        )
+        .unwrap();
+    };
+    update_pair(deps.as_mut(), &update);
+
+    // inbound dust is accumulated in remote decimals
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(mock_env().contract.address.as_str(), &[]),
+            ExecuteMsg::IncreaseChannelBalanceIbcReceive {
+                dest_channel_id: local_channel_id.to_string(),
+                ibc_denom: earth_denom.clone(),
+                amount: Uint128::from(1_500_000_000_000u128),
+                local_receiver: "receiver".to_string(),
+            },
+        )
+        .unwrap();
+    }
+    let query_dust = |deps: Deps, ibc_denom: &str| -> DustResponse {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::Dust {
+                    ibc_denom: ibc_denom.to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let dust = query_dust(deps.as_ref(), &earth_denom);
+    assert_eq!(dust.balance.remote, Uint128::from(1_000_000_000_000u128));
+    assert_eq!(dust.sweepable, Uint128::one());
+
+    let sweep = ExecuteMsg::SweepDust {
+        ibc_denom: earth_denom.clone(),
+        receiver: "treasury".to_string(),
+        reason: "sweep dust".to_string(),
+    };
+    let unauthorized = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("foobar", &[]),
+        sweep.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        unauthorized,
+        ContractError::MissingRole {
+            role: Role::FeeManager
+        }
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        sweep.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(1, "orai")
+        })]
+    );
+    assert_eq!(
+        query_dust(deps.as_ref(), &earth_denom).balance,
+        DustBalance::default()
+    );
+    assert_eq!(
+        execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), sweep).unwrap_err(),
+        ContractError::NoDustToSweep {
+            ibc_denom: earth_denom.clone()
+        }
+    );
+
+    // outbound dust, the local asset has more decimals than the remote one
+    update.denom = "mars".to_string();
+    update.local_asset_info = AssetInfo::NativeToken {
+        denom: "umars".to_string(),
+    };
+    update.remote_decimals = 6;
+    update.local_asset_info_decimals = 18;
+    update.dust_policy = Some(DustPolicy::Reject);
+    update_pair(deps.as_mut(), &update);
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &mars_denom,
+        Uint128::from(10_000_000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    let transfer_back = |deps: DepsMut| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("sender", &coins(1_000_000_000_000_123, "umars")),
+            ExecuteMsg::TransferToRemote(TransferBackMsg {
+                local_channel_id: local_channel_id.to_string(),
+                remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+                remote_denom: "mars".to_string(),
+                timeout: Some(DEFAULT_TIMEOUT),
+                memo: None,
+                remote_prefix: None,
+                callback: None,
+            }),
+        )
+    };
+    assert_eq!(
+        transfer_back(deps.as_mut()).unwrap_err(),
+        ContractError::InexactAmount {
+            amount: Uint128::from(1_000_000_000_000_123u128),
+            dust: Uint128::from(123u128),
+        }
+    );
+
+    // the refund policy returns the dust to the sender
+    update.dust_policy = Some(DustPolicy::Refund);
+    update_pair(deps.as_mut(), &update);
+    let res = transfer_back(deps.as_mut()).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(BankMsg::Send {
+            to_address: "sender".to_string(),
+            amount: coins(123, "umars")
+        })
+    );
+    assert!(matches!(
+        res.messages[1].msg,
+        CosmosMsg::Ibc(IbcMsg::SendPacket { .. })
+    ));
+
+    // the accumulate policy keeps it for sweeping
+    update.dust_policy = Some(DustPolicy::Accumulate);
+    update_pair(deps.as_mut(), &update);
+    transfer_back(deps.as_mut()).unwrap();
+    let dust = query_dust(deps.as_ref(), &mars_denom);
+    assert_eq!(dust.balance.local, Uint128::from(123u128));
+    assert_eq!(dust.sweepable, Uint128::from(123u128));
+    assert_eq!(
+        CHANNEL_REVERSE_STATE
+            .load(deps.as_ref().storage, (local_channel_id, &mars_denom))
+            .unwrap()
+            .outstanding,
+        Uint128::from(8_000_000u128)
+    );
+}
+
+#[test]
+fn test_mapping_status() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let mut update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: Some(MappingStatus::ReceiveOnly),
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
+    };
+    let update_pair = |deps: DepsMut, update: &UpdatePairMsg| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::UpdateMappingPair(update.clone()),
+        )
+        .unwrap();
+    };
+    update_pair(deps.as_mut(), &update);
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(1000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    let transfer_back = |deps: DepsMut| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("sender", &coins(100, "orai")),
+            ExecuteMsg::TransferToRemote(TransferBackMsg {
+                local_channel_id: local_channel_id.to_string(),
+                remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+                remote_denom: "earth".to_string(),
+                timeout: Some(DEFAULT_TIMEOUT),
+                memo: None,
+                remote_prefix: None,
+                callback: None,
+            }),
+        )
+    };
+    let receive = |deps: DepsMut| {
+        let packet =
+            mock_receive_packet_remote_to_local(local_channel_id, 100, "earth", "receiver", None);
+        ibc_packet_receive(
+            deps,
+            mock_env(),
+            IbcPacketReceiveMsg::new(packet, Addr::unchecked("relayer")),
+        )
+        .unwrap()
+    };
+
+    assert_eq!(
+        transfer_back(deps.as_mut()).unwrap_err(),
+        ContractError::MappingSendDisabled {
+            ibc_denom: ibc_denom.clone()
+        }
+    );
+    assert_ne!(
+        receive(deps.as_mut()).acknowledgement,
+        ack_fail(
+            ContractError::MappingReceiveDisabled {
+                ibc_denom: ibc_denom.clone()
+            }
+            .to_string()
+        )
+    );
+
+    update.status = Some(MappingStatus::SendOnly);
+    update_pair(deps.as_mut(), &update);
+    transfer_back(deps.as_mut()).unwrap();
+    assert_eq!(
+        receive(deps.as_mut()).acknowledgement,
+        ack_fail(
+            ContractError::MappingReceiveDisabled {
+                ibc_denom: ibc_denom.clone()
+            }
+            .to_string()
+        )
+    );
+
+    // refunds of in-flight packets keep working on a disabled route
+    update.status = Some(MappingStatus::Disabled);
+    update_pair(deps.as_mut(), &update);
+    transfer_back(deps.as_mut()).unwrap_err();
+    // a transfer drained by fees is rejected as well instead of only collecting the fees
+    TOKEN_FEE
+        .save(
+            deps.as_mut().storage,
+            "earth",
+            &Ratio {
+                nominator: 1,
+                denominator: 1,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        transfer_back(deps.as_mut()).unwrap_err(),
+        ContractError::MappingSendDisabled {
+            ibc_denom: ibc_denom.clone()
+        }
+    );
+    let refund = handle_packet_refund(
+        deps.as_mut().storage,
+        "sender",
+        &ibc_denom,
+        Uint128::from(100u128),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        refund.msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "sender".to_string(),
+            amount: coins(100, "orai")
+        })
+    );
+}
+
+#[test]
+fn test_update_mapping_pair_keeps_empty_fields() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let mut update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: Some(true),
+        supply_cap: Some(UpdateValue::Set(Uint128::from(5000u128))),
+        rescale_outstanding: None,
+        dust_policy: Some(DustPolicy::Reject),
+        status: Some(MappingStatus::Disabled),
+        min_amount: Some(UpdateValue::Set(Uint128::from(10u128))),
+        max_amount: Some(UpdateValue::Set(Uint128::from(1000u128))),
+        reason: "update mapping pair".to_string(),
+    };
+    let update_pair = |deps: DepsMut, update: &UpdatePairMsg| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::UpdateMappingPair(update.clone()),
+        )
+    };
+    update_pair(deps.as_mut(), &update).unwrap();
+    let expected = ics20_denoms()
+        .load(deps.as_ref().storage, &ibc_denom)
+        .unwrap();
+
+    // an update that only changes the decimals does not unpause the pair or drop its limits
+    update.remote_decimals = 18;
+    update.is_mint_burn = None;
+    update.supply_cap = None;
+    update.dust_policy = None;
+    update.status = None;
+    update.min_amount = None;
+    update.max_amount = None;
+    update_pair(deps.as_mut(), &update).unwrap();
+    assert_eq!(
+        ics20_denoms()
+            .load(deps.as_ref().storage, &ibc_denom)
+            .unwrap(),
+        MappingMetadata {
+            remote_decimals: 18,
+            ..expected
+        }
+    );
+
+    // the kept bounds are checked against the new ones
+    update.min_amount = Some(UpdateValue::Set(Uint128::from(2000u128)));
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        ContractError::InvalidAmountBounds {
+            min_amount: Uint128::from(2000u128),
+            max_amount: Uint128::from(1000u128),
+        }
+    );
+
+    // optional limits are only removed explicitly
+    update.min_amount = None;
+    update.supply_cap = Some(UpdateValue::Unset {});
+    update.max_amount = Some(UpdateValue::Unset {});
+    update_pair(deps.as_mut(), &update).unwrap();
+    let pair_mapping = ics20_denoms()
+        .load(deps.as_ref().storage, &ibc_denom)
+        .unwrap();
+    assert_eq!(pair_mapping.supply_cap, None);
+    assert_eq!(pair_mapping.min_amount, Some(Uint128::from(10u128)));
+    assert_eq!(pair_mapping.max_amount, None);
+    assert_eq!(pair_mapping.status, MappingStatus::Disabled);
+}
+
+#[test]
+fn test_transfer_amount_bounds() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let ibc_denom = format!("{}/{}/earth", CONTRACT_PORT, local_channel_id);
+    let mut update = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: Some(UpdateValue::Set(Uint128::from(1000u128))),
+        max_amount: Some(UpdateValue::Set(Uint128::from(10u128))),
+        reason: "update mapping pair".to_string(),
+    };
+    let update_pair = |deps: DepsMut, update: &UpdatePairMsg| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::UpdateMappingPair(update.clone()),
+        )
+    };
+    assert_eq!(
+        update_pair(deps.as_mut(), &update).unwrap_err(),
+        ContractError::InvalidAmountBounds {
+            min_amount: Uint128::from(1000u128),
+            max_amount: Uint128::from(10u128),
+        }
+    );
+    update.min_amount = Some(UpdateValue::Set(Uint128::from(10u128)));
+    update.max_amount = Some(UpdateValue::Set(Uint128::from(1000u128)));
+    update_pair(deps.as_mut(), &update).unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(10000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+
+    let transfer_back = |deps: DepsMut, amount: u128| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("sender", &coins(amount, "orai")),
+            ExecuteMsg::TransferToRemote(TransferBackMsg {
+                local_channel_id: local_channel_id.to_string(),
+                remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+                remote_denom: "earth".to_string(),
+                timeout: Some(DEFAULT_TIMEOUT),
+                memo: None,
+                remote_prefix: None,
+                callback: None,
+            }),
+        )
+    };
+    assert_eq!(
+        transfer_back(deps.as_mut(), 5).unwrap_err(),
+        ContractError::AmountBelowMinimum {
+            amount: Uint128::from(5u128),
+            min_amount: Uint128::from(10u128),
+        }
+    );
+    assert_eq!(
+        transfer_back(deps.as_mut(), 2000).unwrap_err(),
+        ContractError::AmountAboveMaximum {
+            amount: Uint128::from(2000u128),
+            max_amount: Uint128::from(1000u128),
+        }
+    );
+    transfer_back(deps.as_mut(), 1000).unwrap();
+    // the bounds are checked before the fees, which would otherwise drain the transfer and skip them
+    TOKEN_FEE
+        .save(
+            deps.as_mut().storage,
+            "earth",
+            &Ratio {
+                nominator: 1,
+                denominator: 1,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        transfer_back(deps.as_mut(), 5).unwrap_err(),
+        ContractError::AmountBelowMinimum {
+            amount: Uint128::from(5u128),
+            min_amount: Uint128::from(10u128),
+        }
+    );
+    TOKEN_FEE.remove(deps.as_mut().storage, "earth");
+
+    // inbound packets out of bounds are failed so the remote chain refunds them
+    let packet =
+        mock_receive_packet_remote_to_local(local_channel_id, 5, "earth", "receiver", None);
+    let res = ibc_packet_receive(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketReceiveMsg::new(packet, Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.acknowledgement,
+        ack_fail(
+            ContractError::AmountBelowMinimum {
+                amount: Uint128::from(5u128),
+                min_amount: Uint128::from(10u128),
+            }
+            .to_string()
+        )
+    );
+}
+
+#[test]
+fn test_batch_mapping_pairs() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let pair = |denom: &str| UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: denom.to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: format!("u{}", denom),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "onboard chain".to_string(),
+    };
+    let delete = |denom: &str| DeletePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: denom.to_string(),
+        reason: "offboard chain".to_string(),
+    };
+    let ibc_denom = |denom: &str| format!("{}/{}/{}", CONTRACT_PORT, local_channel_id, denom);
+    let mapping_count = |deps: Deps| {
+        ics20_denoms()
+            .keys(deps.storage, None, None, Order::Ascending)
+            .count()
+    };
+    let batch_update = |deps: DepsMut, sender: &str, pairs: Vec<UpdatePairMsg>| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::BatchUpdateMappingPairs { pairs },
+        )
+    };
+    let batch_delete = |deps: DepsMut, pairs: Vec<DeletePairMsg>, force: bool| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::BatchDeleteMappingPairs { pairs, force },
+        )
+    };
+
+    assert_eq!(
+        batch_update(deps.as_mut(), "foobar", vec![pair("earth")]).unwrap_err(),
+        ContractError::MissingRole {
+            role: Role::MappingManager
+        }
+    );
+    assert_eq!(
+        batch_update(deps.as_mut(), "gov", vec![]).unwrap_err(),
+        ContractError::EmptyBatch {}
+    );
+    assert_eq!(
+        batch_update(deps.as_mut(), "gov", vec![pair("earth"), pair("earth")]).unwrap_err(),
+        ContractError::DuplicateMappingPair {
+            ibc_denom: ibc_denom("earth")
+        }
+    );
+    // an invalid entry rejects the whole batch before anything is saved
+    let mut invalid = pair("mars");
+    invalid.min_amount = Some(UpdateValue::Set(Uint128::from(2u128)));
+    invalid.max_amount = Some(UpdateValue::Set(Uint128::one()));
+    batch_update(deps.as_mut(), "gov", vec![pair("earth"), invalid]).unwrap_err();
+    assert_eq!(mapping_count(deps.as_ref()), 0);
+
+    let res = batch_update(
+        deps.as_mut(),
+        "gov",
+        vec![pair("earth"), pair("mars"), pair("venus")],
+    )
+    .unwrap();
+    assert_eq!(res.events.len(), 3);
+    assert_eq!(mapping_count(deps.as_ref()), 3);
+
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom("mars"),
+        Uint128::from(100u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    assert_eq!(
+        batch_delete(deps.as_mut(), vec![delete("moon")], false).unwrap_err(),
+        ContractError::MappingPairNotFound {}
+    );
+    assert_eq!(
+        batch_delete(deps.as_mut(), vec![delete("earth"), delete("mars")], false).unwrap_err(),
+        ContractError::DeleteWithOutstanding {
+            ibc_denom: ibc_denom("mars"),
+            outstanding: Uint128::from(100u128),
+        }
+    );
+    assert_eq!(mapping_count(deps.as_ref()), 3);
+    batch_delete(deps.as_mut(), vec![delete("earth"), delete("mars")], true).unwrap();
+    assert_eq!(
+        ics20_denoms()
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![ibc_denom("venus")]
+    );
+}
+
+#[test]
+fn test_pair_mapping_details() {
+    let mut deps = setup(&["channel-0", "channel-1", "channel-10"], &[]);
+    let pair = |channel_id: &str, denom: &str, is_mint_burn: bool| UpdatePairMsg {
+        local_channel_id: channel_id.to_string(),
+        denom: denom.to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: Some(is_mint_burn),
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "onboard chain".to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::BatchUpdateMappingPairs {
+            pairs: vec![
+                pair("channel-0", "earth", false),
+                pair("channel-0", "mars", true),
+                pair("channel-1", "earth", false),
+                pair("channel-10", "earth", false),
+            ],
+        },
+    )
+    .unwrap();
+    let ibc_denom =
+        |channel_id: &str, denom: &str| format!("{}/{}/{}", CONTRACT_PORT, channel_id, denom);
+    increase_channel_balance(
+        deps.as_mut().storage,
+        "channel-0",
+        &ibc_denom("channel-0", "earth"),
+        Uint128::from(100u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    let fee = Ratio {
+        nominator: 1,
+        denominator: 100,
+    };
+    TOKEN_FEE
+        .save(deps.as_mut().storage, "earth", &fee)
+        .unwrap();
+
+    let query_details = |deps: Deps, msg: QueryMsg| {
+        from_json::<ListMappingDetailResponse>(&query(deps, mock_env(), msg).unwrap())
+            .unwrap()
+            .pairs
+            .into_iter()
+            .map(|detail| detail.key)
+            .collect::<Vec<String>>()
+    };
+    let details =
+        |local_channel_id: Option<&str>, is_mint_burn: Option<bool>| QueryMsg::PairMappingDetails {
+            local_channel_id: local_channel_id.map(|id| id.to_string()),
+            is_mint_burn,
+            status: None,
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+
+    let res: ListMappingDetailResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            details(Some("channel-0"), Some(false)),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.pairs,
+        vec![PairMappingDetail {
+            key: ibc_denom("channel-0", "earth"),
+            pair_mapping: ics20_denoms()
+                .load(deps.as_ref().storage, &ibc_denom("channel-0", "earth"))
+                .unwrap(),
+            outstanding: Uint128::from(100u128),
+            total_sent: Uint128::from(100u128),
+            token_fee: Some(fee),
+        }]
+    );
+    assert_eq!(
+        query_details(deps.as_ref(), details(Some("channel-1"), None)),
+        vec![ibc_denom("channel-1", "earth")]
+    );
+    assert_eq!(
+        query_details(deps.as_ref(), details(None, Some(true))),
+        vec![ibc_denom("channel-0", "mars")]
+    );
+
+    // pagination within the pairs of a channel
+    let channel_details =
+        |start_after: Option<String>, order: Option<u8>| QueryMsg::PairMappingDetails {
+            local_channel_id: Some("channel-0".to_string()),
+            is_mint_burn: None,
+            status: None,
+            start_after,
+            limit: None,
+            order,
+        };
+    assert_eq!(
+        query_details(
+            deps.as_ref(),
+            channel_details(Some(ibc_denom("channel-0", "earth")), None)
+        ),
+        vec![ibc_denom("channel-0", "mars")]
+    );
+    assert_eq!(
+        query_details(deps.as_ref(), channel_details(Some("a".to_string()), None)),
+        vec![
+            ibc_denom("channel-0", "earth"),
+            ibc_denom("channel-0", "mars")
+        ]
+    );
+    assert_eq!(
+        query_details(deps.as_ref(), channel_details(None, Some(2))),
+        vec![
+            ibc_denom("channel-0", "mars"),
+            ibc_denom("channel-0", "earth")
+        ]
+    );
+    assert_eq!(
+        query_details(
+            deps.as_ref(),
+            channel_details(Some(ibc_denom("channel-0", "mars")), Some(2))
+        ),
+        vec![ibc_denom("channel-0", "earth")]
+    );
+    assert_eq!(
+        query_details(
+            deps.as_ref(),
+            channel_details(Some("z".to_string()), Some(2))
+        ),
+        vec![
+            ibc_denom("channel-0", "mars"),
+            ibc_denom("channel-0", "earth")
+        ]
+    );
+
+    // filtered pages are bounded by the pairs scanned, the cursor resumes after the last one
+    let mint_burn_page = |start_after: Option<String>| {
+        from_json::<ListMappingDetailResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PairMappingDetails {
+                    local_channel_id: None,
+                    is_mint_burn: Some(true),
+                    status: None,
+                    start_after,
+                    limit: Some(2),
+                    order: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+    let page = mint_burn_page(None);
+    assert_eq!(page.pairs.len(), 1);
+    assert_eq!(page.pairs[0].key, ibc_denom("channel-0", "mars"));
+    assert_eq!(page.next_start_after, Some(ibc_denom("channel-0", "mars")));
+    let page = mint_burn_page(page.next_start_after);
+    assert!(page.pairs.is_empty());
+    assert_eq!(
+        page.next_start_after,
+        Some(ibc_denom("channel-10", "earth"))
+    );
+    let page = mint_burn_page(page.next_start_after);
+    assert!(page.pairs.is_empty());
+    assert_eq!(page.next_start_after, None);
+
+    // pagination over the asset info index
+    let from_asset_info = |start_after: Option<String>, order: Option<u8>| {
+        QueryMsg::PairMappingDetailsFromAssetInfo {
+            asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            start_after,
+            limit: Some(2),
+            order,
+        }
+    };
+    assert_eq!(
+        query_details(deps.as_ref(), from_asset_info(None, None)),
+        vec![
+            ibc_denom("channel-0", "earth"),
+            ibc_denom("channel-0", "mars")
+        ]
+    );
+    assert_eq!(
+        query_details(
+            deps.as_ref(),
+            from_asset_info(Some(ibc_denom("channel-0", "mars")), None)
+        ),
+        vec![
+            ibc_denom("channel-1", "earth"),
+            ibc_denom("channel-10", "earth")
+        ]
+    );
+    assert_eq!(
+        query_details(deps.as_ref(), from_asset_info(None, Some(2))),
+        vec![
+            ibc_denom("channel-10", "earth"),
+            ibc_denom("channel-1", "earth")
+        ]
+    );
+}
+
+#[test]
+fn test_transfer_route_selection() {
+    let mut deps = setup(&["channel-0", "channel-1"], &[]);
+    let remote_denom = "oraib0xabc";
+    let pair = |channel_id: &str| UpdatePairMsg {
+        local_channel_id: channel_id.to_string(),
+        denom: remote_denom.to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "onboard chain".to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::BatchUpdateMappingPairs {
+            pairs: vec![pair("channel-0"), pair("channel-1")],
+        },
+    )
+    .unwrap();
+    let ibc_denom = |channel_id: &str| format!("{}/{}/{}", CONTRACT_PORT, channel_id, remote_denom);
+    for (channel_id, amount) in [("channel-0", 100u128), ("channel-1", 10000u128)] {
+        increase_channel_balance(
+            deps.as_mut().storage,
+            channel_id,
+            &ibc_denom(channel_id),
+            Uint128::from(amount),
+            &mock_env().block,
+        )
+        .unwrap();
+    }
+
+    let transfer_back = |deps: DepsMut, amount: u128, remote_prefix: &str| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("sender", &coins(amount, "orai")),
+            ExecuteMsg::TransferToRemote(TransferBackMsg {
+                local_channel_id: "".to_string(),
+                remote_address: "0x1234".to_string(),
+                remote_denom: "".to_string(),
+                timeout: Some(DEFAULT_TIMEOUT),
+                memo: None,
+                remote_prefix: Some(remote_prefix.to_string()),
+                callback: None,
+            }),
+        )
+    };
+
+    // channel-0 cannot cover the amount so channel-1 is picked
+    let res = transfer_back(deps.as_mut(), 1000, "oraib").unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("denom", ibc_denom("channel-1"))));
+    assert_eq!(
+        transfer_back(deps.as_mut(), 20000, "oraib").unwrap_err(),
+        ContractError::NoTransferRoute {
+            remote_prefix: "oraib".to_string(),
+            amount: Uint128::from(20000u128),
+            candidates: format!(
+                "{}: Outstanding balance 100 is too low, {}: Outstanding balance 9000 is too low",
+                ibc_denom("channel-0"),
+                ibc_denom("channel-1")
+            ),
+        }
+    );
+    assert_eq!(
+        transfer_back(deps.as_mut(), 1000, "eth").unwrap_err(),
+        ContractError::MappingPairNotFound {}
+    );
+
+    // mappings that would reject the amount are not picked, even with enough outstanding balance
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            max_amount: Some(UpdateValue::Set(Uint128::from(500u128))),
+            ..pair("channel-1")
+        }),
+    )
+    .unwrap();
+    let above_maximum = ContractError::AmountAboveMaximum {
+        amount: Uint128::from(1000u128),
+        max_amount: Uint128::from(500u128),
+    };
+    assert_eq!(
+        transfer_back(deps.as_mut(), 1000, "oraib").unwrap_err(),
+        ContractError::NoTransferRoute {
+            remote_prefix: "oraib".to_string(),
+            amount: Uint128::from(1000u128),
+            candidates: format!(
+                "{}: Outstanding balance 100 is too low, {}: {}",
+                ibc_denom("channel-0"),
+                ibc_denom("channel-1"),
+                above_maximum
+            ),
+        }
+    );
+}
+
+#[test]
+fn test_receive_json_memo() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    let receive = |deps: DepsMut, memo: &str| {
+        let mut packet =
+            mock_receive_packet_remote_to_local(local_channel_id, 100, "earth", "receiver", None);
+        let mut data: Ics20Packet = from_json(&packet.data).unwrap();
+        data.memo = Some(memo.to_string());
+        packet.data = to_json_binary(&data).unwrap();
+        ibc_packet_receive(
+            deps,
+            mock_env(),
+            IbcPacketReceiveMsg::new(packet, Addr::unchecked("relayer")),
+        )
+        .unwrap()
+    };
+
+    let res = receive(
+        deps.as_mut(),
+        r#"{"oraichain":{"version":2,"action":"transfer"}}"#,
+    );
+    assert_eq!(
+        res.acknowledgement,
+        ack_fail(
+            ContractError::InvalidMemo {
+                reason: StdError::generic_err("Unsupported memo version 2").to_string()
+            }
+            .to_string()
+        )
+    );
+    let res = receive(
+        deps.as_mut(),
+        r#"{"oraichain":{"action":"transfer","min_out":"200"}}"#,
+    );
+    assert_eq!(
+        res.acknowledgement,
+        ack_fail(
+            ContractError::MinOutNotMet {
+                amount: Uint128::from(100u128),
+                min_out: Uint128::from(200u128),
+            }
+            .to_string()
+        )
+    );
+
+    // the receiver of the memo overrides the receiver of the packet
+    let res = receive(
+        deps.as_mut(),
+        r#"{"oraichain":{"action":"transfer","receiver":"memo_receiver","min_out":"100"}}"#,
+    );
+    let ack: Ics20Ack = from_json(&res.acknowledgement).unwrap();
+    assert!(matches!(ack, Ics20Ack::Result(_)));
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::reply_always(
+            BankMsg::Send {
+                to_address: "memo_receiver".to_string(),
+                amount: coins(100, "orai"),
+            },
+            NATIVE_RECEIVE_ID
+        )
+    );
+}
+
+#[test]
+fn test_transfer_lifecycle_callback() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &format!("{}/{}/earth", CONTRACT_PORT, local_channel_id),
+        Uint128::from(1000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+
+    // returns the sent packet, after the reply of its send saved the callback
+    let transfer_back = |mut deps: DepsMut, sequence: u64| {
+        let res = execute(
+            deps.branch(),
+            mock_env(),
+            mock_info("vault", &coins(100, "orai")),
+            ExecuteMsg::TransferToRemote(TransferBackMsg {
+                local_channel_id: local_channel_id.to_string(),
+                remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+                remote_denom: "earth".to_string(),
+                timeout: Some(DEFAULT_TIMEOUT),
+                memo: None,
+                remote_prefix: None,
+                callback: Some("vault".to_string()),
+            }),
+        )
+        .unwrap();
+        let send_msg = res.messages.last().unwrap().clone();
+        assert_eq!(send_msg.id, SEND_PACKET_CALLBACK_ID);
+        reply(
+            deps,
+            mock_env(),
+            Reply {
+                id: SEND_PACKET_CALLBACK_ID,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![Event::new("send_packet")
+                        .add_attribute("packet_sequence", sequence.to_string())
+                        .add_attribute("packet_src_channel", local_channel_id)],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        match send_msg.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => IbcPacket::new(
+                data,
+                IbcEndpoint {
+                    port_id: CONTRACT_PORT.to_string(),
+                    channel_id: local_channel_id.to_string(),
+                },
+                IbcEndpoint {
+                    port_id: REMOTE_PORT.to_string(),
+                    channel_id: "channel-1234".to_string(),
+                },
+                sequence,
+                Timestamp::from_nanos(DEFAULT_TIMEOUT).into(),
+            ),
+            msg => panic!("Unexpected return message: {:?}", msg),
+        }
+    };
+    let callback = |sequence: u64, success: bool, refunded_amount: u128| SubMsg {
+        gas_limit: Some(DEFAULT_CALLBACK_GAS_LIMIT),
+        ..SubMsg::reply_on_error(
+            wasm_execute(
+                "vault",
+                &IbcLifecycleCallbackMsg::IbcLifecycleComplete {
+                    channel_id: local_channel_id.to_string(),
+                    sequence,
+                    success,
+                    refunded_amount: Uint128::from(refunded_amount),
+                },
+                vec![],
+            )
+            .unwrap(),
+            LIFECYCLE_CALLBACK_FAILURE_ID,
+        )
+    };
+    let transfer_msg = |callback: &str| {
+        ExecuteMsg::TransferToRemote(TransferBackMsg {
+            local_channel_id: local_channel_id.to_string(),
+            remote_address: "cosmos1603j3e4juddh7cuhfquxspl0p0nsun046us7n0".to_string(),
+            remote_denom: "earth".to_string(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            memo: None,
+            remote_prefix: None,
+            callback: Some(callback.to_string()),
+        })
+    };
+
+    // only the sender can be notified
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("attacker", &coins(100, "orai")),
+        transfer_msg("vault"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CallbackNotSender {
+            callback: "vault".to_string(),
+            sender: "attacker".to_string()
+        }
+    );
+
+    // identical packets wait for their callbacks at the same time, they are told apart by their sequence
+    let packet = transfer_back(deps.as_mut(), 1);
+    let identical_packet = transfer_back(deps.as_mut(), 2);
+    assert_eq!(packet.data, identical_packet.data);
+    let res = ibc_packet_ack(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketAckMsg::new(
+            IbcAcknowledgement::new(to_json_binary(&Ics20Ack::Result(b"1".into())).unwrap()),
+            packet.clone(),
+            Addr::unchecked("relayer"),
+        ),
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![callback(1, true, 0)]);
+    // the callback is only sent once
+    let res = ibc_packet_ack(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketAckMsg::new(
+            IbcAcknowledgement::new(to_json_binary(&Ics20Ack::Result(b"1".into())).unwrap()),
+            packet,
+            Addr::unchecked("relayer"),
+        ),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    // the sender is refunded before being notified of the timeout
+    let res = ibc_packet_timeout(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketTimeoutMsg::new(identical_packet, Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[1], callback(2, false, 100));
+}
+
+#[test]
+fn test_receive_contract_call_memo() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    let mut packet =
+        mock_receive_packet_remote_to_local(local_channel_id, 100, "earth", "vault", None);
+    let mut data: Ics20Packet = from_json(&packet.data).unwrap();
+    let hook_msg = Binary::from(br#"{"deposit":{}}"#);
+    data.memo = Some(format!(
+        r#"{{"oraichain":{{"action":"contract_call","msg":"{}"}}}}"#,
+        hook_msg.to_base64()
+    ));
+    packet.data = to_json_binary(&data).unwrap();
+
+    let call = WasmMsg::Execute {
+        contract_addr: "vault".to_string(),
+        msg: hook_msg,
+        funds: coins(100, "orai"),
+    };
+
+    let res = ibc_packet_receive(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketReceiveMsg::new(packet.clone(), Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    // a failing call fails the whole receive, so it has no reply. Native calls are capped as well
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg {
+            gas_limit: Some(DEFAULT_CALLBACK_GAS_LIMIT),
+            ..SubMsg::new(call.clone())
+        }
+    );
+
+    // the default gas limit caps them when there is one
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.default_gas_limit = Some(300000);
+            Ok(config)
+        })
+        .unwrap();
+    let res = ibc_packet_receive(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketReceiveMsg::new(packet, Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg {
+            gas_limit: Some(300000),
+            ..SubMsg::new(call)
+        }
+    );
+}
+
+#[test]
+fn test_roles_and_ownership() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let pair = UpdatePairMsg {
+        local_channel_id: local_channel_id.to_string(),
+        denom: "earth".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "orai".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "onboard chain".to_string(),
+    };
+    let grant = |role: Role, address: &str| ExecuteMsg::GrantRole {
+        role,
+        address: address.to_string(),
+        reason: "grant role".to_string(),
+    };
+
+    // only the owner grants roles
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        grant(Role::MappingManager, "manager"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        grant(Role::MappingManager, "manager"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        grant(Role::Pauser, "pauser"),
+    )
+    .unwrap();
+
+    let roles: RolesResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Roles {
+                address: "manager".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(roles.roles, vec![Role::MappingManager]);
+    let roles: RolesResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Roles {
+                address: "gov".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(roles.roles, Role::ALL.to_vec());
+    let members: Vec<String> = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoleMembers {
+                role: Role::MappingManager,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(members, vec!["manager".to_string()]);
+    let list: ListRolesResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListRoles {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        list.grants,
+        vec![RoleGrant {
+            role: Role::MappingManager,
+            address: Addr::unchecked("manager"),
+        }]
+    );
+    let list: ListRolesResponse = from_json(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListRoles {
+                start_after: Some((Role::MappingManager, "manager".to_string())),
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        list.grants,
+        vec![RoleGrant {
+            role: Role::Pauser,
+            address: Addr::unchecked("pauser"),
+        }]
+    );
+
+    // a mapping manager cannot pause, a pauser cannot update mappings
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        ExecuteMsg::UpdateMappingPair(pair.clone()),
+    )
+    .unwrap();
+    let ibc_denom = get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel_id, "earth");
+    let pause = ExecuteMsg::PauseMappingPair {
+        ibc_denom: ibc_denom.clone(),
+        reason: "incident".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        pause.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MissingRole { role: Role::Pauser });
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pauser", &[]),
+        ExecuteMsg::UpdateMappingPair(pair),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: Role::MappingManager
+        }
+    );
+    execute(deps.as_mut(), mock_env(), mock_info("pauser", &[]), pause).unwrap();
+    assert_eq!(
+        ics20_denoms()
+            .load(deps.as_ref().storage, &ibc_denom)
+            .unwrap()
+            .status,
+        MappingStatus::Disabled
+    );
+
+    // revoked roles are no longer usable
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::RevokeRole {
+            role: Role::Pauser,
+            address: "pauser".to_string(),
+            reason: "rotate pauser".to_string(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pauser", &[]),
+        ExecuteMsg::PauseMappingPair {
+            ibc_denom,
+            reason: "incident".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::MissingRole { role: Role::Pauser });
+
+    // ownership moves only once the new owner accepts it
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::AcceptOwnership {
+            reason: "take over".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoPendingOwner {});
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: "new_owner".to_string(),
+            reason: "hand over".to_string(),
+        },
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        ExecuteMsg::AcceptOwnership {
+            reason: "take over".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NotPendingOwner {});
+    let ownership: OwnershipResponse =
+        from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+    assert_eq!(
+        ownership,
+        OwnershipResponse {
+            owner: Some(Addr::unchecked("gov")),
+            pending_owner: Some(Addr::unchecked("new_owner")),
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("new_owner", &[]),
+        ExecuteMsg::AcceptOwnership {
+            reason: "take over".to_string(),
+        },
+    )
+    .unwrap();
+    let ownership: OwnershipResponse =
+        from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+    assert_eq!(
+        ownership,
+        OwnershipResponse {
+            owner: Some(Addr::unchecked("new_owner")),
+            pending_owner: None,
+        }
+    );
+    // both the proposal and the acceptance are audited with their reason
+    let res: ListAuditLogResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::AuditLog {
+                action: Some(AuditAction::TransferOwnership),
+                start_after: None,
+                limit: None,
+                order: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.entries
+            .iter()
+            .map(|entry| (
+                entry.subject.as_str(),
+                entry.reason.as_str(),
+                entry.before.as_deref(),
+                entry.after.as_deref()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("pending_owner", "hand over", None, Some("new_owner")),
+            ("owner", "take over", Some("gov"), Some("new_owner")),
+        ]
+    );
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        grant(Role::FeeManager, "gov"),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+}
+
+#[test]
+fn test_timelock() {
+    let channel = "channel-0";
+    let ibc_denom = "port/channel/denom";
+    let mut deps = setup(&[channel], &[]);
+    let override_balance = ExecuteMsg::OverrideChannelBalance {
+        channel_id: channel.to_string(),
+        ibc_denom: ibc_denom.to_string(),
+        outstanding: Uint128::from(100u128),
+        total_sent: None,
+        reason: "fix balance".to_string(),
+    };
+    let query_operations = |deps: Deps| -> TimelockOperationsResponse {
+        from_json(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::TimelockOperations {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        override_balance.clone(),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateTimelockDelay {
+            delay: 3600,
+            reason: "shorter delay".to_string(),
+        },
+    )
+    .unwrap();
+    let executable_at = mock_env().block.time.plus_seconds(DEFAULT_TIMELOCK_DELAY);
+    assert_eq!(
+        query_operations(deps.as_ref()),
+        TimelockOperationsResponse {
+            delay: DEFAULT_TIMELOCK_DELAY,
+            operations: vec![
+                TimelockOperation {
+                    id: 0,
+                    action: TimelockAction::OverrideChannelBalance {
+                        channel_id: channel.to_string(),
+                        ibc_denom: ibc_denom.to_string(),
+                        outstanding: Uint128::from(100u128),
+                        total_sent: None,
+                    },
+                    proposer: Addr::unchecked("gov"),
+                    reason: "fix balance".to_string(),
+                    executable_at,
+                },
+                TimelockOperation {
+                    id: 1,
+                    action: TimelockAction::UpdateDelay { delay: 3600 },
+                    proposer: Addr::unchecked("gov"),
+                    reason: "shorter delay".to_string(),
+                    executable_at,
+                },
+            ],
+        }
+    );
+    assert!(CHANNEL_REVERSE_STATE
+        .may_load(deps.as_ref().storage, (channel, ibc_denom))
+        .unwrap()
+        .is_none());
+
+    // nothing can be executed before the delay has passed
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteTimelock { id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TimelockNotReady {
+            id: 0,
+            executable_at
+        }
+    );
+
+    // only guardians cancel operations
+    let cancel = ExecuteMsg::CancelTimelock {
+        id: 1,
+        reason: "keep the delay".to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        cancel.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: Role::Guardian
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Guardian,
+            address: "guardian".to_string(),
+            reason: "add guardian".to_string(),
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        cancel,
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.time = executable_at;
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteTimelock { id: 1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::TimelockNotFound { id: 1 });
+
+    // anyone executes the remaining operation after the delay
+    execute_timelock_after_delay(deps.as_mut(), mock_env(), 0);
+    assert_eq!(
+        CHANNEL_REVERSE_STATE
+            .load(deps.as_ref().storage, (channel, ibc_denom))
+            .unwrap()
+            .outstanding,
+        Uint128::from(100u128)
+    );
+    let res = query_operations(deps.as_ref());
+    assert_eq!(res.delay, DEFAULT_TIMELOCK_DELAY);
+    assert!(res.operations.is_empty());
+}
+
+#[test]
+fn test_allow_list_gas_limit() {
+    let local_channel_id = "channel-0";
+    let cw20_addr = "cw20token";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let allow = |gas_limit: Option<u64>| ExecuteMsg::Allow {
+        contract: cw20_addr.to_string(),
+        gas_limit,
+        reason: "list cw20".to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("foobar", &[]),
+        allow(Some(200000)),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingRole {
+            role: Role::MappingManager
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        allow(Some(200000)),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        allow(Some(100000)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::CannotLowerGas);
+    let allowed: AllowedResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Allowed {
+                contract: cw20_addr.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(allowed.gas_limit, Some(200000));
+
+    // cw20 transfers and refunds are limited to the allowed gas
+    let to_send = Amount::cw20(Uint128::from(100u128), Addr::unchecked(cw20_addr));
+    let sub_msgs = get_follow_up_msgs(
+        &mut deps.storage,
+        &deps.api,
+        "receiver".to_string(),
+        to_send.clone(),
+        None,
+    )
+    .unwrap();
+    assert_eq!(sub_msgs[0].gas_limit, Some(200000));
+    // the swap that a universal swap send triggers is not limited
+    let sub_msgs = get_follow_up_msgs(
+        &mut deps.storage,
+        &deps.api,
+        "receiver".to_string(),
+        to_send.clone(),
+        Some("SwapMemo".to_string()),
+    )
+    .unwrap();
+    assert_eq!(sub_msgs[0].id, UNIVERSAL_SWAP_ERROR_ID);
+    assert_eq!(sub_msgs[0].gas_limit, None);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: AssetInfo::Token {
+                contract_addr: Addr::unchecked(cw20_addr),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    let ibc_denom = get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel_id, "earth");
+    let refund = handle_packet_refund(
+        deps.as_mut().storage,
+        "sender",
+        &ibc_denom,
+        Uint128::from(100u128),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        refund,
+        SubMsg {
+            gas_limit: Some(200000),
+            ..SubMsg::reply_always(
+                to_send.send_amount("sender".to_string(), None),
+                REFUND_FAILURE_ID
+            )
+        }
+    );
+
+    // disallowed contracts can no longer be sent, receives and refunds fall back to the default gas limit
+    CONFIG
+        .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+            config.default_gas_limit = Some(300000);
+            Ok(config)
+        })
+        .unwrap();
+    let disallow = ExecuteMsg::Disallow {
+        contract: cw20_addr.to_string(),
+        reason: "delist cw20".to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        disallow.clone(),
+    )
+    .unwrap();
+    let err = check_gas_limit(deps.as_ref().storage, &to_send).unwrap_err();
+    assert_eq!(err, ContractError::NotOnAllowList);
+    let sub_msgs = get_follow_up_msgs(
+        &mut deps.storage,
+        &deps.api,
+        "receiver".to_string(),
+        to_send,
+        None,
+    )
+    .unwrap();
+    assert_eq!(sub_msgs[0].gas_limit, Some(300000));
+    let err = execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), disallow).unwrap_err();
+    assert_eq!(err, ContractError::NotOnAllowList);
+}
+
+#[test]
+fn test_withdraw_asset_surplus() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: AssetInfo::NativeToken {
+                denom: "orai".to_string(),
+            },
+            remote_decimals: 6,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::RefundOperator,
+            address: "operator".to_string(),
+            reason: "add operator".to_string(),
+        },
+    )
+    .unwrap();
+    let ibc_denom = get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel_id, "earth");
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(600u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    REFUND_INFO_LIST
+        .save(
+            deps.as_mut().storage,
+            &vec![RefundInfo {
+                receiver: "sender".to_string(),
+                amount: Amount::native(Uint128::from(100u128), "orai".to_string()),
+            }],
+        )
+        .unwrap();
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(1000, "orai"));
+    let withdraw = |amount: u128, emergency: bool| ExecuteMsg::WithdrawAsset {
+        coin: Amount::native(Uint128::from(amount), "orai".to_string()),
+        receiver: Some(Addr::unchecked("receiver")),
+        emergency,
+        reason: "withdraw surplus".to_string(),
+    };
+
+    // only what is not owed to users can be withdrawn
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator", &[]),
+        withdraw(301, false),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawExceedsSurplus {
+            denom: "orai".to_string(),
+            amount: Uint128::from(301u128),
+            surplus: Uint128::from(300u128),
+        }
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator", &[]),
+        withdraw(300, false),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(300, "orai"),
+        })]
+    );
+
+    // emergency withdrawals are queued by the owner behind the timelock
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("operator", &[]),
+        withdraw(1000, true),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        withdraw(1000, true),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    let res = execute_timelock_after_delay(deps.as_mut(), mock_env(), 0);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "receiver".to_string(),
+            amount: coins(1000, "orai"),
+        })]
+    );
+}
+
+#[test]
+fn test_local_obligations_round_up_dust() {
+    let local_channel_id = "channel-0";
+    let mut deps = setup(&[local_channel_id], &[]);
+    let asset_info = AssetInfo::NativeToken {
+        denom: "orai".to_string(),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("gov", &[]),
+        ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+            local_channel_id: local_channel_id.to_string(),
+            denom: "earth".to_string(),
+            local_asset_info: asset_info.clone(),
+            remote_decimals: 18,
+            local_asset_info_decimals: 6,
+            is_mint_burn: None,
+            supply_cap: None,
+            rescale_outstanding: None,
+            dust_policy: None,
+            status: None,
+            min_amount: None,
+            max_amount: None,
+            reason: "onboard chain".to_string(),
+        }),
+    )
+    .unwrap();
+    let ibc_denom = get_key_ics20_ibc_denom(CONTRACT_PORT, local_channel_id, "earth");
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(1_000_000_000_000u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    assert_eq!(
+        get_local_obligations_of_asset(deps.as_ref().storage, &asset_info).unwrap(),
+        Uint128::from(1u128)
+    );
+
+    // the remote dust is still owed, so it counts as a whole local unit
+    increase_channel_balance(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(1u128),
+        &mock_env().block,
+    )
+    .unwrap();
+    assert_eq!(
+        get_local_obligations_of_asset(deps.as_ref().storage, &asset_info).unwrap(),
+        Uint128::from(2u128)
+    );
+
+    // transfers back in flight are refunded from the contract if they fail
+    add_pending_packet(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(3_000_000_000_000u128),
+    )
+    .unwrap();
+    assert_eq!(
+        get_local_obligations_of_asset(deps.as_ref().storage, &asset_info).unwrap(),
+        Uint128::from(5u128)
+    );
+    // and so is the dust kept for sweeping, inbound dust once it adds up to whole local units
+    accumulate_dust(
+        deps.as_mut().storage,
+        &ibc_denom,
+        Uint128::from(2_000_000_000_005u128),
+        Uint128::from(4u128),
+    )
+    .unwrap();
+    assert_eq!(
+        get_local_obligations_of_asset(deps.as_ref().storage, &asset_info).unwrap(),
+        Uint128::from(11u128)
+    );
+    settle_pending_packet(
+        deps.as_mut().storage,
+        local_channel_id,
+        &ibc_denom,
+        Uint128::from(3_000_000_000_000u128),
+    )
+    .unwrap();
+    assert_eq!(
+        get_local_obligations_of_asset(deps.as_ref().storage, &asset_info).unwrap(),
+        Uint128::from(8u128)
+    );
+}
+
+#[test]
+fn test_update_config_unset_and_contract_info() {
+    let mut deps = setup(&["channel-1", "channel-2"], &[]);
+    let update_config = |default_gas_limit, admin| ExecuteMsg::UpdateConfig {
+        admin,
+        default_timeout: None,
+        default_gas_limit,
+        swap_router_contract: None,
+        token_fee: None,
+        relayer_fee: None,
+        fee_receiver: None,
+        relayer_fee_receiver: None,
+        converter_contract: None,
+        osor_entrypoint_contract: None,
+        token_factory_addr: None,
+        reason: "gas limit".to_string(),
+    };
+    let query_config = |deps: Deps| -> ConfigResponse {
+        from_json(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+    };
+    let gov = mock_info("gov", &[]);
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        gov.clone(),
+        update_config(
+            Some(UpdateValue::Set(123456)),
+            Some(UpdateValue::Set("new_owner".to_string())),
+        ),
+    )
+    .unwrap();
+    let config = query_config(deps.as_ref());
+    assert_eq!(config.default_gas_limit, Some(123456));
+    assert_eq!(config.pending_owner, Some(Addr::unchecked("new_owner")));
+
+    // fields left out are unchanged
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        gov.clone(),
+        update_config(None, None),
+    )
+    .unwrap();
+    let config = query_config(deps.as_ref());
+    assert_eq!(config.default_gas_limit, Some(123456));
+    assert_eq!(config.pending_owner, Some(Addr::unchecked("new_owner")));
+
+    // unset clears the gas limit and cancels the ownership transfer
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        gov.clone(),
+        update_config(Some(UpdateValue::Unset {}), Some(UpdateValue::Unset {})),
+    )
+    .unwrap();
+    let config = query_config(deps.as_ref());
+    assert_eq!(config.default_gas_limit, None);
+    assert_eq!(config.pending_owner, None);
+
+    assert_eq!(config.timelock_delay, DEFAULT_TIMELOCK_DELAY);
+
+    let query_contract_info = |deps: Deps| -> ContractInfoResponse {
+        from_json(&query(deps, mock_env(), QueryMsg::ContractInfo {}).unwrap()).unwrap()
+    };
+    let info = query_contract_info(deps.as_ref());
+    assert_eq!(info.contract, "crates.io:cw20-ics20");
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(info.features.contains(&"timelock".to_string()));
+    assert_eq!(info.channel_count, 2);
+    assert_eq!(info.mapping_count, 0);
+
+    // the mapping count follows added, updated and deleted pairs
+    let update = ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+        local_channel_id: "channel-1".to_string(),
+        denom: "uatom".to_string(),
+        local_asset_info: AssetInfo::NativeToken {
+            denom: "atom".to_string(),
+        },
+        remote_decimals: 6,
+        local_asset_info_decimals: 6,
+        is_mint_burn: None,
+        supply_cap: None,
+        rescale_outstanding: None,
+        dust_policy: None,
+        status: None,
+        min_amount: None,
+        max_amount: None,
+        reason: "update mapping pair".to_string(),
+    });
+    execute(deps.as_mut(), mock_env(), gov.clone(), update.clone()).unwrap();
+    execute(deps.as_mut(), mock_env(), gov.clone(), update).unwrap();
+    assert_eq!(query_contract_info(deps.as_ref()).mapping_count, 1);
+    let delete = ExecuteMsg::DeleteMappingPair(DeletePairMsg {
+        local_channel_id: "channel-1".to_string(),
+        denom: "uatom".to_string(),
+        reason: "delete mapping pair".to_string(),
+    });
+    execute(deps.as_mut(), mock_env(), gov.clone(), delete.clone()).unwrap();
+    execute(deps.as_mut(), mock_env(), gov, delete).unwrap();
+    assert_eq!(query_contract_info(deps.as_ref()).mapping_count, 0);
+}
+
+#[test]
+fn test_migrate() {
+    let channel = "channel-0";
+    let mut deps = setup(&[channel], &[]);
+    let env = mock_env();
+    let refund = RefundInfo {
+        receiver: "receiver".to_string(),
+        amount: Amount::from_parts("orai".to_string(), Uint128::from(10u128)),
+    };
+    REFUND_INFO_LIST
+        .save(deps.as_mut().storage, &vec![refund.clone()])
+        .unwrap();
+    CHANNEL_HISTORY_START.remove(deps.as_mut().storage);
+    CHANNEL_REVERSE_STATE
+        .save(
+            deps.as_mut().storage,
+            (channel, "port/channel/legacy"),
+            &ChannelState {
+                outstanding: Uint128::from(7u128),
+                total_sent: Uint128::from(20u128),
+                ..Default::default()
+            },
+            env.block.height,
+        )
+        .unwrap();
+    // a mint-burn mapping deployed before the minted supply was tracked
+    let minted_asset = AssetInfo::NativeToken {
+        denom: "factory/token_factory_addr/atom".to_string(),
+    };
+    let minted_denom = "wasm.cosmos2contract/channel-0/uatom";
+    ics20_denoms()
+        .save(
+            deps.as_mut().storage,
+            minted_denom,
+            &MappingMetadata {
+                asset_info: minted_asset.clone(),
+                remote_decimals: 6,
+                asset_info_decimals: 6,
+                is_mint_burn: true,
+                supply_cap: None,
+                dust_policy: DustPolicy::Truncate,
+                status: MappingStatus::Active,
+                min_amount: None,
+                max_amount: None,
+            },
+        )
+        .unwrap();
+    CHANNEL_REVERSE_STATE
+        .save(
+            deps.as_mut().storage,
+            (channel, minted_denom),
+            &ChannelState {
+                outstanding: Uint128::from(30u128),
+                total_sent: Uint128::from(30u128),
+                total_received: Uint128::from(30u128),
+                ..Default::default()
+            },
+            env.block.height,
+        )
+        .unwrap();
+    // a cw20 mapping onboarded before sends required the allow list
+    ics20_denoms()
+        .save(
+            deps.as_mut().storage,
+            "port/channel/legacy",
+            &MappingMetadata {
+                asset_info: AssetInfo::Token {
+                    contract_addr: Addr::unchecked("legacy_cw20"),
+                },
+                remote_decimals: 6,
+                asset_info_decimals: 6,
+                is_mint_burn: false,
+                supply_cap: None,
+                dust_policy: DustPolicy::Truncate,
+                status: MappingStatus::Active,
+                min_amount: None,
+                max_amount: None,
+            },
+        )
+        .unwrap();
+    let msg = MigrateMsg {
+        skip_migrations: vec![],
+    };
+
+    // foreign contracts and downgrades are refused
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "1.0.0").unwrap();
+    assert_eq!(
+        migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap_err(),
+        ContractError::CannotMigrate {
+            previous_contract: "crates.io:other".to_string()
+        }
+    );
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-ics20", "99.0.0").unwrap();
+    assert_eq!(
+        migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap_err(),
+        ContractError::CannotMigrateVersion {
+            previous_version: "99.0.0".to_string()
+        }
+    );
+
+    // skipped migrations do not run
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-ics20", "1.0.9").unwrap();
+    // only optional migrations can be skipped
+    for name in ["minted_supply", "unknown"] {
+        assert_eq!(
+            migrate(
+                deps.as_mut(),
+                env.clone(),
+                MigrateMsg {
+                    skip_migrations: vec![name.to_string()],
+                },
+            )
+            .unwrap_err(),
+            ContractError::CannotSkipMigration {
+                name: name.to_string()
+            }
+        );
+    }
+    let res = migrate(
+        deps.as_mut(),
+        env.clone(),
+        MigrateMsg {
+            skip_migrations: vec!["channel_stats".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "migrations",
+            "channel_history_start,refund_info,minted_supply,counts,allow_list"
+        )
+    );
+    // channel history starts from the upgrade
+    assert_eq!(
+        CHANNEL_HISTORY_START.load(deps.as_ref().storage).unwrap(),
+        env.block.height
+    );
+    // the pairs saved without the contract count them
+    assert_eq!(MAPPING_COUNT.load(deps.as_ref().storage).unwrap(), 2);
+    assert_eq!(CHANNEL_COUNT.load(deps.as_ref().storage).unwrap(), 1);
+    assert_eq!(
+        MINTED_SUPPLY
+            .load(deps.as_ref().storage, &minted_asset.to_string())
+            .unwrap(),
+        Uint128::from(30u128)
+    );
+    assert_eq!(
+        ALLOW_LIST
+            .load(deps.as_ref().storage, &Addr::unchecked("legacy_cw20"))
+            .unwrap(),
+        AllowInfo { gas_limit: None }
+    );
+    // the migrated tokens have no gas limit until they are capped
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("gov", &[]),
+        ExecuteMsg::Allow {
+            contract: "legacy_cw20".to_string(),
+            gas_limit: Some(200000),
+            reason: "cap legacy cw20".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        ALLOW_LIST
+            .load(deps.as_ref().storage, &Addr::unchecked("legacy_cw20"))
+            .unwrap(),
+        AllowInfo {
+            gas_limit: Some(200000)
+        }
+    );
+    let state = CHANNEL_REVERSE_STATE
+        .load(deps.as_ref().storage, (channel, "port/channel/legacy"))
+        .unwrap();
+    assert_eq!(state.total_received, Uint128::zero());
+
+    // pending refunds are kept on upgrade
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw20-ics20", "1.0.9").unwrap();
+    let res = migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr(
+            "migrations",
+            "channel_history_start,channel_stats,refund_info,minted_supply,counts,allow_list"
+        )
+    );
+    assert_eq!(
+        REFUND_INFO_LIST.load(deps.as_ref().storage).unwrap(),
+        vec![refund]
+    );
+    let state = CHANNEL_REVERSE_STATE
+        .load(deps.as_ref().storage, (channel, "port/channel/legacy"))
+        .unwrap();
+    assert_eq!(state.total_received, Uint128::from(20u128));
+    assert_eq!(
+        cw2::get_contract_version(deps.as_ref().storage)
+            .unwrap()
+            .version,
+        env!("CARGO_PKG_VERSION")
+    );
+
+    // migrating from the current version runs nothing
+    let res = migrate(deps.as_mut(), env, msg).unwrap();
+    assert_eq!(res.attributes[3], attr("migrations", ""));
+}
+
+// token fee ratios are at most one, a zero denominator disables the fee
+fn token_fee_ratio() -> impl Strategy<Value = Ratio> {
+    (any::<u64>(), any::<u64>()).prop_map(|(a, b)| Ratio {
+        nominator: a.min(b),
+        denominator: a.max(b),
+    })
+}
+
+proptest! {
+    #[test]
+    fn prop_deduct_fee_never_exceeds_amount(amount in any::<u128>(), ratio in token_fee_ratio()) {
+        prop_assert!(deduct_fee(ratio, Uint128::from(amount)) <= Uint128::from(amount));
+    }
+
+    #[test]
+    fn prop_process_deduct_fee_conserves_amount(
+        amount in any::<u64>(),
+        ratio in token_fee_ratio(),
+        relayer_fee in any::<u64>(),
+    ) {
+        let mut deps = mock_dependencies();
+        let deps_mut = deps.as_mut();
+        TOKEN_FEE.save(deps_mut.storage, "uatom", &ratio).unwrap();
+        RELAYER_FEE
+            .save(deps_mut.storage, "cosmos", &Uint128::from(relayer_fee))
+            .unwrap();
+        // relayer fees are quoted in orai, so no swap simulation is needed
+        let amount = Amount::native(Uint128::from(amount), "orai".to_string());
+        let fee_data = process_deduct_fee(
+            deps_mut.storage,
+            &deps_mut.querier,
+            deps_mut.api,
+            "cosmos1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n",
+            "uatom",
+            amount.clone(),
+            &RouterController("router".to_string()),
+        )
+        .unwrap();
+        prop_assert_eq!(
+            fee_data.token_fee.amount() + fee_data.relayer_fee.amount() + fee_data.deducted_amount,
+            amount.amount()
+        );
+    }
+}
+
+#[test]
+fn test_auto_registered_mapping_saved_only_on_success() {
+    let send_channel = "channel-9";
+    let denom = "oraib0x10407cEa4B614AB11bd05B326193d84ec20851f6";
+    let ibc_denom = format!("{}/{}/{}", CONTRACT_PORT, send_channel, denom);
+    let mut deps = setup(&[send_channel], &[]);
+    let mut packet =
+        mock_receive_packet_remote_to_local(send_channel, 876543210, denom, "custom-addr", None);
+    let mut data: Ics20Packet = from_json(&packet.data).unwrap();
+    data.memo = Some(r#"{"oraichain":{"action":"transfer","min_out":"1000000000"}}"#.to_string());
+    packet.data = to_json_binary(&data).unwrap();
+
+    // the register denom msg is dropped with the failed packet, so is the mapping
+    let res = ibc_packet_receive(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketReceiveMsg::new(packet.clone(), Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    assert!(matches!(
+        from_json::<Ics20Ack>(&res.acknowledgement).unwrap(),
+        Ics20Ack::Error(_)
+    ));
+    assert!(!ics20_denoms().has(deps.as_ref().storage, &ibc_denom));
+
+    data.memo = None;
+    packet.data = to_json_binary(&data).unwrap();
+    let res = ibc_packet_receive(
+        deps.as_mut(),
+        mock_env(),
+        IbcPacketReceiveMsg::new(packet, Addr::unchecked("relayer")),
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert!(ics20_denoms().has(deps.as_ref().storage, &ibc_denom));
+}
+
+#[test]
+fn test_refund_skips_supply_cap() {
+    let mut deps = setup(&["channel-0"], &[]);
+    let ibc_denom = "wasm.cosmos2contract/channel-0/uatom";
+    let asset_info = AssetInfo::NativeToken {
+        denom: "factory/token_factory_addr/atom".to_string(),
+    };
+    // the cap was lowered to zero while the packet was in flight
+    ics20_denoms()
+        .save(
+            deps.as_mut().storage,
+            ibc_denom,
+            &MappingMetadata {
+                asset_info: asset_info.clone(),
+                remote_decimals: 6,
+                asset_info_decimals: 6,
+                is_mint_burn: true,
+                supply_cap: Some(UpdateValue::Set(Uint128::zero())),
+                dust_policy: DustPolicy::Truncate,
+                status: MappingStatus::Active,
+                min_amount: None,
+                max_amount: None,
+            },
+        )
+        .unwrap();
+
+    handle_packet_refund(
+        deps.as_mut().storage,
+        "sender",
+        ibc_denom,
+        Uint128::from(100u128),
+        true,
+    )
+    .unwrap();
+    assert_eq!(
+        MINTED_SUPPLY
+            .load(deps.as_ref().storage, &asset_info.to_string())
+            .unwrap(),
+        Uint128::from(100u128)
+    );
+}
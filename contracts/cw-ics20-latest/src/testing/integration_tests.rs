@@ -1,171 +1,736 @@
-// use crate::ibc::Ics20Packet;
-// use crate::msg::{AllowMsg, InitMsg, UpdatePairMsg};
-// use crate::testing::test_helpers::{CONTRACT_PORT, DEFAULT_TIMEOUT, REMOTE_PORT, WASM_BYTES};
-
-// use cosmwasm_std::{to_json_binary, Addr, Coin, IbcEndpoint, IbcPacket, Timestamp};
-// use oraiswap::asset::{AssetInfo, ORAI_DENOM};
-// use osmosis_test_tube::{Account, Module, OraichainTestApp, Wasm};
-
-// use crate::msg::ExecuteMsg;
-
-// fn mock_app() -> OraichainTestApp {
-//     let router = OraichainTestApp::default();
-//     router
-// }
-
-// fn _mock_receive_packet(
-//     my_channel: &str,
-//     remote_channel: &str,
-//     amount: u128,
-//     native_denom: &str,
-//     remote_sender: &str,
-//     receiver: &str,
-// ) -> IbcPacket {
-//     let data = Ics20Packet {
-//         // this is returning a foreign (our) token, thus denom is <port>/<channel>/<denom>
-//         denom: format!("{}/{}/{}", REMOTE_PORT, remote_channel, native_denom),
-//         amount: amount.into(),
-//         sender: remote_sender.to_string(),
-//         receiver: receiver.to_string(),
-//         memo: None,
-//     };
-//     IbcPacket::new(
-//         to_json_binary(&data).unwrap(),
-//         IbcEndpoint {
-//             port_id: REMOTE_PORT.to_string(),
-//             channel_id: remote_channel.to_string(),
-//         },
-//         IbcEndpoint {
-//             port_id: CONTRACT_PORT.to_string(),
-//             channel_id: my_channel.to_string(),
-//         },
-//         3,
-//         Timestamp::from_seconds(1665321069).into(),
-//     )
-// }
-
-// fn initialize_basic_data_for_testings() -> (
-//     OraichainTestApp,
-//     Addr,
-//     String,
-//     IbcEndpoint,
-//     String,
-//     String,
-//     String,
-//     u8,
-// ) {
-//     let router = mock_app();
-//     let init_funds = [Coin::new(5_000_000_000_000u128, ORAI_DENOM)];
-//     let accounts = router.init_accounts(&init_funds, 1).unwrap();
-//     let owner = &accounts[0];
-//     let wasm = Wasm::new(&router);
-
-//     let cw20_ics20_id = wasm
-//         .store_code(WASM_BYTES, None, owner)
-//         .unwrap()
-//         .data
-//         .code_id;
-
-//     let allowlist: Vec<AllowMsg> = vec![];
-
-//     // arrange
-//     let addr1 = Addr::unchecked("addr1");
-//     let gov_cw20_ics20 = owner.address();
-
-//     // ibc stuff
-//     let src_ibc_endpoint = IbcEndpoint {
-//         port_id: REMOTE_PORT.to_string(),
-//         channel_id: "channel-0".to_string(),
-//     };
-
-//     let local_channel_id = "channel-0".to_string();
-
-//     let native_denom = "orai";
-//     let asset_info = AssetInfo::Token {
-//         contract_addr: Addr::unchecked("cw20:oraifoobarhelloworld".to_string()),
-//     };
-//     let remote_decimals = 18u8;
-//     let asset_info_decimals = 18u8;
-
-//     let cw20_ics20_init_msg = InitMsg {
-//         default_gas_limit: Some(20000000u64),
-//         default_timeout: DEFAULT_TIMEOUT,
-//         gov_contract: gov_cw20_ics20.clone(),
-//         allowlist,
-//         swap_router_contract: "router".to_string(),
-//         converter_contract: "converter".to_string(),
-//     };
-
-//     let cw20_ics20_contract = wasm
-//         .instantiate(
-//             cw20_ics20_id,
-//             &cw20_ics20_init_msg,
-//             Some(gov_cw20_ics20.as_str()),
-//             Some("cw20_ics20"),
-//             &[],
-//             owner,
-//         )
-//         .unwrap()
-//         .data
-//         .address;
-
-//     // update receiver contract
-
-//     let update_allow_msg = ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
-//         local_channel_id: local_channel_id.clone(),
-//         denom: native_denom.to_string(),
-//         local_asset_info: asset_info.clone(),
-//         remote_decimals,
-//         local_asset_info_decimals: asset_info_decimals,
-//     });
-//     wasm.execute(&cw20_ics20_contract, &update_allow_msg, &[], owner)
-//         .unwrap();
-
-//     (
-//         router,
-//         addr1,
-//         gov_cw20_ics20,
-//         src_ibc_endpoint,
-//         local_channel_id,
-//         native_denom.to_string(),
-//         asset_info.to_string(),
-//         remote_decimals,
-//     )
-// }
-
-// #[test]
-// // cw3 multisig account can control cw20 admin actions
-// fn initialize_valid_successful_cw20_ics20_and_receiver_contract() {
-//     initialize_basic_data_for_testings();
-// }
-
-// // #[test]
-// // // cw3 multisig account can control cw20 admin actions
-// // fn on_ibc_receive_invalid_submsg_when_calling_allow_contract_should_undo_increase_channel_balance()
-// // {
-// //     let (
-// //         router,
-// //         addr1,
-// //         gov_cw20_ics20,
-// //         src_ibc_endpoint,
-// //         dest_ibc_endpoint,
-// //         native_denom,
-// //         cw20_denom,
-// //         remote_decimals,
-// //         receiver_contract,
-// //     ) = initialize_basic_data_for_testings();
-
-// //     let amount = 1u128;
-// //     let remote_sender = Addr::unchecked("remote_sender");
-// //     let local_receiver = Addr::unchecked("local_receiver");
-
-// //     let recv_packet = mock_receive_packet(
-// //         &dest_ibc_endpoint.channel_id,
-// //         src_ibc_endpoint.channel_id.as_str(),
-// //         amount,
-// //         &native_denom,
-// //         remote_sender.as_str(),
-// //         local_receiver.as_str(),
-// //     );
-// //     let msg = IbcPacketReceiveMsg::new(recv_packet.clone());
-// // }
+use std::collections::VecDeque;
+
+use anybuf::Anybuf;
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Api, Binary, Decimal, IbcPacket, Uint128,
+};
+use cw20::Cw20ExecuteMsg;
+use cw20_ics20_msg::ibc_hooks::HookMethods;
+use cw20_ics20_msg::msg::{IbcLifecycleCallbackMsg, TransferBackMsg, UpdatePairMsg};
+use cw20_ics20_msg::state::RelayerFee;
+use oraiswap::asset::AssetInfo;
+use oraiswap::converter::TokenRatio;
+use proptest::prelude::*;
+use skip::entry_point::ExecuteMsg as EntryPointExecuteMsg;
+
+use crate::ibc::{Ics20Ack, Ics20Packet};
+use crate::msg::{ChannelResponse, ExecuteMsg, QueryMsg};
+use crate::testing::mock_chain::{
+    MockChain, BLOCK_GAS_LIMIT, BRIDGE, CONVERTER, DEFAULT_GAS_LIMIT, GOV, OSOR_ENTRYPOINT, RELAYER,
+};
+use crate::testing::test_helpers::{CONTRACT_PORT, DEFAULT_TIMEOUT};
+
+const CHANNEL: &str = "channel-0";
+const REMOTE_SENDER: &str = "cosmos1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5lzv7xu";
+const RECEIVER: &str = "orai1receiver";
+const ATOM_TOKEN: &str = "atom_token";
+
+fn ack_success() -> Ics20Ack {
+    Ics20Ack::Result(b"1".into())
+}
+
+fn update_pair(
+    chain: &mut MockChain,
+    denom: &str,
+    local_asset_info: AssetInfo,
+    is_mint_burn: bool,
+) {
+    chain
+        .execute(
+            GOV,
+            ExecuteMsg::UpdateMappingPair(UpdatePairMsg {
+                local_channel_id: CHANNEL.to_string(),
+                denom: denom.to_string(),
+                local_asset_info,
+                remote_decimals: 6,
+                local_asset_info_decimals: 6,
+                is_mint_burn: Some(is_mint_burn),
+                supply_cap: None,
+                rescale_outstanding: None,
+                dust_policy: None,
+                status: None,
+                min_amount: None,
+                max_amount: None,
+                reason: "update mapping pair".to_string(),
+            }),
+            &[],
+        )
+        .unwrap();
+}
+
+fn channel_balance(chain: &MockChain, denom: &str) -> Uint128 {
+    let ibc_denom = format!("{}/{}/{}", CONTRACT_PORT, CHANNEL, denom);
+    let channel: ChannelResponse = chain.query(QueryMsg::Channel {
+        id: CHANNEL.to_string(),
+    });
+    channel
+        .balances
+        .iter()
+        .find(|balance| balance.denom() == ibc_denom)
+        .map(|balance| balance.amount())
+        .unwrap_or_default()
+}
+
+fn transfer_back_msg() -> TransferBackMsg {
+    TransferBackMsg {
+        local_channel_id: CHANNEL.to_string(),
+        remote_address: REMOTE_SENDER.to_string(),
+        remote_denom: "uatom".to_string(),
+        timeout: None,
+        memo: None,
+        remote_prefix: None,
+        callback: None,
+    }
+}
+
+#[test]
+fn test_receive_and_transfer_back_cw20_with_relayer_fee() {
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.add_cw20(ATOM_TOKEN, 6);
+    chain.mint_cw20(ATOM_TOKEN, BRIDGE, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked(ATOM_TOKEN),
+        },
+        false,
+    );
+    // cw20 tokens must be on the allow list to be sent back
+    chain
+        .execute(
+            GOV,
+            ExecuteMsg::Allow {
+                contract: ATOM_TOKEN.to_string(),
+                gas_limit: Some(DEFAULT_GAS_LIMIT),
+                reason: "list atom".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    // relayer fee of 10 orai, paid in atom at the router price
+    chain.world().swap_rate = Some(Decimal::percent(200));
+    chain
+        .execute(
+            GOV,
+            ExecuteMsg::UpdateFees {
+                token_fee: None,
+                relayer_fee: Some(vec![RelayerFee {
+                    prefix: "cosmos".to_string(),
+                    fee: Uint128::from(10u128),
+                }]),
+                fee_receiver: None,
+                relayer_fee_receiver: None,
+                reason: "relayer fee".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(1000u128));
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, RECEIVER, "uatom", 1000, None)
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    assert_eq!(
+        chain.cw20_balance(ATOM_TOKEN, RECEIVER),
+        Uint128::from(980u128)
+    );
+    assert_eq!(
+        chain.cw20_balance(ATOM_TOKEN, RELAYER),
+        Uint128::from(20u128)
+    );
+    assert_eq!(chain.remote.escrow["uatom"], Uint128::from(1000u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(1000u128));
+
+    // the relayer fee of the transfer back goes to the relayer fee receiver
+    let res = chain
+        .execute_contract(
+            RECEIVER,
+            ATOM_TOKEN,
+            &Cw20ExecuteMsg::Send {
+                contract: BRIDGE.to_string(),
+                amount: Uint128::from(400u128),
+                msg: to_json_binary(&transfer_back_msg()).unwrap(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(res.attribute("relayer_fee"), Some("20"));
+    assert_eq!(
+        chain.cw20_balance(ATOM_TOKEN, RECEIVER),
+        Uint128::from(580u128)
+    );
+    assert_eq!(chain.cw20_balance(ATOM_TOKEN, GOV), Uint128::from(20u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(620u128));
+
+    chain.relay_outbound().unwrap();
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(380u128)
+    );
+    assert_eq!(chain.remote.escrow["uatom"], Uint128::from(620u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(620u128));
+}
+
+#[test]
+fn test_receive_mint_burn_with_universal_swap() {
+    let osmo = "factory/token_factory_addr/osmo";
+    let memo = "CgRvcmFpEgRvcmFp".to_string();
+    let mut chain = MockChain::new(&[CHANNEL]);
+    update_pair(
+        &mut chain,
+        "uosmo",
+        AssetInfo::NativeToken {
+            denom: osmo.to_string(),
+        },
+        true,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uosmo", Uint128::from(2000u128));
+
+    // the minted tokens are forwarded to the osor entrypoint with the memo
+    chain
+        .remote_transfer(
+            CHANNEL,
+            REMOTE_SENDER,
+            RECEIVER,
+            "uosmo",
+            1000,
+            Some(memo.clone()),
+        )
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    let calls = chain.contract_calls(OSOR_ENTRYPOINT);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].sender, BRIDGE);
+    assert_eq!(
+        calls[0].msg,
+        to_json_binary(&EntryPointExecuteMsg::UniversalSwap { memo: memo.clone() }).unwrap()
+    );
+    assert_eq!(calls[0].funds, coins(1000, osmo));
+    assert_eq!(
+        chain.balance(OSOR_ENTRYPOINT, osmo),
+        Uint128::from(1000u128)
+    );
+    let minted: Uint128 = chain.query(QueryMsg::MintedSupply {
+        asset_info: AssetInfo::NativeToken {
+            denom: osmo.to_string(),
+        },
+    });
+    assert_eq!(minted, Uint128::from(1000u128));
+
+    // a failed swap is still acked with success, the tokens stay on the bridge
+    chain
+        .world()
+        .failing_contracts
+        .insert(OSOR_ENTRYPOINT.to_string());
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, RECEIVER, "uosmo", 1000, Some(memo))
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    assert_eq!(chain.contract_calls(OSOR_ENTRYPOINT).len(), 1);
+    assert_eq!(chain.balance(BRIDGE, osmo), Uint128::from(1000u128));
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uosmo"),
+        Uint128::zero()
+    );
+    assert_eq!(channel_balance(&chain, "uosmo"), Uint128::from(2000u128));
+}
+
+#[test]
+fn test_transfer_back_refunded_on_error_ack_and_timeout() {
+    let atom = "ibc/atom";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.mint(BRIDGE, atom, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::NativeToken {
+            denom: atom.to_string(),
+        },
+        false,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(1000u128));
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, RECEIVER, "uatom", 1000, None)
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    assert_eq!(chain.balance(RECEIVER, atom), Uint128::from(1000u128));
+
+    let transfer_back = ExecuteMsg::TransferToRemote(transfer_back_msg());
+    chain
+        .execute(RECEIVER, transfer_back.clone(), &coins(300, atom))
+        .unwrap();
+    assert_eq!(chain.balance(RECEIVER, atom), Uint128::from(700u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(700u128));
+
+    // the counterparty rejects the packet
+    chain
+        .relay_outbound_with_error("receiver is blocked")
+        .unwrap();
+    assert_eq!(chain.balance(RECEIVER, atom), Uint128::from(1000u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(1000u128));
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::zero()
+    );
+
+    // the packet is not relayed before it expires
+    chain
+        .execute(RECEIVER, transfer_back, &coins(300, atom))
+        .unwrap();
+    chain.advance_time(DEFAULT_TIMEOUT + 1);
+    chain.relay_outbound().unwrap();
+    assert_eq!(chain.balance(RECEIVER, atom), Uint128::from(1000u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(1000u128));
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::zero()
+    );
+    assert_eq!(chain.remote.escrow["uatom"], Uint128::from(1000u128));
+}
+
+#[test]
+fn test_receive_min_out_not_met_refunds_remote_sender() {
+    let atom = "ibc/atom";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.mint(BRIDGE, atom, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::NativeToken {
+            denom: atom.to_string(),
+        },
+        false,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(1000u128));
+
+    let memo = r#"{"oraichain":{"action":"transfer","min_out":"2000"}}"#.to_string();
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, RECEIVER, "uatom", 1000, Some(memo))
+        .unwrap();
+    let acks = chain.relay_inbound();
+    assert!(matches!(acks[..], [Ics20Ack::Error(_)]));
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(chain.balance(RECEIVER, atom), Uint128::zero());
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::zero());
+}
+
+#[test]
+fn test_receive_contract_call_failure_refunds_sender() {
+    let atom = "ibc/atom";
+    let vault = "orai1vault";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.mint(BRIDGE, atom, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::NativeToken {
+            denom: atom.to_string(),
+        },
+        false,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(2000u128));
+    let hook_msg = Binary::from(br#"{"deposit":{}}"#);
+    let memo = format!(
+        r#"{{"oraichain":{{"action":"contract_call","msg":"{}"}}}}"#,
+        hook_msg.to_base64()
+    );
+
+    chain
+        .remote_transfer(
+            CHANNEL,
+            REMOTE_SENDER,
+            vault,
+            "uatom",
+            1000,
+            Some(memo.clone()),
+        )
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    let calls = chain.contract_calls(vault);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].msg, hook_msg);
+    assert_eq!(calls[0].funds, coins(1000, atom));
+
+    // a failing call reverts the receive, the sender is refunded on the counterparty
+    chain.world().failing_contracts.insert(vault.to_string());
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, vault, "uatom", 1000, Some(memo))
+        .unwrap();
+    let acks = chain.relay_inbound();
+    assert!(matches!(acks[..], [Ics20Ack::Error(_)]));
+    assert_eq!(chain.contract_calls(vault).len(), 1);
+    assert_eq!(chain.balance(vault, atom), Uint128::from(1000u128));
+    assert_eq!(
+        chain.balance(BRIDGE, atom),
+        Uint128::from(1_000_000u128 - 1000)
+    );
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(chain.remote.escrow["uatom"], Uint128::from(1000u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(1000u128));
+}
+
+#[test]
+fn test_receive_contract_call_out_of_gas() {
+    let osmo = "ibc/osmo";
+    let vault = "orai1vault";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.add_cw20(ATOM_TOKEN, 6);
+    chain.mint_cw20(ATOM_TOKEN, BRIDGE, 1_000_000);
+    chain.mint(BRIDGE, osmo, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::Token {
+            contract_addr: Addr::unchecked(ATOM_TOKEN),
+        },
+        false,
+    );
+    update_pair(
+        &mut chain,
+        "uosmo",
+        AssetInfo::NativeToken {
+            denom: osmo.to_string(),
+        },
+        false,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(1000u128));
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uosmo", Uint128::from(1000u128));
+    let memo = format!(
+        r#"{{"oraichain":{{"action":"contract_call","msg":"{}"}}}}"#,
+        Binary::from(br#"{"deposit":{}}"#).to_base64()
+    );
+
+    // the cw20 send runs out of the default gas limit, the receive fails and the sender is refunded
+    chain
+        .world()
+        .gas_costs
+        .insert(vault.to_string(), DEFAULT_GAS_LIMIT + 1);
+    chain
+        .remote_transfer(
+            CHANNEL,
+            REMOTE_SENDER,
+            vault,
+            "uatom",
+            1000,
+            Some(memo.clone()),
+        )
+        .unwrap();
+    let acks = chain.relay_inbound();
+    assert!(matches!(acks[..], [Ics20Ack::Error(_)]));
+    assert!(chain.contract_calls(vault).is_empty());
+    assert_eq!(chain.cw20_balance(ATOM_TOKEN, vault), Uint128::zero());
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::zero());
+
+    // native calls are capped by the default gas limit as well
+    chain
+        .world()
+        .gas_costs
+        .insert(vault.to_string(), DEFAULT_GAS_LIMIT + 1);
+    chain
+        .remote_transfer(
+            CHANNEL,
+            REMOTE_SENDER,
+            vault,
+            "uosmo",
+            1000,
+            Some(memo.clone()),
+        )
+        .unwrap();
+    let acks = chain.relay_inbound();
+    assert!(matches!(acks[..], [Ics20Ack::Error(_)]));
+    assert!(chain.contract_calls(vault).is_empty());
+    assert_eq!(chain.balance(vault, osmo), Uint128::zero());
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uosmo"),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(channel_balance(&chain, "uosmo"), Uint128::zero());
+
+    // a limit above the block gas does not stop the call from running out of the block gas, which aborts
+    // the receive. The packet is never acked, the sender is refunded when it times out
+    chain
+        .execute(
+            GOV,
+            ExecuteMsg::Allow {
+                contract: ATOM_TOKEN.to_string(),
+                gas_limit: Some(2 * BLOCK_GAS_LIMIT),
+                reason: "list atom".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    chain
+        .world()
+        .gas_costs
+        .insert(vault.to_string(), BLOCK_GAS_LIMIT + 1);
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, vault, "uatom", 1000, Some(memo))
+        .unwrap();
+    assert!(chain.relay_inbound().is_empty());
+    assert_eq!(chain.unacked.len(), 1);
+    assert!(chain.contract_calls(vault).is_empty());
+    assert_eq!(chain.cw20_balance(ATOM_TOKEN, vault), Uint128::zero());
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::zero());
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::zero()
+    );
+    assert!(chain.timeout_inbound().is_empty());
+    chain.advance_time(DEFAULT_TIMEOUT);
+    assert_eq!(chain.timeout_inbound().len(), 1);
+    assert!(chain.unacked.is_empty());
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(1000u128)
+    );
+}
+
+#[test]
+fn test_transfer_back_lifecycle_callback() {
+    let atom = "ibc/atom";
+    let vault = "orai1vault";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.mint(BRIDGE, atom, 1_000_000);
+    update_pair(
+        &mut chain,
+        "uatom",
+        AssetInfo::NativeToken {
+            denom: atom.to_string(),
+        },
+        false,
+    );
+    chain
+        .remote
+        .mint(REMOTE_SENDER, "uatom", Uint128::from(1000u128));
+    chain
+        .remote_transfer(CHANNEL, REMOTE_SENDER, vault, "uatom", 1000, None)
+        .unwrap();
+    assert_eq!(chain.relay_inbound(), vec![ack_success()]);
+    let transfer_back = ExecuteMsg::TransferToRemote(TransferBackMsg {
+        callback: Some(vault.to_string()),
+        ..transfer_back_msg()
+    });
+
+    // the vault is notified once the counterparty acks the packet
+    chain
+        .execute(vault, transfer_back.clone(), &coins(300, atom))
+        .unwrap();
+    chain.relay_outbound().unwrap();
+    let calls = chain.contract_calls(vault);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].sender, BRIDGE);
+    assert_eq!(
+        calls[0].msg,
+        to_json_binary(&IbcLifecycleCallbackMsg::IbcLifecycleComplete {
+            channel_id: CHANNEL.to_string(),
+            sequence: 2,
+            success: true,
+            refunded_amount: Uint128::zero(),
+        })
+        .unwrap()
+    );
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(300u128)
+    );
+
+    // a callback that runs out of gas is reverted, the ack is still processed
+    chain
+        .world()
+        .gas_costs
+        .insert(vault.to_string(), DEFAULT_GAS_LIMIT + 1);
+    chain
+        .execute(vault, transfer_back.clone(), &coins(300, atom))
+        .unwrap();
+    let res = chain.relay_outbound().unwrap();
+    assert!(res[0]
+        .attribute("error_trying_to_call_lifecycle_callback")
+        .is_some());
+    assert_eq!(chain.contract_calls(vault).len(), 1);
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(600u128)
+    );
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(400u128));
+
+    // a failing callback does not block the refund of a timed out packet
+    chain.world().gas_costs.clear();
+    chain.world().failing_contracts.insert(vault.to_string());
+    chain
+        .execute(vault, transfer_back, &coins(300, atom))
+        .unwrap();
+    assert_eq!(chain.balance(vault, atom), Uint128::from(100u128));
+    chain.advance_time(DEFAULT_TIMEOUT + 1);
+    let res = chain.relay_outbound().unwrap();
+    assert!(res[0]
+        .attribute("error_trying_to_call_lifecycle_callback")
+        .is_some());
+    assert_eq!(chain.contract_calls(vault).len(), 1);
+    assert_eq!(chain.balance(vault, atom), Uint128::from(400u128));
+    assert_eq!(channel_balance(&chain, "uatom"), Uint128::from(400u128));
+    assert_eq!(
+        chain.remote.balance(REMOTE_SENDER, "uatom"),
+        Uint128::from(600u128)
+    );
+}
+
+#[test]
+fn test_ibc_hooks_transfer_through_converter() {
+    let inj_token = "inj_token";
+    let hooks_caller = "hooks_caller";
+    let mut chain = MockChain::new(&[CHANNEL]);
+    chain.add_cw20(inj_token, 6);
+    chain.world().converter.insert(
+        AssetInfo::NativeToken {
+            denom: "uinj".to_string(),
+        }
+        .to_string(),
+        TokenRatio {
+            is_mint_burn: false,
+            info: AssetInfo::Token {
+                contract_addr: Addr::unchecked(inj_token),
+            },
+            ratio: Decimal::from_ratio(1u128, 1000u128),
+        },
+    );
+    chain.mint(hooks_caller, "uinj", 1_000_000);
+
+    let receiver = chain.deps.api.addr_canonicalize(RECEIVER).unwrap();
+    let args = Binary::from(
+        Anybuf::new()
+            .append_bytes(1, receiver.as_slice())
+            .as_bytes(),
+    );
+    let res = chain
+        .execute(
+            hooks_caller,
+            ExecuteMsg::IbcHooksReceive {
+                func: HookMethods::Transfer,
+                orai_receiver: RECEIVER.to_string(),
+                args,
+            },
+            &coins(1_000_000, "uinj"),
+        )
+        .unwrap();
+    assert_eq!(res.attribute("action"), Some("receive_ibc_hooks_transfer"));
+    assert_eq!(
+        chain.balance(CONVERTER, "uinj"),
+        Uint128::from(1_000_000u128)
+    );
+    assert_eq!(
+        chain.cw20_balance(inj_token, RECEIVER),
+        Uint128::from(1000u128)
+    );
+    assert_eq!(chain.cw20_balance(inj_token, BRIDGE), Uint128::zero());
+}
+
+#[derive(Clone, Debug)]
+enum Step {
+    Receive(u64),
+    TransferBack(u64),
+    RelayInbound,
+    RelayOutbound,
+    RejectOutbound,
+    TimeoutOutbound,
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    prop_oneof![
+        (1..1000u64).prop_map(Step::Receive),
+        (1..1000u64).prop_map(Step::TransferBack),
+        Just(Step::RelayInbound),
+        Just(Step::RelayOutbound),
+        Just(Step::RejectOutbound),
+        Just(Step::TimeoutOutbound),
+    ]
+}
+
+fn in_flight(packets: &VecDeque<IbcPacket>) -> Uint128 {
+    packets
+        .iter()
+        .map(|packet| from_json::<Ics20Packet>(&packet.data).unwrap().amount)
+        .sum()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_channel_balance_matches_counterparty_escrow(
+        steps in prop::collection::vec(step(), 1..30),
+    ) {
+        let atom = "ibc/atom";
+        let supply = Uint128::from(1_000_000u128);
+        let mut chain = MockChain::new(&[CHANNEL]);
+        chain.mint(BRIDGE, atom, supply.u128());
+        update_pair(
+            &mut chain,
+            "uatom",
+            AssetInfo::NativeToken {
+                denom: atom.to_string(),
+            },
+            false,
+        );
+        chain.remote.mint(REMOTE_SENDER, "uatom", supply);
+
+        for step in steps {
+            match step {
+                Step::Receive(amount) => chain
+                    .remote_transfer(CHANNEL, REMOTE_SENDER, RECEIVER, "uatom", amount.into(), None)
+                    .unwrap(),
+                // transfers above the balance of the receiver or the channel fail and are reverted
+                Step::TransferBack(amount) => {
+                    let msg = ExecuteMsg::TransferToRemote(transfer_back_msg());
+                    let _ = chain.execute(RECEIVER, msg, &coins(amount.into(), atom));
+                }
+                Step::RelayInbound => {
+                    chain.relay_inbound();
+                }
+                Step::RelayOutbound => {
+                    chain.relay_outbound().unwrap();
+                }
+                Step::RejectOutbound => {
+                    chain.relay_outbound_with_error("rejected").unwrap();
+                }
+                Step::TimeoutOutbound => {
+                    chain.advance_time(DEFAULT_TIMEOUT + 1);
+                    chain.relay_outbound().unwrap();
+                }
+            }
+
+            // the escrow of the counterparty is owed by the channel or in flight
+            let escrow = chain.remote.escrow.get("uatom").copied().unwrap_or_default();
+            let pending = in_flight(&chain.inbox) + in_flight(&chain.outbox);
+            prop_assert_eq!(escrow, channel_balance(&chain, "uatom") + pending);
+            // tokens only move between the sender and the escrow on each side
+            prop_assert_eq!(chain.remote.balance(REMOTE_SENDER, "uatom") + escrow, supply);
+            prop_assert_eq!(chain.balance(BRIDGE, atom) + chain.balance(RECEIVER, atom), supply);
+        }
+    }
+}
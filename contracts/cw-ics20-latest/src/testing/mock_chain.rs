@@ -0,0 +1,1001 @@
+//! In-process multi-chain harness. It drives the bridge entry points the way wasmd does, with mock bank, cw20,
+//! tokenfactory, converter, router and osor entrypoint contracts on Oraichain, and a counterparty ics20 chain
+//! whose packets are relayed by hand, so full receive, swap, transfer back and refund flows run in `cargo test`.
+//!
+//! It is not built on an app of cosmwasm-testing-util or cw-multi-test because those never call the ibc entry
+//! points of a contract and fail its ibc msgs, so packets could not be received, acked or timed out. The
+//! `MockContract` of cosmwasm-testing-util runs a single compiled contract without the other chain modules.
+//! Gas is only counted for the contracts given a cost in `World::gas_costs`, which is enough to check how
+//! the bridge limits the submessages that run untrusted contracts
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::marker::PhantomData;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::serde::{de::DeserializeOwned, Serialize};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Attribute, BankMsg, BankQuery, Binary, Coin, CosmosMsg,
+    Decimal, Empty, Env, Event, IbcAcknowledgement, IbcEndpoint, IbcMsg, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcTimeout, Order, OwnedDeps,
+    Querier, QuerierResult, QueryRequest, Reply, ReplyOn, Response, StdError, StdResult, Storage,
+    SubMsg, SubMsgResponse, SubMsgResult, SystemError, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
+use oraiswap::asset::AssetInfo;
+use oraiswap::converter::{ConvertInfoResponse, TokenRatio};
+use oraiswap::math::Converter128;
+
+use crate::contract::{execute, instantiate, query};
+use crate::ibc::{
+    ack_fail, ibc_packet_ack, ibc_packet_receive, ibc_packet_timeout, reply, Ics20Ack, Ics20Packet,
+};
+use crate::msg::{ExecuteMsg, InitMsg, QueryMsg};
+use crate::state::CHANNEL_INFO;
+use crate::testing::test_helpers::{add_channel, CONTRACT_PORT, DEFAULT_TIMEOUT};
+
+pub const BRIDGE: &str = cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+pub const GOV: &str = "gov";
+pub const ROUTER: &str = "router";
+pub const CONVERTER: &str = "converter";
+pub const OSOR_ENTRYPOINT: &str = "osor_entrypoint_contract";
+pub const TOKEN_FACTORY: &str = "token_factory_addr";
+pub const RELAYER: &str = "relayer";
+/// gas limit of cw20 contracts off the allow list
+pub const DEFAULT_GAS_LIMIT: u64 = 1_000_000;
+/// gas of a whole transaction
+pub const BLOCK_GAS_LIMIT: u64 = 10_000_000;
+
+/// Result of a transaction, with the attributes of every contract call it made
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppResponse {
+    pub attributes: Vec<Attribute>,
+    pub events: Vec<Event>,
+    pub data: Option<Binary>,
+}
+
+impl AppResponse {
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+    }
+}
+
+/// Execute msg received by a contract that is only recorded by the harness, eg: the osor entrypoint
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractCall {
+    pub contract: String,
+    pub sender: String,
+    pub msg: Binary,
+    pub funds: Vec<Coin>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Cw20Token {
+    pub decimals: u8,
+    pub balances: BTreeMap<String, Uint128>,
+}
+
+/// State of the mock contracts and the bank module of Oraichain
+#[derive(Clone, Debug, Default)]
+pub struct World {
+    pub bank: BTreeMap<(String, String), Uint128>,
+    pub cw20: BTreeMap<String, Cw20Token>,
+    /// convert info by the source asset
+    pub converter: BTreeMap<String, TokenRatio>,
+    /// price of orai in any other asset, router simulations fail if not set
+    pub swap_rate: Option<Decimal>,
+    /// contracts whose executions fail
+    pub failing_contracts: BTreeSet<String>,
+    /// gas burned by each execution of a contract, others are free
+    pub gas_costs: BTreeMap<String, u64>,
+    pub contract_calls: Vec<ContractCall>,
+}
+
+impl World {
+    pub fn balance(&self, address: &str, denom: &str) -> Uint128 {
+        self.bank
+            .get(&(address.to_string(), denom.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn supply(&self, denom: &str) -> Uint128 {
+        self.bank
+            .iter()
+            .filter(|((_, balance_denom), _)| balance_denom == denom)
+            .map(|(_, amount)| *amount)
+            .sum()
+    }
+
+    fn mint(&mut self, address: &str, denom: &str, amount: Uint128) {
+        *self
+            .bank
+            .entry((address.to_string(), denom.to_string()))
+            .or_default() += amount;
+    }
+
+    fn burn(&mut self, address: &str, denom: &str, amount: Uint128) -> Result<(), String> {
+        let balance = self.balance(address, denom);
+        let left = balance.checked_sub(amount).map_err(|_| {
+            format!(
+                "insufficient funds: {} has {}{}, needs {}{}",
+                address, balance, denom, amount, denom
+            )
+        })?;
+        self.bank
+            .insert((address.to_string(), denom.to_string()), left);
+        Ok(())
+    }
+
+    fn cw20_mint(&mut self, token: &str, address: &str, amount: Uint128) -> Result<(), String> {
+        let token = self
+            .cw20
+            .get_mut(token)
+            .ok_or_else(|| format!("no such cw20 token {}", token))?;
+        *token.balances.entry(address.to_string()).or_default() += amount;
+        Ok(())
+    }
+
+    fn cw20_burn(&mut self, token: &str, address: &str, amount: Uint128) -> Result<(), String> {
+        let cw20 = self
+            .cw20
+            .get_mut(token)
+            .ok_or_else(|| format!("no such cw20 token {}", token))?;
+        let balance = cw20.balances.entry(address.to_string()).or_default();
+        *balance = balance
+            .checked_sub(amount)
+            .map_err(|_| format!("insufficient {} balance of {}", token, address))?;
+        Ok(())
+    }
+
+    fn query_wasm(&self, contract: &str, msg: &Binary) -> StdResult<Binary> {
+        if let Some(token) = self.cw20.get(contract) {
+            return match from_json(msg)? {
+                Cw20QueryMsg::Balance { address } => to_json_binary(&BalanceResponse {
+                    balance: token.balances.get(&address).copied().unwrap_or_default(),
+                }),
+                Cw20QueryMsg::TokenInfo {} => to_json_binary(&TokenInfoResponse {
+                    name: contract.to_string(),
+                    symbol: "TOKEN".to_string(),
+                    decimals: token.decimals,
+                    total_supply: token.balances.values().copied().sum(),
+                }),
+                _ => Err(StdError::generic_err("unsupported cw20 query")),
+            };
+        }
+        match contract {
+            CONVERTER => {
+                let ConverterQuery::ConvertInfo { asset_info } = from_json(msg)?;
+                let token_ratio = self
+                    .converter
+                    .get(&asset_info.to_string())
+                    .cloned()
+                    .ok_or_else(|| StdError::generic_err("Converter info not found"))?;
+                to_json_binary(&ConvertInfoResponse { token_ratio })
+            }
+            ROUTER => {
+                let RouterQuery::SimulateSwapOperations { offer_amount, .. } = from_json(msg)?;
+                let rate = self
+                    .swap_rate
+                    .ok_or_else(|| StdError::generic_err("no pool for the swap"))?;
+                to_json_binary(&SimulateSwapResponse {
+                    amount: offer_amount * rate,
+                })
+            }
+            _ => Err(StdError::generic_err(format!(
+                "no such contract {}",
+                contract
+            ))),
+        }
+    }
+}
+
+// mirrors of the external msgs the bridge sends, so that the harness only depends on their json format
+#[cw_serde]
+enum TokenFactoryMsg {
+    MintTokens {
+        denom: String,
+        amount: Uint128,
+        mint_to_address: String,
+    },
+    BurnTokens {
+        denom: String,
+        amount: Uint128,
+        burn_from_address: String,
+    },
+}
+
+#[cw_serde]
+enum ConverterMsg {
+    Convert {},
+    ConvertReverse { from_asset: AssetInfo },
+    Receive(Cw20ReceiveMsg),
+}
+
+#[cw_serde]
+enum ConverterHookMsg {
+    Convert {},
+    ConvertReverse { from: AssetInfo },
+}
+
+#[cw_serde]
+enum ConverterQuery {
+    ConvertInfo { asset_info: AssetInfo },
+}
+
+// only the offer amount matters, the operations are ignored
+#[derive(cosmwasm_schema::serde::Deserialize)]
+#[serde(crate = "cosmwasm_schema::serde", rename_all = "snake_case")]
+enum RouterQuery {
+    SimulateSwapOperations { offer_amount: Uint128 },
+}
+
+#[cw_serde]
+struct SimulateSwapResponse {
+    amount: Uint128,
+}
+
+#[cw_serde]
+struct CoinResponse {
+    amount: Coin,
+}
+
+#[cw_serde]
+struct CoinsResponse {
+    amount: Vec<Coin>,
+}
+
+/// Answers bank and wasm queries from the state of the mock world, others go to the mock querier
+pub struct ChainQuerier {
+    base: MockQuerier,
+    pub world: World,
+}
+
+impl Querier for ChainQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_json(bin_request) {
+            Ok(request) => request,
+            Err(err) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", err),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        match &request {
+            QueryRequest::Bank(BankQuery::Balance { address, denom }) => {
+                querier_result(Ok(CoinResponse {
+                    amount: Coin::new(self.world.balance(address, denom).u128(), denom),
+                }))
+            }
+            QueryRequest::Bank(BankQuery::AllBalances { address }) => {
+                querier_result(Ok(CoinsResponse {
+                    amount: self
+                        .world
+                        .bank
+                        .iter()
+                        .filter(|((owner, _), amount)| owner == address && !amount.is_zero())
+                        .map(|((_, denom), amount)| Coin::new(amount.u128(), denom))
+                        .collect(),
+                }))
+            }
+            QueryRequest::Bank(BankQuery::Supply { denom }) => querier_result(Ok(CoinResponse {
+                amount: Coin::new(self.world.supply(denom).u128(), denom),
+            })),
+            QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
+                SystemResult::Ok(self.world.query_wasm(contract_addr, msg).into())
+            }
+            _ => self.base.handle_query(&request),
+        }
+    }
+}
+
+fn querier_result<T: Serialize>(res: StdResult<T>) -> QuerierResult {
+    SystemResult::Ok(res.and_then(|res| to_json_binary(&res)).into())
+}
+
+/// Bank balances of the counterparty chain and the escrow of its transfer module
+#[derive(Clone, Debug, Default)]
+pub struct Counterparty {
+    pub balances: BTreeMap<(String, String), Uint128>,
+    pub escrow: BTreeMap<String, Uint128>,
+}
+
+impl Counterparty {
+    pub fn balance(&self, address: &str, denom: &str) -> Uint128 {
+        self.balances
+            .get(&(address.to_string(), denom.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn mint(&mut self, address: &str, denom: &str, amount: Uint128) {
+        *self
+            .balances
+            .entry((address.to_string(), denom.to_string()))
+            .or_default() += amount;
+    }
+
+    fn burn(&mut self, address: &str, denom: &str, amount: Uint128) -> Result<(), String> {
+        let balance = self
+            .balances
+            .entry((address.to_string(), denom.to_string()))
+            .or_default();
+        *balance = balance
+            .checked_sub(amount)
+            .map_err(|_| format!("insufficient {} on the counterparty", denom))?;
+        Ok(())
+    }
+
+    // tokens leaving their source chain are escrowed, vouchers would be burned instead
+    fn escrow(&mut self, sender: &str, denom: &str, amount: Uint128) -> Result<(), String> {
+        self.burn(sender, denom, amount)?;
+        *self.escrow.entry(denom.to_string()).or_default() += amount;
+        Ok(())
+    }
+
+    fn unescrow(&mut self, receiver: &str, denom: &str, amount: Uint128) -> Result<(), String> {
+        let escrow = self.escrow.entry(denom.to_string()).or_default();
+        *escrow = escrow
+            .checked_sub(amount)
+            .map_err(|_| format!("insufficient {} in escrow", denom))?;
+        self.mint(receiver, denom, amount);
+        Ok(())
+    }
+
+    // receives a packet from Oraichain like the transfer module: denoms prefixed with the source endpoint
+    // originated here and are unescrowed, others are minted as vouchers
+    fn on_recv_packet(&mut self, packet: &IbcPacket) -> Result<(), String> {
+        let msg: Ics20Packet = from_json(&packet.data).map_err(|err| err.to_string())?;
+        let source_prefix = format!("{}/{}/", packet.src.port_id, packet.src.channel_id);
+        match msg.denom.strip_prefix(&source_prefix) {
+            Some(denom) => self.unescrow(&msg.receiver, denom, msg.amount),
+            None => {
+                let voucher = format!(
+                    "{}/{}/{}",
+                    packet.dest.port_id, packet.dest.channel_id, msg.denom
+                );
+                self.mint(&msg.receiver, &voucher, msg.amount);
+                Ok(())
+            }
+        }
+    }
+
+    // refunds the sender of a packet sent to Oraichain that failed
+    fn on_packet_failure(&mut self, packet: &IbcPacket) -> Result<(), String> {
+        let msg: Ics20Packet = from_json(&packet.data).map_err(|err| err.to_string())?;
+        self.unescrow(&msg.sender, &msg.denom, msg.amount)
+    }
+}
+
+struct Snapshot {
+    storage: Vec<(Vec<u8>, Vec<u8>)>,
+    world: World,
+    remote: Counterparty,
+    outbox: VecDeque<IbcPacket>,
+    inbox: VecDeque<IbcPacket>,
+}
+
+/// Oraichain with the bridge deployed at `BRIDGE`, connected to a counterparty chain over ics20 channels
+pub struct MockChain {
+    pub deps: OwnedDeps<MockStorage, MockApi, ChainQuerier>,
+    pub env: Env,
+    pub remote: Counterparty,
+    /// packets sent by the bridge that have not been relayed to the counterparty yet
+    pub outbox: VecDeque<IbcPacket>,
+    /// packets sent by the counterparty that have not been relayed to the bridge yet
+    pub inbox: VecDeque<IbcPacket>,
+    /// packets of the counterparty whose receive aborted, they are never acked and time out instead
+    pub unacked: VecDeque<IbcPacket>,
+    sequence: u64,
+    /// gas used by the current transaction
+    gas_used: u64,
+}
+
+impl MockChain {
+    /// Deploys the bridge with the mock dependencies and opens the given channels
+    pub fn new(channels: &[&str]) -> Self {
+        let mut chain = MockChain {
+            deps: OwnedDeps {
+                storage: MockStorage::default(),
+                api: MockApi::default(),
+                querier: ChainQuerier {
+                    base: MockQuerier::new(&[]),
+                    world: World::default(),
+                },
+                custom_query_type: PhantomData,
+            },
+            env: mock_env(),
+            remote: Counterparty::default(),
+            outbox: VecDeque::new(),
+            inbox: VecDeque::new(),
+            unacked: VecDeque::new(),
+            sequence: 0,
+            gas_used: 0,
+        };
+        instantiate(
+            chain.deps.as_mut(),
+            chain.env.clone(),
+            mock_info(GOV, &[]),
+            InitMsg {
+                default_timeout: DEFAULT_TIMEOUT,
+                gov_contract: GOV.to_string(),
+                allowlist: vec![],
+                default_gas_limit: Some(DEFAULT_GAS_LIMIT),
+                swap_router_contract: ROUTER.to_string(),
+                converter_contract: CONVERTER.to_string(),
+                osor_entrypoint_contract: OSOR_ENTRYPOINT.to_string(),
+                token_factory_addr: TOKEN_FACTORY.to_string(),
+            },
+        )
+        .unwrap();
+        for channel in channels {
+            add_channel(chain.deps.as_mut(), channel);
+        }
+        chain
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        &mut self.deps.querier.world
+    }
+
+    pub fn balance(&self, address: &str, denom: &str) -> Uint128 {
+        self.deps.querier.world.balance(address, denom)
+    }
+
+    pub fn mint(&mut self, address: &str, denom: &str, amount: u128) {
+        self.world().mint(address, denom, amount.into());
+    }
+
+    pub fn add_cw20(&mut self, token: &str, decimals: u8) {
+        self.world().cw20.insert(
+            token.to_string(),
+            Cw20Token {
+                decimals,
+                balances: BTreeMap::new(),
+            },
+        );
+    }
+
+    pub fn mint_cw20(&mut self, token: &str, address: &str, amount: u128) {
+        self.world()
+            .cw20_mint(token, address, amount.into())
+            .unwrap();
+    }
+
+    pub fn cw20_balance(&self, token: &str, address: &str) -> Uint128 {
+        self.deps.querier.world.cw20[token]
+            .balances
+            .get(address)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Calls made to the contracts that are only recorded by the harness
+    pub fn contract_calls(&self, contract: &str) -> Vec<ContractCall> {
+        self.deps
+            .querier
+            .world
+            .contract_calls
+            .iter()
+            .filter(|call| call.contract == contract)
+            .cloned()
+            .collect()
+    }
+
+    pub fn advance_time(&mut self, seconds: u64) {
+        self.env.block.time = self.env.block.time.plus_seconds(seconds);
+        self.env.block.height += seconds / 5;
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, msg: QueryMsg) -> T {
+        from_json(query(self.deps.as_ref(), self.env.clone(), msg).unwrap()).unwrap()
+    }
+
+    /// Executes the bridge in a transaction, all state changes are reverted if it fails
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        msg: ExecuteMsg,
+        funds: &[Coin],
+    ) -> Result<AppResponse, String> {
+        let msg = to_json_binary(&msg).map_err(|err| err.to_string())?;
+        self.transact(|chain| chain.execute_wasm(sender, BRIDGE, msg, funds.to_vec()))
+    }
+
+    /// Executes a mock contract in a transaction, eg: a cw20 send to the bridge
+    pub fn execute_contract<T: Serialize>(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: &T,
+        funds: &[Coin],
+    ) -> Result<AppResponse, String> {
+        let msg = to_json_binary(msg).map_err(|err| err.to_string())?;
+        self.transact(|chain| chain.execute_wasm(sender, contract, msg, funds.to_vec()))
+    }
+
+    /// Sends tokens of the counterparty chain to Oraichain over the counterparty of the local channel
+    pub fn remote_transfer(
+        &mut self,
+        local_channel_id: &str,
+        sender: &str,
+        receiver: &str,
+        denom: &str,
+        amount: u128,
+        memo: Option<String>,
+    ) -> Result<(), String> {
+        let channel = CHANNEL_INFO
+            .load(&self.deps.storage, local_channel_id)
+            .map_err(|err| err.to_string())?;
+        self.remote.escrow(sender, denom, amount.into())?;
+        let packet = Ics20Packet::new(amount.into(), denom, sender, receiver, memo);
+        self.sequence += 1;
+        self.inbox.push_back(IbcPacket::new(
+            to_json_binary(&packet).map_err(|err| err.to_string())?,
+            channel.counterparty_endpoint,
+            IbcEndpoint {
+                port_id: CONTRACT_PORT.to_string(),
+                channel_id: local_channel_id.to_string(),
+            },
+            self.sequence,
+            IbcTimeout::with_timestamp(self.env.block.time.plus_seconds(DEFAULT_TIMEOUT)),
+        ));
+        Ok(())
+    }
+
+    /// Relays the packets of the counterparty to the bridge and their acks back, failed packets are refunded.
+    /// Packets whose receive aborted have no ack, they are kept in `unacked` until they time out
+    pub fn relay_inbound(&mut self) -> Vec<Ics20Ack> {
+        let mut acks = vec![];
+        while let Some(packet) = self.inbox.pop_front() {
+            let Some(ack) = self.receive_packet(packet.clone()) else {
+                self.unacked.push_back(packet);
+                continue;
+            };
+            let ack: Ics20Ack = from_json(&ack).unwrap();
+            if let Ics20Ack::Error(_) = ack {
+                self.remote.on_packet_failure(&packet).unwrap();
+            }
+            acks.push(ack);
+        }
+        acks
+    }
+
+    /// Times out the unacked packets of the counterparty that expired at the current block time, their
+    /// senders are refunded
+    pub fn timeout_inbound(&mut self) -> Vec<IbcPacket> {
+        let block_time = self.env.block.time;
+        let (expired, unacked): (Vec<_>, VecDeque<_>) = std::mem::take(&mut self.unacked)
+            .into_iter()
+            .partition(|packet| {
+                packet
+                    .timeout
+                    .timestamp()
+                    .map_or(false, |timeout| timeout <= block_time)
+            });
+        self.unacked = unacked;
+        for packet in &expired {
+            self.remote.on_packet_failure(packet).unwrap();
+        }
+        expired
+    }
+
+    /// Relays the packets of the bridge to the counterparty and their acks back. Packets that expired
+    /// at the current block time are timed out instead
+    pub fn relay_outbound(&mut self) -> Result<Vec<AppResponse>, String> {
+        self.relay_outbound_with(|remote, packet| remote.on_recv_packet(packet))
+    }
+
+    /// Relays the packets of the bridge with an error ack, as if the counterparty rejected them
+    pub fn relay_outbound_with_error(&mut self, err: &str) -> Result<Vec<AppResponse>, String> {
+        self.relay_outbound_with(|_, _| Err(err.to_string()))
+    }
+
+    fn relay_outbound_with(
+        &mut self,
+        on_recv: impl Fn(&mut Counterparty, &IbcPacket) -> Result<(), String>,
+    ) -> Result<Vec<AppResponse>, String> {
+        let mut responses = vec![];
+        while let Some(packet) = self.outbox.pop_front() {
+            let expired = packet
+                .timeout
+                .timestamp()
+                .map_or(false, |timeout| timeout <= self.env.block.time);
+            let res = if expired {
+                let msg = IbcPacketTimeoutMsg::new(packet, Addr::unchecked(RELAYER));
+                self.transact(|chain| {
+                    let res = ibc_packet_timeout(chain.deps.as_mut(), chain.env.clone(), msg)
+                        .map_err(|err| err.to_string())?;
+                    chain.process_response(BRIDGE, res.messages, res.attributes, res.events, None)
+                })?
+            } else {
+                // the counterparty state is only changed by packets it acks with success
+                let mut remote = self.remote.clone();
+                let ack = match on_recv(&mut remote, &packet) {
+                    Ok(()) => {
+                        self.remote = remote;
+                        to_json_binary(&Ics20Ack::Result(b"1".into())).unwrap()
+                    }
+                    Err(err) => ack_fail(err),
+                };
+                let msg = IbcPacketAckMsg::new(
+                    IbcAcknowledgement::new(ack),
+                    packet,
+                    Addr::unchecked(RELAYER),
+                );
+                self.transact(|chain| {
+                    let res = ibc_packet_ack(chain.deps.as_mut(), chain.env.clone(), msg)
+                        .map_err(|err| err.to_string())?;
+                    chain.process_response(BRIDGE, res.messages, res.attributes, res.events, None)
+                })?
+            };
+            responses.push(res);
+        }
+        Ok(responses)
+    }
+
+    // like wasmd, a receive that fails is reverted and acked with an error, and the data of a reply overrides
+    // the ack of the packet. A receive that runs out of the block gas aborts and is not acked at all
+    fn receive_packet(&mut self, packet: IbcPacket) -> Option<Binary> {
+        let snapshot = self.snapshot();
+        self.gas_used = 0;
+        let msg = IbcPacketReceiveMsg::new(packet, Addr::unchecked(RELAYER));
+        let res = ibc_packet_receive(self.deps.as_mut(), self.env.clone(), msg).unwrap();
+        let ack = res.acknowledgement.clone();
+        match self.process_response(BRIDGE, res.messages, res.attributes, res.events, None) {
+            Ok(processed) => Some(processed.data.unwrap_or(ack)),
+            Err(err) => {
+                let aborted = self.gas_used > BLOCK_GAS_LIMIT;
+                self.restore(snapshot);
+                if aborted {
+                    None
+                } else {
+                    Some(ack_fail(err))
+                }
+            }
+        }
+    }
+
+    fn transact(
+        &mut self,
+        tx: impl FnOnce(&mut Self) -> Result<AppResponse, String>,
+    ) -> Result<AppResponse, String> {
+        let snapshot = self.snapshot();
+        self.gas_used = 0;
+        let res = tx(self);
+        if res.is_err() {
+            self.restore(snapshot);
+        }
+        res
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            storage: self
+                .deps
+                .storage
+                .range(None, None, Order::Ascending)
+                .collect(),
+            world: self.deps.querier.world.clone(),
+            remote: self.remote.clone(),
+            outbox: self.outbox.clone(),
+            inbox: self.inbox.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        let keys: Vec<Vec<u8>> = self
+            .deps
+            .storage
+            .range(None, None, Order::Ascending)
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.deps.storage.remove(&key);
+        }
+        for (key, value) in snapshot.storage {
+            self.deps.storage.set(&key, &value);
+        }
+        self.deps.querier.world = snapshot.world;
+        self.remote = snapshot.remote;
+        self.outbox = snapshot.outbox;
+        self.inbox = snapshot.inbox;
+    }
+
+    fn process_response(
+        &mut self,
+        contract: &str,
+        messages: Vec<SubMsg>,
+        attributes: Vec<Attribute>,
+        events: Vec<Event>,
+        data: Option<Binary>,
+    ) -> Result<AppResponse, String> {
+        let mut res = AppResponse {
+            attributes,
+            events,
+            data,
+        };
+        for sub_msg in messages {
+            let sub_res = self.execute_submsg(contract, sub_msg)?;
+            res.attributes.extend(sub_res.attributes);
+            res.events.extend(sub_res.events);
+            if sub_res.data.is_some() {
+                res.data = sub_res.data;
+            }
+        }
+        Ok(res)
+    }
+
+    fn process_bridge_response(&mut self, res: Response) -> Result<AppResponse, String> {
+        self.process_response(BRIDGE, res.messages, res.attributes, res.events, res.data)
+    }
+
+    // a failed submessage only reverts its own state changes, the reply decides whether the caller fails
+    fn execute_submsg(&mut self, contract: &str, sub_msg: SubMsg) -> Result<AppResponse, String> {
+        let snapshot = self.snapshot();
+        let gas_before = self.gas_used;
+        let mut res = self.dispatch(contract, sub_msg.msg);
+        // running out of the gas limit of a submessage is an error of that submessage
+        if let Some(gas_limit) = sub_msg.gas_limit {
+            if self.gas_used - gas_before > gas_limit {
+                self.gas_used = gas_before + gas_limit;
+                res = Err(format!("out of gas: limit {}", gas_limit));
+            }
+        }
+        // running out of the gas of the whole transaction aborts it, which no reply can catch
+        if self.gas_used > BLOCK_GAS_LIMIT {
+            return Err(format!("out of gas: block limit {}", BLOCK_GAS_LIMIT));
+        }
+        if res.is_err() {
+            self.restore(snapshot);
+        }
+        match (sub_msg.reply_on, res) {
+            (ReplyOn::Always | ReplyOn::Success, Ok(res)) => {
+                let result = SubMsgResult::Ok(SubMsgResponse {
+                    events: res.events.clone(),
+                    data: res.data,
+                });
+                let reply_res = self.reply(sub_msg.id, result)?;
+                Ok(AppResponse {
+                    attributes: [res.attributes, reply_res.attributes].concat(),
+                    events: [res.events, reply_res.events].concat(),
+                    data: reply_res.data,
+                })
+            }
+            (ReplyOn::Always | ReplyOn::Error, Err(err)) => {
+                self.reply(sub_msg.id, SubMsgResult::Err(err))
+            }
+            (_, Ok(res)) => Ok(AppResponse { data: None, ..res }),
+            (_, Err(err)) => Err(err),
+        }
+    }
+
+    fn reply(&mut self, id: u64, result: SubMsgResult) -> Result<AppResponse, String> {
+        let res = reply(self.deps.as_mut(), self.env.clone(), Reply { id, result })
+            .map_err(|err| err.to_string())?;
+        self.process_bridge_response(res)
+    }
+
+    fn dispatch(&mut self, sender: &str, msg: CosmosMsg) -> Result<AppResponse, String> {
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                self.transfer(sender, &to_address, &amount)?;
+                Ok(AppResponse::default())
+            }
+            CosmosMsg::Bank(BankMsg::Burn { amount }) => {
+                for coin in amount {
+                    self.world().burn(sender, &coin.denom, coin.amount)?;
+                }
+                Ok(AppResponse::default())
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => self.execute_wasm(sender, &contract_addr, msg, funds),
+            CosmosMsg::Ibc(IbcMsg::SendPacket {
+                channel_id,
+                data,
+                timeout,
+            }) => {
+                let channel = CHANNEL_INFO
+                    .load(&self.deps.storage, &channel_id)
+                    .map_err(|err| err.to_string())?;
+                self.sequence += 1;
+                // like the ibc module, the sequence of the packet is in the send_packet event
+                let event = Event::new("send_packet")
+                    .add_attribute("packet_sequence", self.sequence.to_string())
+                    .add_attribute("packet_src_channel", &channel_id);
+                self.outbox.push_back(IbcPacket::new(
+                    data,
+                    IbcEndpoint {
+                        port_id: CONTRACT_PORT.to_string(),
+                        channel_id,
+                    },
+                    channel.counterparty_endpoint,
+                    self.sequence,
+                    timeout,
+                ));
+                Ok(AppResponse {
+                    events: vec![event],
+                    ..AppResponse::default()
+                })
+            }
+            msg => Err(format!("unsupported msg {:?}", msg)),
+        }
+    }
+
+    fn transfer(&mut self, from: &str, to: &str, coins: &[Coin]) -> Result<(), String> {
+        for coin in coins {
+            self.world().burn(from, &coin.denom, coin.amount)?;
+            self.world().mint(to, &coin.denom, coin.amount);
+        }
+        Ok(())
+    }
+
+    fn execute_wasm(
+        &mut self,
+        sender: &str,
+        contract: &str,
+        msg: Binary,
+        funds: Vec<Coin>,
+    ) -> Result<AppResponse, String> {
+        self.transfer(sender, contract, &funds)?;
+        self.gas_used += self
+            .deps
+            .querier
+            .world
+            .gas_costs
+            .get(contract)
+            .copied()
+            .unwrap_or_default();
+        if contract == BRIDGE {
+            let msg: ExecuteMsg = from_json(&msg).map_err(|err| err.to_string())?;
+            let res = execute(
+                self.deps.as_mut(),
+                self.env.clone(),
+                mock_info(sender, &funds),
+                msg,
+            )
+            .map_err(|err| err.to_string())?;
+            return self.process_bridge_response(res);
+        }
+        if self.deps.querier.world.failing_contracts.contains(contract) {
+            return Err(format!("execution of {} failed", contract));
+        }
+        if self.deps.querier.world.cw20.contains_key(contract) {
+            let msg = from_json(&msg).map_err(|err| err.to_string())?;
+            return self.execute_cw20(sender, contract, msg);
+        }
+        match contract {
+            TOKEN_FACTORY => {
+                match from_json(&msg).map_err(|err| err.to_string())? {
+                    TokenFactoryMsg::MintTokens {
+                        denom,
+                        amount,
+                        mint_to_address,
+                    } => self.world().mint(&mint_to_address, &denom, amount),
+                    TokenFactoryMsg::BurnTokens {
+                        denom,
+                        amount,
+                        burn_from_address,
+                    } => self.world().burn(&burn_from_address, &denom, amount)?,
+                }
+                Ok(AppResponse::default())
+            }
+            CONVERTER => self.execute_converter(sender, msg, funds),
+            _ => {
+                self.world().contract_calls.push(ContractCall {
+                    contract: contract.to_string(),
+                    sender: sender.to_string(),
+                    msg,
+                    funds,
+                });
+                Ok(AppResponse::default())
+            }
+        }
+    }
+
+    fn execute_cw20(
+        &mut self,
+        sender: &str,
+        token: &str,
+        msg: Cw20ExecuteMsg,
+    ) -> Result<AppResponse, String> {
+        match msg {
+            Cw20ExecuteMsg::Transfer { recipient, amount } => {
+                self.world().cw20_burn(token, sender, amount)?;
+                self.world().cw20_mint(token, &recipient, amount)?;
+                Ok(AppResponse::default())
+            }
+            Cw20ExecuteMsg::Send {
+                contract,
+                amount,
+                msg,
+            } => {
+                self.world().cw20_burn(token, sender, amount)?;
+                self.world().cw20_mint(token, &contract, amount)?;
+                let hook = Cw20ReceiveMsg {
+                    sender: sender.to_string(),
+                    amount,
+                    msg,
+                }
+                .into_binary()
+                .map_err(|err| err.to_string())?;
+                self.execute_wasm(token, &contract, hook, vec![])
+            }
+            Cw20ExecuteMsg::Mint { recipient, amount } => {
+                self.world().cw20_mint(token, &recipient, amount)?;
+                Ok(AppResponse::default())
+            }
+            Cw20ExecuteMsg::Burn { amount } => {
+                self.world().cw20_burn(token, sender, amount)?;
+                Ok(AppResponse::default())
+            }
+            msg => Err(format!("unsupported cw20 msg {:?}", msg)),
+        }
+    }
+
+    // the converter has unlimited liquidity, it mints the asset it returns
+    fn execute_converter(
+        &mut self,
+        sender: &str,
+        msg: Binary,
+        funds: Vec<Coin>,
+    ) -> Result<AppResponse, String> {
+        let (receiver, source, amount, reverse) =
+            match from_json(&msg).map_err(|err| err.to_string())? {
+                ConverterMsg::Convert {} => {
+                    let coin = funds.first().ok_or("no funds to convert")?;
+                    let source = AssetInfo::NativeToken {
+                        denom: coin.denom.clone(),
+                    };
+                    (sender.to_string(), source, coin.amount, false)
+                }
+                ConverterMsg::ConvertReverse { from_asset } => {
+                    let coin = funds.first().ok_or("no funds to convert")?;
+                    (sender.to_string(), from_asset, coin.amount, true)
+                }
+                ConverterMsg::Receive(wrapper) => {
+                    match from_json(&wrapper.msg).map_err(|err| err.to_string())? {
+                        ConverterHookMsg::Convert {} => {
+                            let source = AssetInfo::Token {
+                                contract_addr: Addr::unchecked(sender),
+                            };
+                            (wrapper.sender, source, wrapper.amount, false)
+                        }
+                        ConverterHookMsg::ConvertReverse { from } => {
+                            (wrapper.sender, from, wrapper.amount, true)
+                        }
+                    }
+                }
+            };
+        let token_ratio = self
+            .deps
+            .querier
+            .world
+            .converter
+            .get(&source.to_string())
+            .cloned()
+            .ok_or("Converter info not found")?;
+        let (asset, amount) = if reverse {
+            let amount = amount
+                .checked_div_decimal(token_ratio.ratio)
+                .map_err(|err| err.to_string())?;
+            (source, amount)
+        } else {
+            (token_ratio.info, amount * token_ratio.ratio)
+        };
+        match asset {
+            AssetInfo::NativeToken { denom } => self.world().mint(&receiver, &denom, amount),
+            AssetInfo::Token { contract_addr } => {
+                self.world()
+                    .cw20_mint(contract_addr.as_str(), &receiver, amount)?
+            }
+        }
+        Ok(AppResponse::default())
+    }
+}
@@ -1,4 +1,5 @@
 mod ibc_hooks_test;
 mod ibc_tests;
 mod integration_tests;
+mod mock_chain;
 mod test_helpers;
@@ -1,16 +1,17 @@
-use crate::contract::instantiate;
+use crate::contract::{execute, instantiate};
 use crate::ibc::{ibc_channel_connect, ibc_channel_open, ICS20_ORDERING, ICS20_VERSION};
 use cw20_ics20_msg::state::ChannelInfo;
 
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
 
 use cosmwasm_std::{
-    Api, Binary, DepsMut, IbcChannel, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint,
-    OwnedDeps,
+    Api, Binary, DepsMut, Env, IbcChannel, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint,
+    OwnedDeps, Response,
 };
 use cosmwasm_testing_util::mock::MockApi;
 
-use crate::msg::{AllowMsg, InitMsg};
+use crate::msg::{AllowMsg, ExecuteMsg, InitMsg};
+use crate::state::DEFAULT_TIMELOCK_DELAY;
 
 pub const DEFAULT_TIMEOUT: u64 = 3600; // 1 hour,
 pub const CONTRACT_PORT: &str = "wasm.cosmos2contract"; // wasm.MOCK_CONTRACT_ADDR
@@ -93,6 +94,18 @@ pub fn setup(
     deps
 }
 
+// executes a timelocked operation as soon as its delay has passed
+pub fn execute_timelock_after_delay(deps: DepsMut, mut env: Env, id: u64) -> Response {
+    env.block.time = env.block.time.plus_seconds(DEFAULT_TIMELOCK_DELAY);
+    execute(
+        deps,
+        env,
+        mock_info("anyone", &[]),
+        ExecuteMsg::ExecuteTimelock { id },
+    )
+    .unwrap()
+}
+
 use anybuf::{Anybuf, Bufany};
 
 #[test]
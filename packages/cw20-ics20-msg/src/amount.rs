@@ -143,12 +143,38 @@ fn mul_ratio_decimal(amount: Uint128, ratio: Decimal) -> StdResult<Uint128> {
         .checked_mul(ratio)
         .map_err(|err| StdError::generic_err(err.to_string()))
         .map(|coeff| amount * coeff)?;
-    if result.is_zero() {
+    assert_non_zero_conversion(result)
+}
+
+fn assert_non_zero_conversion(amount: Uint128) -> StdResult<Uint128> {
+    if amount.is_zero() {
         return Err(StdError::generic_err(
             "Converting decimals results in a zero amount. Revert this transaction!",
         ));
     }
-    Ok(result)
+    Ok(amount)
+}
+
+/// Converts `amount` from `from_decimals` to `to_decimals` with integer arithmetic.
+/// Returns the converted amount and the remainder of `amount`, in `from_decimals`, that cannot be represented
+/// in `to_decimals`. Unlike the `convert_*` functions, a zero result is not an error
+pub fn split_decimals(
+    amount: Uint128,
+    from_decimals: u8,
+    to_decimals: u8,
+) -> StdResult<(Uint128, Uint128)> {
+    let factor = |diff: u8| {
+        10u128
+            .checked_pow(diff as u32)
+            .map(Uint128::from)
+            .ok_or_else(|| StdError::generic_err("Decimals difference is too large"))
+    };
+    if to_decimals >= from_decimals {
+        let converted = amount.checked_mul(factor(to_decimals - from_decimals)?)?;
+        return Ok((converted, Uint128::zero()));
+    }
+    let factor = factor(from_decimals - to_decimals)?;
+    Ok((amount.checked_div(factor)?, amount.checked_rem(factor)?))
 }
 
 pub fn convert_remote_to_local(
@@ -179,10 +205,31 @@ pub fn convert_local_to_remote(
     )
 }
 
+/// Returns the local amount and the remote dust that is truncated by the conversion
+pub fn convert_remote_to_local_exact(
+    amount: Uint128,
+    remote_decimals: u8,
+    local_decimals: u8,
+) -> StdResult<(Uint128, Uint128)> {
+    let (local_amount, dust) = split_decimals(amount, remote_decimals, local_decimals)?;
+    Ok((assert_non_zero_conversion(local_amount)?, dust))
+}
+
+/// Returns the remote amount and the local dust that is truncated by the conversion
+pub fn convert_local_to_remote_exact(
+    amount: Uint128,
+    remote_decimals: u8,
+    local_decimals: u8,
+) -> StdResult<(Uint128, Uint128)> {
+    let (remote_amount, dust) = split_decimals(amount, local_decimals, remote_decimals)?;
+    Ok((assert_non_zero_conversion(remote_amount)?, dust))
+}
+
 #[cfg(test)]
 mod tests {
 
     use cosmwasm_std::{testing::mock_dependencies, Addr};
+    use proptest::prelude::*;
 
     use super::*;
 
@@ -194,6 +241,28 @@ mod tests {
         assert_eq!(new_amount, Uint128::from(1u128))
     }
 
+    #[test]
+    pub fn test_convert_exact() {
+        assert_eq!(
+            convert_remote_to_local_exact(Uint128::from(1000000000123u128), 18, 6).unwrap(),
+            (Uint128::from(1u128), Uint128::from(123u128))
+        );
+        assert_eq!(
+            convert_local_to_remote_exact(Uint128::from(10u128), 18, 6).unwrap(),
+            (Uint128::from(10000000000000u128), Uint128::zero())
+        );
+        assert_eq!(
+            convert_local_to_remote_exact(Uint128::from(1000000000123u128), 6, 18).unwrap(),
+            (Uint128::from(1u128), Uint128::from(123u128))
+        );
+        // dust only amounts cannot be converted
+        convert_remote_to_local_exact(Uint128::from(123u128), 18, 6).unwrap_err();
+        assert_eq!(
+            split_decimals(Uint128::from(123u128), 18, 6).unwrap(),
+            (Uint128::zero(), Uint128::from(123u128))
+        );
+    }
+
     #[test]
     pub fn test_into_asset_info() {
         let deps = mock_dependencies();
@@ -225,4 +294,70 @@ mod tests {
             Amount::cw20(1u128.into(), Addr::unchecked("addr"))
         )
     }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip_conversion_never_creates_value(
+            amount in any::<u64>(),
+            remote_decimals in 0u8..=18,
+            local_decimals in 0u8..=18,
+        ) {
+            let amount = Uint128::from(amount);
+            // conversions truncated to zero are rejected, so only the successful ones are compared
+            let remote = convert_remote_to_local(amount, remote_decimals, local_decimals)
+                .and_then(|local| {
+                    convert_local_to_remote(local, remote_decimals, local_decimals)
+                });
+            if let Ok(remote) = remote {
+                prop_assert!(remote <= amount);
+            }
+            let local = convert_local_to_remote(amount, remote_decimals, local_decimals)
+                .and_then(|remote| {
+                    convert_remote_to_local(remote, remote_decimals, local_decimals)
+                });
+            if let Ok(local) = local {
+                prop_assert!(local <= amount);
+            }
+        }
+
+        #[test]
+        fn prop_exact_conversion_matches_conversion(
+            amount in any::<u64>(),
+            remote_decimals in 0u8..=18,
+            local_decimals in 0u8..=18,
+        ) {
+            let amount = Uint128::from(amount);
+            prop_assert_eq!(
+                convert_remote_to_local_exact(amount, remote_decimals, local_decimals)
+                    .map(|(local, _)| local)
+                    .ok(),
+                convert_remote_to_local(amount, remote_decimals, local_decimals).ok()
+            );
+            prop_assert_eq!(
+                convert_local_to_remote_exact(amount, remote_decimals, local_decimals)
+                    .map(|(remote, _)| remote)
+                    .ok(),
+                convert_local_to_remote(amount, remote_decimals, local_decimals).ok()
+            );
+        }
+
+        #[test]
+        fn prop_split_decimals_conserves_value(
+            amount in any::<u64>(),
+            from_decimals in 0u8..=18,
+            to_decimals in 0u8..=18,
+        ) {
+            let amount = Uint128::from(amount);
+            let (converted, dust) = split_decimals(amount, from_decimals, to_decimals).unwrap();
+            if to_decimals >= from_decimals {
+                let factor = Uint128::from(10u128.pow((to_decimals - from_decimals) as u32));
+                prop_assert_eq!(converted, amount * factor);
+                prop_assert!(dust.is_zero());
+            } else {
+                let factor = Uint128::from(10u128.pow((from_decimals - to_decimals) as u32));
+                prop_assert_eq!(converted * factor + dust, amount);
+                prop_assert!(dust < factor);
+            }
+        }
+    }
 }
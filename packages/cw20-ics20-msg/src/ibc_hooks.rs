@@ -6,6 +6,9 @@ use cosmwasm_std::{Api, Binary, StdError, StdResult};
 #[cw_serde]
 pub enum HookMethods {
     UniversalSwap,
+    Transfer,
+    ContractCall,
+    TransferToRemote,
 }
 
 #[cw_serde]
@@ -49,6 +52,10 @@ impl IbcHooksUniversalSwap {
             .string(4)
             .ok_or_else(|| StdError::generic_err("Error on deserialize destination_denom"))?;
 
+        if destination_denom.is_empty() {
+            return Err(StdError::generic_err("Require destination denom in memo"));
+        }
+
         let bridge_receiver = to_orai_bridge_address(&receiver).map_err(|err| {
             StdError::generic_err(format!(
                 "Error on convert to orai bridge address: {:?}",
@@ -66,6 +73,103 @@ impl IbcHooksUniversalSwap {
     }
 }
 
+fn deserialize_args(value: &Binary) -> StdResult<Bufany> {
+    Bufany::deserialize(value)
+        .map_err(|err| StdError::generic_err(format!("Error on deserialize: {:?}", err)))
+}
+
+fn humanize_field(
+    api: &dyn Api,
+    deserialized: &Bufany,
+    field: u32,
+    name: &str,
+) -> StdResult<String> {
+    let canonical = deserialized
+        .bytes(field)
+        .ok_or_else(|| StdError::generic_err(format!("Error on deserialize {}", name)))?;
+    Ok(api.addr_humanize(&canonical.into())?.to_string())
+}
+
+#[cw_serde]
+pub struct IbcHooksTransfer {
+    pub receiver: String, // receiver on Oraichain
+}
+
+impl IbcHooksTransfer {
+    pub fn from_json(api: &dyn Api, value: &Binary) -> StdResult<Self> {
+        let deserialized = deserialize_args(value)?;
+        let receiver = humanize_field(api, &deserialized, 1, "receiver")?;
+        Ok(Self { receiver })
+    }
+}
+
+#[cw_serde]
+pub struct IbcHooksContractCall {
+    pub contract: String, // contract on Oraichain, must be allowlisted
+    pub msg: Binary,      // execute msg of the contract
+}
+
+impl IbcHooksContractCall {
+    pub fn from_json(api: &dyn Api, value: &Binary) -> StdResult<Self> {
+        let deserialized = deserialize_args(value)?;
+        let contract = humanize_field(api, &deserialized, 1, "contract")?;
+        let msg = deserialized
+            .bytes(2)
+            .ok_or_else(|| StdError::generic_err("Error on deserialize msg"))?;
+
+        if msg.is_empty() {
+            return Err(StdError::generic_err("Require contract msg in memo"));
+        }
+
+        Ok(Self {
+            contract,
+            msg: Binary::from(msg),
+        })
+    }
+}
+
+#[cw_serde]
+pub struct IbcHooksTransferToRemote {
+    pub local_channel_id: String,
+    pub remote_address: String,
+    pub remote_denom: String,
+    pub timeout: Option<u64>, // 0 means the default timeout
+    pub memo: Option<String>, // empty means no memo
+}
+
+impl IbcHooksTransferToRemote {
+    pub fn from_json(value: &Binary) -> StdResult<Self> {
+        let deserialized = deserialize_args(value)?;
+        let string_field = |field: u32, name: &str| {
+            deserialized
+                .string(field)
+                .ok_or_else(|| StdError::generic_err(format!("Error on deserialize {}", name)))
+        };
+
+        let local_channel_id = string_field(1, "local_channel_id")?;
+        let remote_address = string_field(2, "remote_address")?;
+        let remote_denom = string_field(3, "remote_denom")?;
+        if local_channel_id.is_empty() || remote_address.is_empty() || remote_denom.is_empty() {
+            return Err(StdError::generic_err(
+                "Require local channel, remote address and remote denom in memo",
+            ));
+        }
+
+        let timeout = deserialized
+            .uint64(4)
+            .ok_or_else(|| StdError::generic_err("Error on deserialize timeout"))?;
+        let memo = string_field(5, "memo")?;
+
+        Ok(Self {
+            local_channel_id,
+            remote_address,
+            remote_denom,
+            timeout: Some(timeout).filter(|timeout| *timeout > 0),
+            memo: Some(memo).filter(|memo| !memo.is_empty()),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -73,7 +177,7 @@ mod tests {
     use cosmwasm_std::{Api, Binary, StdError};
     use cosmwasm_testing_util::mock::MockApi;
 
-    use crate::ibc_hooks::IbcHooksUniversalSwap;
+    use crate::ibc_hooks::{IbcHooksContractCall, IbcHooksTransferToRemote, IbcHooksUniversalSwap};
 
     #[test]
     fn test_parse_ibc_hooks_deserialize_error() {
@@ -184,4 +288,71 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_parse_ibc_hooks_contract_call() {
+        let mock_api = MockApi::default();
+        let contract = mock_api
+            .addr_canonicalize("orai1asz5wl5c2xt8y5kyp9r04v54zh77pq90fhchjq")
+            .unwrap();
+
+        let memo = Binary::from(
+            Anybuf::new()
+                .append_bytes(1, contract.as_slice())
+                .as_bytes(),
+        );
+        assert_eq!(
+            IbcHooksContractCall::from_json(&mock_api, &memo).unwrap_err(),
+            StdError::generic_err("Require contract msg in memo")
+        );
+
+        let memo = Binary::from(
+            Anybuf::new()
+                .append_bytes(1, contract.as_slice())
+                .append_bytes(2, br#"{"ping":{}}"#)
+                .as_bytes(),
+        );
+        assert_eq!(
+            IbcHooksContractCall::from_json(&mock_api, &memo).unwrap(),
+            IbcHooksContractCall {
+                contract: "orai1asz5wl5c2xt8y5kyp9r04v54zh77pq90fhchjq".to_string(),
+                msg: Binary::from(br#"{"ping":{}}"#),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ibc_hooks_transfer_to_remote() {
+        let memo = Binary::from(
+            Anybuf::new()
+                .append_string(1, "channel-29")
+                .append_string(2, "")
+                .append_string(3, "oraib0x55d398326f99059fF775485246999027B3197955")
+                .as_bytes(),
+        );
+        assert_eq!(
+            IbcHooksTransferToRemote::from_json(&memo).unwrap_err(),
+            StdError::generic_err("Require local channel, remote address and remote denom in memo")
+        );
+
+        let memo = Binary::from(
+            Anybuf::new()
+                .append_string(1, "channel-29")
+                .append_string(2, "oraib1asz5wl5c2xt8y5kyp9r04v54zh77pq907kumrr")
+                .append_string(3, "oraib0x55d398326f99059fF775485246999027B3197955")
+                .append_uint64(4, 0)
+                .append_string(5, "")
+                .as_bytes(),
+        );
+        assert_eq!(
+            IbcHooksTransferToRemote::from_json(&memo).unwrap(),
+            IbcHooksTransferToRemote {
+                local_channel_id: "channel-29".to_string(),
+                remote_address: "oraib1asz5wl5c2xt8y5kyp9r04v54zh77pq907kumrr".to_string(),
+                remote_denom: "oraib0x55d398326f99059fF775485246999027B3197955".to_string(),
+                timeout: None,
+                memo: None,
+            }
+        );
+    }
 }
@@ -6,5 +6,6 @@ pub mod amount;
 pub mod converter;
 pub mod helper;
 pub mod ibc_hooks;
+pub mod memo;
 pub mod msg;
 pub mod state;
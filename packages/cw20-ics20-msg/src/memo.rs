@@ -0,0 +1,122 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, Binary, StdError, StdResult, Uint128};
+
+/// Latest version of the json memo schema
+pub const MEMO_VERSION: u32 = 1;
+
+/// Json memo of an incoming transfer, eg: {"oraichain":{"action":"transfer","receiver":"orai1..."}}
+#[cw_serde]
+pub struct Memo {
+    pub oraichain: OraichainMemo,
+}
+
+#[cw_serde]
+pub struct OraichainMemo {
+    /// version of the memo schema. If not specified, the latest version is used
+    pub version: Option<u32>,
+    pub action: MemoAction,
+    /// receiver on Oraichain. If not specified, the receiver of the packet is used
+    pub receiver: Option<String>,
+    /// minimum amount to deliver on Oraichain after fees, in local decimals
+    pub min_out: Option<Uint128>,
+    /// protobuf memo forwarded to the universal swap entrypoint
+    pub swap_memo: Option<String>,
+    /// execute msg of the receiver contract for the contract call action. Native funds are attached
+    /// to it, cw20 funds are sent with it as the hook payload. If the call fails the packet is acked
+    /// with an error and the sender is refunded
+    pub msg: Option<Binary>,
+}
+
+#[cw_serde]
+pub enum MemoAction {
+    Transfer,
+    UniversalSwap,
+    ContractCall,
+}
+
+impl OraichainMemo {
+    /// Parses a json memo. Returns none for memos in other formats, eg: an address or a protobuf memo
+    pub fn parse(memo: &str) -> Option<StdResult<Self>> {
+        if !memo.trim_start().starts_with('{') {
+            return None;
+        }
+        Some(from_json::<Memo>(memo.as_bytes()).and_then(|memo| memo.oraichain.validate()))
+    }
+
+    fn validate(self) -> StdResult<Self> {
+        let version = self.version.unwrap_or(MEMO_VERSION);
+        if version == 0 || version > MEMO_VERSION {
+            return Err(StdError::generic_err(format!(
+                "Unsupported memo version {}",
+                version
+            )));
+        }
+        if self.action == MemoAction::UniversalSwap
+            && self.swap_memo.as_deref().unwrap_or_default().is_empty()
+        {
+            return Err(StdError::generic_err(
+                "Require swap memo for universal swap action",
+            ));
+        }
+        if self.action == MemoAction::ContractCall
+            && self.msg.as_ref().map_or(true, |msg| msg.is_empty())
+        {
+            return Err(StdError::generic_err(
+                "Require msg for contract call action",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{StdError, Uint128};
+
+    use super::{MemoAction, OraichainMemo};
+
+    #[test]
+    fn test_parse_memo() {
+        // other formats are left to the caller
+        assert!(OraichainMemo::parse("orai1asz5wl5c2xt8y5kyp9r04v54zh77pq90fhchjq").is_none());
+
+        let memo = OraichainMemo::parse(
+            r#"{"oraichain":{"action":"transfer","receiver":"orai1receiver","min_out":"100"}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            memo,
+            OraichainMemo {
+                version: None,
+                action: MemoAction::Transfer,
+                receiver: Some("orai1receiver".to_string()),
+                min_out: Some(Uint128::from(100u128)),
+                swap_memo: None,
+                msg: None,
+            }
+        );
+
+        assert_eq!(
+            OraichainMemo::parse(r#"{"oraichain":{"version":2,"action":"transfer"}}"#)
+                .unwrap()
+                .unwrap_err(),
+            StdError::generic_err("Unsupported memo version 2")
+        );
+        assert_eq!(
+            OraichainMemo::parse(r#"{"oraichain":{"action":"universal_swap"}}"#)
+                .unwrap()
+                .unwrap_err(),
+            StdError::generic_err("Require swap memo for universal swap action")
+        );
+        assert_eq!(
+            OraichainMemo::parse(r#"{"oraichain":{"action":"contract_call"}}"#)
+                .unwrap()
+                .unwrap_err(),
+            StdError::generic_err("Require msg for contract call action")
+        );
+        OraichainMemo::parse(r#"{"forward":{"receiver":"cosmos1receiver"}}"#)
+            .unwrap()
+            .unwrap_err();
+    }
+}
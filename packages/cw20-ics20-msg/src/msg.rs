@@ -3,19 +3,43 @@ use cosmwasm_std::{Binary, IbcEndpoint, SubMsg, Uint128};
 use oraiswap::asset::AssetInfo;
 
 use crate::amount::Amount;
+use crate::state::{DustPolicy, MappingStatus};
 
 /// This is the message we accept via Receive
 #[cw_serde]
 pub struct TransferBackMsg {
-    /// the local ibc endpoint you want to send tokens back on
+    /// the local ibc endpoint you want to send tokens back on. Can be left empty when remote_prefix is set
+    #[serde(default)]
     pub local_channel_id: String,
     pub remote_address: String,
     /// remote denom so that we know what denom to filter when we query based on the asset info. Most likely be: oraib0x... or eth0x...
+    /// Can be left empty when remote_prefix is set
+    #[serde(default)]
     pub remote_denom: String,
     /// How long the packet lives in seconds. If not specified, use default_timeout
     pub timeout: Option<u64>,
     /// metadata of the transfer to suit the new fungible token transfer
     pub memo: Option<String>,
+    /// destination prefix of the remote denom, eg: oraib or eth. If set, the contract picks the mapping
+    /// whose channel has enough outstanding balance to cover the amount
+    #[serde(default)]
+    pub remote_prefix: Option<String>,
+    /// contract that receives an IbcLifecycleComplete execute msg once the packet is acknowledged or timed out.
+    /// It must be the sender of the transfer
+    #[serde(default)]
+    pub callback: Option<String>,
+}
+
+/// Execute msg sent to the callback contract of a transfer
+#[cw_serde]
+pub enum IbcLifecycleCallbackMsg {
+    IbcLifecycleComplete {
+        channel_id: String,
+        sequence: u64,
+        success: bool,
+        /// amount refunded to the sender in local decimals, zero on success
+        refunded_amount: Uint128,
+    },
 }
 
 /// This is the message we accept via Receive
@@ -61,7 +85,37 @@ pub struct UpdatePairMsg {
     pub local_asset_info: AssetInfo,
     pub remote_decimals: u8,
     pub local_asset_info_decimals: u8,
+    /// fields left empty keep the value of an existing pair
     pub is_mint_burn: Option<bool>,
+    pub supply_cap: Option<UpdateValue<Uint128>>,
+    /// changing the decimals of a pair with outstanding balance is rejected, unless only the remote decimals change
+    /// and this is set, in which case the channel balance is rescaled to the new remote decimals
+    pub rescale_outstanding: Option<bool>,
+    /// defaults to truncate
+    pub dust_policy: Option<DustPolicy>,
+    /// defaults to active
+    pub status: Option<MappingStatus>,
+    /// bounds of a single transfer, in remote decimals
+    pub min_amount: Option<UpdateValue<Uint128>>,
+    pub max_amount: Option<UpdateValue<Uint128>>,
+    /// why the pair is updated, stored in the audit log
+    pub reason: String,
+}
+
+/// Update of an optional field, eg: {"set":100} or {"unset":{}}
+#[cw_serde]
+pub enum UpdateValue<T> {
+    Set(T),
+    Unset {},
+}
+
+impl<T> UpdateValue<T> {
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            UpdateValue::Set(value) => Some(value),
+            UpdateValue::Unset {} => None,
+        }
+    }
 }
 
 #[cw_serde]
@@ -69,4 +123,6 @@ pub struct DeletePairMsg {
     pub local_channel_id: String,
     /// native denom of the remote chain. Eg: orai
     pub denom: String,
+    /// why the pair is deleted, stored in the audit log
+    pub reason: String,
 }
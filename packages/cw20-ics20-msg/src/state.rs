@@ -43,6 +43,57 @@ pub struct MappingMetadata {
     pub asset_info_decimals: u8,
     #[serde(default)]
     pub is_mint_burn: bool,
+    /// hard cap on the supply minted for the local asset, in local decimals. Only used by mint-burn mappings
+    #[serde(default)]
+    pub supply_cap: Option<Uint128>,
+    #[serde(default)]
+    pub dust_policy: DustPolicy,
+    #[serde(default)]
+    pub status: MappingStatus,
+    /// smallest amount of a single transfer in either direction, in remote decimals
+    #[serde(default)]
+    pub min_amount: Option<Uint128>,
+    /// largest amount of a single transfer in either direction, in remote decimals
+    #[serde(default)]
+    pub max_amount: Option<Uint128>,
+}
+
+/// Which directions of a mapping are open. Refunds of packets that are already in flight are always processed
+#[cw_serde]
+#[derive(Default)]
+pub enum MappingStatus {
+    #[default]
+    Active,
+    /// only transfers from the remote chain are accepted
+    ReceiveOnly,
+    /// only transfers back to the remote chain are accepted
+    SendOnly,
+    Disabled,
+}
+
+impl MappingStatus {
+    pub fn can_receive(&self) -> bool {
+        matches!(self, MappingStatus::Active | MappingStatus::ReceiveOnly)
+    }
+
+    pub fn can_send(&self) -> bool {
+        matches!(self, MappingStatus::Active | MappingStatus::SendOnly)
+    }
+}
+
+/// What to do with the part of a transfer that is lost when converting between remote and local decimals
+#[cw_serde]
+#[derive(Default)]
+pub enum DustPolicy {
+    /// drop the dust, which stays in the contract
+    #[default]
+    Truncate,
+    /// reject outbound transfers and fail inbound packets that cannot be converted exactly
+    Reject,
+    /// return the outbound dust to the sender. Inbound dust cannot be returned without failing the packet, so it is accumulated
+    Refund,
+    /// accumulate the dust of both directions so the admin can sweep it later
+    Accumulate,
 }
 
 #[cw_serde]